@@ -21,6 +21,9 @@ rm -rf ${PREFIX}
 mkdir -p ${PREFIX}
 mkdir -p ${PREFIX}/lib
 cp target/release/fuzzer ${PREFIX}
+cp target/release/parmesan-tmin ${PREFIX}
+cp target/release/parmesan-cmin ${PREFIX}
+cp target/release/parmesan-targets ${PREFIX}
 cp target/release/*.a ${PREFIX}/lib
 cp target/release/log_reader ${PREFIX}
 
@@ -0,0 +1,17 @@
+// The cmps of a run of the fast binary, in shared memory: runtime_fast writes
+// them, fuzzer/src/cond_stmt/cmp_log.rs sets the mode and reads them.
+use crate::{cond_stmt_base::CondStmtBase, config};
+
+pub const CMP_LOG_OFF: u32 = 0;
+// Every cmp is appended, up to MAX_COND_ORDER times per cmpid and context
+pub const CMP_LOG_ALL: u32 = 1;
+// Only the entries set by the fuzzer get the operands of the run
+pub const CMP_LOG_WATCH: u32 = 2;
+
+#[repr(C)] // It should be repr C since we will used it in shared memory
+pub struct CmpLog {
+    pub mode: u32,
+    pub len: u32,
+    // lb1 is the outcome of the cmp, u32::MAX if it was not reached
+    pub entries: [CondStmtBase; config::CMP_LOG_MAX_ENTRIES],
+}
@@ -60,8 +60,9 @@ impl CondStmtBase {
                 && (self.op & COND_BASIC_MASK) <= COND_ICMP_SLE_OP)
     }
 
+    // Not a cmp, but a whole input for the AFL-style or the custom mutator
     pub fn is_afl(&self) -> bool {
-        self.op == COND_AFL_OP
+        self.op == COND_AFL_OP || self.op == COND_CUSTOM_OP
     }
 
     pub fn may_be_bool(&self) -> bool {
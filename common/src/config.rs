@@ -26,6 +26,29 @@ pub const MAX_INVARIABLE_NUM: usize = 16;
 pub const MAX_NUM_MINIMAL_OPTIMA_ALL: usize = 28;
 // based the bit bucket: [1], [2], [3], [4, 7], [8, 15], [16, 31], [32, 127], [128, infinity]
 pub const MAX_COND_ORDER: u32 = 16;
+// cmps a run of the fast binary logs without the track binary
+pub const CMP_LOG_MAX_ENTRIES: usize = 4096;
+
+// fuzz_main.rs
+pub const CHECKPOINT_INTERVAL: u64 = 600; // seconds
+pub const CFG_DUMP_INTERVAL: u64 = 600; // seconds
+// Schedules whose ranks change while the conds wait, e.g. bandit
+pub const RERANK_INTERVAL: u64 = 30; // seconds
+
+// scheduler.rs
+pub const SCHEDULE_BATCH_SIZE: usize = 4;
+pub const SCHEDULE_IDLE_MS: u64 = 100;
+
+// depot/schedule.rs
+// Time to exploitation of the annealing schedule, the temperature is 0.05 then
+pub const ANNEALING_EXPLOIT_TIME: u64 = 2700; // seconds
+pub const ANNEALING_MIN_ENERGY: f64 = 0.05;
+pub const BANDIT_EXPLORATION: f64 = 1.0;
+
+// triage
+pub const TRIAGE_INTERVAL: u64 = 5; // seconds
+pub const TIME_LIMIT_TRIAGE: u64 = 30;
+pub const TRIAGE_STACK_DEPTH: usize = 5;
 
 // ************ Mutation ****************
 // SEARCH
@@ -37,9 +60,49 @@ pub const MAX_RANDOM_SAMPLE_NUM: usize = 10;
 pub const GD_MOMENTUM_BETA: f64 = 0.0;
 pub const GD_ESCAPE_RATIO: f64 = 1.0;
 pub const BONUS_EXEC_NUM: usize = 66;
+// SmtSearch (with the smt feature): runs to fit the operand, cmps kept on
+// the path prefix, inputs tried and the time of a query
+pub const SMT_NUM_SAMPLES: usize = 8;
+pub const SMT_MAX_PREFIX: usize = 32;
+pub const SMT_MAX_SOLUTIONS: usize = 4;
+pub const SMT_TIMEOUT_MS: u64 = 2000;
+// GdSearch with --joint: conds before the target on the path that read its
+// bytes and are kept satisfied
+pub const JOINT_MAX_PREFIX_CONDS: usize = 8;
+// Occurrences of an operand in the input that I2sSearch replaces
+pub const I2S_MAX_MATCHES: usize = 16;
 
 // AFL
 pub const MUTATE_ARITH_MAX: u32 = 30;
 pub const RANDOM_LEN_NUM: usize = 30;
 pub const MAX_HAVOC_FLIP_TIMES: usize = 45; // for all bytes
 pub const MAX_SPLICE_TIMES: usize = 45;
+// Adaptive havoc operators: executions an operator is assumed to have used
+// for one new path before it has been tried, and the uniform share of the
+// probabilities.
+pub const HAVOC_OP_PRIOR_EXECS: f64 = 100.0;
+pub const HAVOC_OP_EXPLORATION: f64 = 0.1;
+// Custom mutator
+pub const MAX_CUSTOM_FUZZ_TIMES: usize = 90;
+pub const MAX_CUSTOM_TRIM_STEPS: usize = 256;
+
+// Checksum checks: tainted bytes the computed value needs at least, and the
+// checks repaired on every input
+pub const MIN_CHECKSUM_SPAN: usize = 16;
+pub const MAX_CHECKSUMS_PER_INPUT: usize = 4;
+
+// grammar
+pub const GRAMMAR_NUM_SEEDS: usize = 100;
+// Deeper nodes only take the alternatives with the fewest nodes
+pub const GRAMMAR_MAX_DEPTH: usize = 12;
+pub const GRAMMAR_MAX_STACKING: usize = 4;
+// Parsed trees kept in memory
+pub const GRAMMAR_TREE_CACHE_SIZE: usize = 1024;
+
+// depot/dict.rs
+pub const MAX_DICT_TOKEN_LEN: usize = 128;
+pub const MIN_AUTO_TOKEN_LEN: usize = 2;
+pub const MAX_AUTO_TOKEN_LEN: usize = 32;
+pub const MAX_AUTO_DICT_SIZE: usize = 1024;
+// Tokens tried at the offsets of a cond by OneByteFuzz and ExploitFuzz
+pub const MAX_DICT_TOKENS_TRY: usize = 256;
@@ -6,6 +6,7 @@ pub static ANGORA_BIN_DIR: &str = "ANGORA_BIN_DIR";
 // executor.rs
 pub static TRACK_OUTPUT_VAR: &str = "ANGORA_TRACK_OUTPUT";
 pub static COND_STMT_ENV_VAR: &str = "ANGORA_COND_STMT_SHM_ID";
+pub static CMP_LOG_ENV_VAR: &str = "ANGORA_CMP_LOG_SHM_ID";
 pub static BRANCHES_SHM_ENV_VAR: &str = "ANGORA_BRANCHES_SHM_ID";
 pub static LD_LIBRARY_PATH_VAR: &str = "LD_LIBRARY_PATH";
 pub static ASAN_OPTIONS_VAR: &str = "ASAN_OPTIONS";
@@ -15,12 +16,43 @@ pub static ASAN_OPTIONS_CONTENT: &str =
 pub const MSAN_ERROR_CODE: i32 = 86;
 pub static MSAN_OPTIONS_CONTENT: &str =
     "exit_code=86:symbolize=0:abort_on_error=1:allocator_may_return_null=1:msan_track_origins=0";
+pub static UBSAN_OPTIONS_VAR: &str = "UBSAN_OPTIONS";
+pub const UBSAN_ERROR_CODE: i32 = 87;
+pub static UBSAN_OPTIONS_CONTENT: &str =
+    "halt_on_error=1:exitcode=87:symbolize=0:print_stacktrace=0";
 pub static DFSAN_OPTIONS_VAR: &str = "DFSAN_OPTIONS";
 
+// triage
+pub static ASAN_TRIAGE_OPTIONS_CONTENT: &str =
+    "abort_on_error=1:detect_leaks=0:symbolize=1:allocator_may_return_null=1";
+pub static MSAN_TRIAGE_OPTIONS_CONTENT: &str =
+    "exit_code=86:symbolize=1:abort_on_error=1:allocator_may_return_null=1";
+pub static UBSAN_TRIAGE_OPTIONS_CONTENT: &str =
+    "halt_on_error=1:abort_on_error=1:print_stacktrace=1:symbolize=1";
+pub static TRIAGE_DIR: &str = "triage";
+pub static TRIAGE_INDEX_FILE: &str = "index.json";
+pub static TRIAGE_REPORT_FILE: &str = "report.txt";
+pub static TRIAGE_REPRO_FILE: &str = "repro";
+
 // depot.rs
 pub static CRASHES_DIR: &str = "crashes";
 pub static HANGS_DIR: &str = "hangs";
 pub static INPUTS_DIR: &str = "queue";
+pub static TREES_DIR: &str = "trees";
+// depot/sync.rs, the tracked conds of the inputs, for the peers
+pub static CONDS_DIR: &str = "conds";
+// only used by the tools
+pub static SEEDS_DIR: &str = "seeds";
+// depot/dict.rs, the learned tokens in AFL dictionary format
+pub static AUTO_DICT_FILE: &str = "auto_dict";
+
+// checkpoint.rs
+pub static CHECKPOINT_DIR: &str = "checkpoint";
+pub static CHECKPOINT_STATE_FILE: &str = "state.json";
+
+// depot/sync.rs, in the directory of the instance in the sync directory
+pub static SYNC_CFG_FILE: &str = "cfg.json";
+pub static SYNC_STATE_FILE: &str = "sync_state.json";
 
 // forksrv.rs
 pub static ENABLE_FORKSRV: &str = "ANGORA_ENABLE_FORKSRV";
@@ -31,9 +63,13 @@ pub static ANGORA_DIR_NAME: &str = "angora";
 pub static ANGORA_LOG_FILE: &str = "angora.log";
 pub static COND_QUEUE_FILE: &str = "cond_queue.csv";
 pub static CHART_STAT_FILE: &str = "chart_stat.json";
+pub static LEARNED_CFG_FILE: &str = "learned_targets.json";
 
 pub const SLOW_SPEED: u32 = 888888;
 pub const UNREACHABLE: u64 = std::u64::MAX;
+// The cond id of the fast binary while its cmps are logged, every cmp calls
+// the runtime then (CMP_LOG_CMPID in llvm_mode/include/defs.h)
+pub const CMP_LOG_CMPID: u32 = std::u32::MAX;
 
 // ** Cond Type
 // < 0xFF: simple if
@@ -97,6 +133,7 @@ pub const COND_AFL_OP: u32 = 0x8001;
 // sensititve offsets
 pub const COND_FN_OP: u32 = 0x8002;
 pub const COND_LEN_OP: u32 = 0x8003;
+pub const COND_CUSTOM_OP: u32 = 0x8004;
 // pub const COND_ENTER_FN: u32 = 0x8010;
 // pub const COND_LEAVE_FN: u32 = 0x8011;
 
@@ -1,3 +1,4 @@
+pub mod cmp_log;
 pub mod cond_stmt_base;
 pub mod config;
 pub mod defs;
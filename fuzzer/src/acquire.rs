@@ -0,0 +1,327 @@
+// Target acquisition without a second bitcode build. The fast build writes
+// the source location of every cmp to `cmp_locs.json` (and its CFG to
+// `edges.json`), and the targets are picked by location from:
+//   - a unified diff or a list of `file:line`, to fuzz freshly changed code,
+//   - sanitizer reports, to fuzz the code around known crashes,
+//   - a list of function names, every cmp in them is a target.
+// A line without a cmp maps to the closest cmp before it in the same
+// function (the one that decides whether the line runs), or the closest one
+// after it if the line is at the start of the function.
+
+use crate::{
+    dyncfg::{
+        cfg::{BbId, CmpId, Edge},
+        fparse::{write_targets_file, CfgFile, TargetGroup},
+    },
+    triage::CrashReport,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    process,
+};
+
+#[derive(Clone, Debug, Deserialize)]
+struct CmpLoc {
+    cmp: CmpId,
+    bb: BbId,
+    func: String,
+    file: String,
+    line: u32,
+}
+
+#[derive(Deserialize)]
+struct EdgesFile {
+    edges: HashSet<Edge>,
+}
+
+// A location in the sources, the file can be relative to any directory
+type SrcLine = (String, u32);
+
+// Added lines of a unified diff ("+++ b/path", "@@ -a,b +c,d @@", "+...").
+fn parse_diff(diff: &str) -> Vec<SrcLine> {
+    let mut lines = vec![];
+    let mut file: Option<String> = None;
+    let mut line = 0;
+    for l in diff.lines() {
+        if let Some(path) = l.strip_prefix("+++ ") {
+            let path = path.split('\t').next().unwrap_or(path).trim();
+            file = if path == "/dev/null" {
+                None
+            } else {
+                Some(path.strip_prefix("b/").unwrap_or(path).to_string())
+            };
+        } else if l.starts_with("@@ ") {
+            // The new side of the hunk: "+c,d" or "+c"
+            line = l
+                .split_whitespace()
+                .find_map(|s| s.strip_prefix('+'))
+                .and_then(|s| s.split(',').next())
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0);
+        } else if l.starts_with("--- ") || l.starts_with('\\') {
+            continue;
+        } else if let Some(f) = &file {
+            if l.starts_with('+') {
+                lines.push((f.clone(), line));
+                line += 1;
+            } else if !l.starts_with('-') {
+                line += 1;
+            }
+        }
+    }
+    lines
+}
+
+// "file:line", one per line, an optional column is ignored
+fn parse_lines(content: &str) -> Vec<SrcLine> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(parse_src_line)
+        .collect()
+}
+
+fn parse_src_line(loc: &str) -> Option<SrcLine> {
+    let mut parts: Vec<&str> = loc.split(':').collect();
+    let n = parts.len();
+    if n >= 3 && parts[n - 1].parse::<u32>().is_ok() && parts[n - 2].parse::<u32>().is_ok() {
+        parts.pop();
+    }
+    let line = parts.pop()?.parse().ok()?;
+    if parts.is_empty() {
+        return None;
+    }
+    Some((parts.join(":"), line))
+}
+
+// The crashing frame and up to `depth - 1` of its callers. The frames are
+// "func file:line", or "file:line" for a UBSan report without a stack.
+fn report_lines(stderr: &str, depth: usize) -> Vec<SrcLine> {
+    let report = match CrashReport::parse(stderr) {
+        Some(r) => r,
+        None => return vec![],
+    };
+    let mut frames: Vec<&String> = report.stack.iter().take(depth).collect();
+    if frames.is_empty() {
+        frames.extend(report.frame.iter());
+    }
+    frames
+        .into_iter()
+        .filter_map(|f| f.split_whitespace().last())
+        .filter_map(parse_src_line)
+        .collect()
+}
+
+// `path` is a suffix of `file` on a component boundary
+fn same_file(file: &str, path: &str) -> bool {
+    let path = path.trim_start_matches("./");
+    file.ends_with(path) && {
+        let prefix = &file[..file.len() - path.len()];
+        prefix.is_empty() || prefix.ends_with('/') || path.starts_with('/')
+    }
+}
+
+struct LocTable {
+    locs: Vec<CmpLoc>,
+}
+
+impl LocTable {
+    fn new(mut locs: Vec<CmpLoc>) -> Self {
+        locs.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+        Self { locs }
+    }
+
+    fn lookup(&self, (path, line): &SrcLine) -> Vec<CmpId> {
+        let in_file: Vec<&CmpLoc> = self.locs.iter().filter(|l| same_file(&l.file, path)).collect();
+        let exact: Vec<CmpId> = in_file.iter().filter(|l| l.line == *line).map(|l| l.cmp).collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+
+        // The functions whose cmps surround the line
+        let mut ranges: HashMap<&str, (u32, u32)> = HashMap::new();
+        for l in in_file.iter() {
+            let r = ranges.entry(&l.func).or_insert((l.line, l.line));
+            r.0 = r.0.min(l.line);
+            r.1 = r.1.max(l.line);
+        }
+        let mut targets = vec![];
+        for (func, _) in ranges.iter().filter(|(_, r)| r.0 <= *line && *line <= r.1) {
+            let cmps: Vec<&&CmpLoc> = in_file.iter().filter(|l| l.func == *func).collect();
+            let closest = cmps
+                .iter()
+                .filter(|l| l.line < *line)
+                .map(|l| l.line)
+                .max()
+                .or_else(|| cmps.iter().filter(|l| l.line > *line).map(|l| l.line).min());
+            if let Some(closest) = closest {
+                targets.extend(cmps.iter().filter(|l| l.line == closest).map(|l| l.cmp));
+            }
+        }
+        targets
+    }
+
+    fn lookup_function(&self, func: &str) -> Vec<CmpId> {
+        self.locs.iter().filter(|l| l.func == func).map(|l| l.cmp).collect()
+    }
+
+    // All cmps of every basic block, like the id mapping of llvm-diff-parmesan
+    fn id_mapping(&self) -> HashMap<BbId, HashSet<CmpId>> {
+        let mut mapping: HashMap<BbId, HashSet<CmpId>> = HashMap::new();
+        for l in self.locs.iter().filter(|l| l.bb != 0) {
+            mapping.entry(l.bb).or_default().insert(l.cmp);
+        }
+        mapping
+    }
+}
+
+fn read_file(path: &str) -> String {
+    fs::read_to_string(path).unwrap_or_else(|e| {
+        error!("Could not read {}: {}", path, e);
+        process::exit(1);
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn acquire_main(
+    locs_file: &str,
+    edges_file: Option<&str>,
+    diff_file: Option<&str>,
+    lines_file: Option<&str>,
+    report_files: &[&str],
+    report_depth: usize,
+    functions_file: Option<&str>,
+    group: Option<&str>,
+    group_weight: f64,
+    out_file: &str,
+) {
+    pretty_env_logger::init();
+    let locs: Vec<CmpLoc> = serde_json::from_str(&read_file(locs_file)).unwrap_or_else(|e| {
+        error!("Could not parse {}: {}", locs_file, e);
+        process::exit(1);
+    });
+    let table = LocTable::new(locs);
+
+    let mut src_lines = vec![];
+    if let Some(f) = diff_file {
+        src_lines.extend(parse_diff(&read_file(f)));
+    }
+    if let Some(f) = lines_file {
+        src_lines.extend(parse_lines(&read_file(f)));
+    }
+    for f in report_files {
+        let lines = report_lines(&read_file(f), report_depth);
+        if lines.is_empty() {
+            warn!("No symbolized frame in report {}", f);
+        }
+        src_lines.extend(lines);
+    }
+
+    let mut targets = HashSet::new();
+    let mut unmatched = 0;
+    for l in src_lines.iter() {
+        let cmps = table.lookup(l);
+        if cmps.is_empty() {
+            unmatched += 1;
+            debug!("No cmp for {}:{}", l.0, l.1);
+        }
+        targets.extend(cmps);
+    }
+    if let Some(f) = functions_file {
+        for func in read_file(f).lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let cmps = table.lookup_function(func);
+            if cmps.is_empty() {
+                warn!("No cmp in function {}", func);
+            }
+            targets.extend(cmps);
+        }
+    }
+    info!(
+        "{} targets from {} source lines ({} without a cmp)",
+        targets.len(),
+        src_lines.len(),
+        unmatched
+    );
+    if targets.is_empty() {
+        warn!("No targets found, is {} from the same build?", locs_file);
+    }
+
+    let edges = match edges_file {
+        // The pass writes the edges without the enclosing object
+        Some(f) => match serde_json::from_str::<EdgesFile>(&format!("{{{}}}", read_file(f))) {
+            Ok(e) => e.edges,
+            Err(e) => {
+                error!("Could not parse {}: {}", f, e);
+                process::exit(1);
+            },
+        },
+        None => HashSet::new(),
+    };
+
+    let mut target_groups = HashMap::new();
+    if let Some(name) = group {
+        let g = TargetGroup {
+            targets: targets.clone(),
+            weight: group_weight,
+        };
+        target_groups.insert(name.to_string(), g);
+    }
+    let cfg = CfgFile {
+        targets,
+        edges,
+        id_mapping: table.id_mapping(),
+        callsite_dominators: HashMap::new(),
+        callsite_edges: HashMap::new(),
+        magic_bytes: vec![],
+        solved_targets: HashSet::new(),
+        target_weights: HashMap::new(),
+        target_groups,
+    };
+    if let Err(e) = write_targets_file(Path::new(out_file), &cfg) {
+        error!("Could not write {}: {}", out_file, e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acquire_diff() {
+        let diff = "diff --git a/src/num.c b/src/num.c
+--- a/src/num.c
++++ b/src/num.c
+@@ -10,4 +10,5 @@ int parse(char *s) {
+   int n = 0;
+-  if (n > 1)
++  if (n > 2)
++    n++;
+   return n;
+";
+        let lines = parse_diff(diff);
+        assert_eq!(lines, vec![("src/num.c".to_string(), 11), ("src/num.c".to_string(), 12)]);
+        assert_eq!(parse_src_line("/src/num.c:7:12"), Some(("/src/num.c".to_string(), 7)));
+    }
+
+    #[test]
+    fn acquire_lookup() {
+        let loc = |cmp, line| CmpLoc {
+            cmp,
+            bb: cmp,
+            func: "parse".to_string(),
+            file: "/work/src/num.c".to_string(),
+            line,
+        };
+        let table = LocTable::new(vec![loc(1, 11), loc(2, 11), loc(3, 15), loc(4, 20)]);
+        let mut cmps = table.lookup(&("src/num.c".to_string(), 11));
+        cmps.sort();
+        assert_eq!(cmps, vec![1, 2]);
+        assert_eq!(table.lookup(&("src/num.c".to_string(), 17)), vec![3]);
+        assert!(table.lookup(&("src/num.c".to_string(), 25)).is_empty());
+        assert!(table.lookup(&("um.c".to_string(), 11)).is_empty());
+    }
+}
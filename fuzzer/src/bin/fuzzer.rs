@@ -4,7 +4,8 @@ use clap::{App, Arg};
 
 extern crate angora;
 extern crate angora_common;
-use angora::fuzz_main;
+use angora::{fuzz_main, FuzzOptions, StopConditions};
+use std::process;
 
 fn main() {
     let matches = App::new("angora-fuzzer")
@@ -23,7 +24,7 @@ fn main() {
             .value_name("DIR")
             .help("Sets the directory of input seeds, use \"-\" to restart with existing output directory")
             .takes_value(true)
-            .required(true))
+            .required_unless("resume"))
         .arg(Arg::with_name("output_dir")
             .short("o")
             .long("output")
@@ -40,9 +41,11 @@ fn main() {
         .arg(Arg::with_name("sanopt_target")
             .short("s")
             .long("sanopt")
-            .value_name("PROM")
-            .help("Sets the unsanitized (fast executable) target for sanopt")
-            .takes_value(true))
+            .value_name("[KIND=]PROM[:TARGETS]")
+            .help("Sets a sanitized (fast executable) target for sanopt, KIND is asan, ubsan or msan, TARGETS is the cfg file of the targets it covers. Can be repeated")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1))
         .arg(Arg::with_name("pargs")
             .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename from Angora.")
             .required(true)
@@ -72,8 +75,8 @@ fn main() {
             .short("r")
             .long("search_method")
             .value_name("SearchMethod")
-            .help("Which search method to run the program in?")
-            .possible_values(&["gd", "random", "mb"]))
+            .help("Which search method to run the program in? i2s also runs without -t, on the cmps logged by the fast binary")
+            .possible_values(&["gd", "random", "mb", "i2s"]))
         .arg(Arg::with_name("sync_afl")
             .short("S")
             .long("sync_afl")
@@ -97,23 +100,133 @@ fn main() {
             .short("D")
             .long("only_directed")
             .help("Only consider CMPs that have a path to one of the targets (Warn: only use if static CFG is sufficient.)"))
+        .arg(Arg::with_name("resume")
+            .short("R")
+            .long("resume")
+            .conflicts_with("input_dir")
+            .help("Resume fuzzing from the checkpoint in the existing output directory"))
+        .arg(Arg::with_name("distance_metric")
+            .short("d")
+            .long("distance_metric")
+            .value_name("METRIC")
+            .help("How the distance to the targets is aggregated over the successors of a basic block, default is harmonic, or weighted if the targets file has weights")
+            .possible_values(&["harmonic", "greedy", "coverage", "arithmetic", "weighted"]))
+        .arg(Arg::with_name("schedule")
+            .short("p")
+            .long("schedule")
+            .value_name("POLICY")
+            .help("In which order the conds are fuzzed: round-robin, AFLGo-style annealing, or a multi-armed bandit rewarding new paths")
+            .possible_values(&["round_robin", "annealing", "bandit"]))
+        .arg(Arg::with_name("sync_dir")
+            .short("y")
+            .long("sync_dir")
+            .value_name("DIR")
+            .help("Shared directory to sync inputs, learned CFG edges and solved targets with other ParmeSan instances")
+            .requires("sync_id")
+            .takes_value(true))
+        .arg(Arg::with_name("sync_id")
+            .short("n")
+            .long("sync_id")
+            .value_name("NAME")
+            .help("Unique name of this instance in the sync directory")
+            .requires("sync_dir")
+            .takes_value(true))
+        .arg(Arg::with_name("dict")
+            .short("x")
+            .long("dict")
+            .value_name("FILE")
+            .help("Dictionary of tokens in AFL format, or a directory with one token per file")
+            .takes_value(true))
+        .arg(Arg::with_name("grammar")
+            .short("g")
+            .long("grammar")
+            .value_name("FILE")
+            .help("Grammar of the inputs (JSON file), generate and mutate derivation trees")
+            .takes_value(true))
+        .arg(Arg::with_name("custom_mutator")
+            .short("l")
+            .long("custom_mutator")
+            .value_name("LIB")
+            .help("Shared library of a custom mutator, see docs/custom_mutator.md")
+            .takes_value(true))
+        .arg(Arg::with_name("joint_conds")
+            .long("joint")
+            .help("Gradient search keeps the conditions before the target that read the same bytes"))
+        .arg(Arg::with_name("max_time")
+            .long("max_time")
+            .value_name("SECS")
+            .help("Stop after the given wall-clock time, exit code 10")
+            .takes_value(true))
+        .arg(Arg::with_name("max_execs")
+            .long("max_execs")
+            .value_name("NUM")
+            .help("Stop after the given number of executions, exit code 11")
+            .takes_value(true))
+        .arg(Arg::with_name("stop_on_crash")
+            .long("stop_on_crash")
+            .value_name("WHERE")
+            .help("Stop at the first crash (exit code 12), or the first crash at a target (exit code 13)")
+            .possible_values(&["any", "target"])
+            .takes_value(true))
+        .arg(Arg::with_name("stop_on_targets")
+            .long("stop_on_targets")
+            .value_name("WHEN")
+            .help("Stop once any or all targets are reached (exit code 14) or solved (exit code 15)")
+            .possible_values(&["any_reached", "all_reached", "any_solved", "all_solved"])
+            .takes_value(true))
+        .arg(Arg::with_name("max_idle")
+            .long("max_idle")
+            .value_name("MINS")
+            .help("Stop after the given minutes without new coverage, exit code 16")
+            .takes_value(true))
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .value_name("SEED")
+            .help("Seed of the RNG, random by default. The seed of a run is in chart_stat.json")
+            .takes_value(true))
+        .arg(Arg::with_name("deterministic")
+            .long("deterministic")
+            .help("Run a single fuzzing thread and ignore the exec time, so the same seed gives the same queue for a deterministic target"))
         .get_matches();
 
-    fuzz_main(
-        matches.value_of("mode").unwrap_or("llvm"),
-        matches.value_of("input_dir").unwrap(),
-        matches.value_of("output_dir").unwrap(),
-        matches.value_of("track_target").unwrap_or("-"),
-        matches.values_of_lossy("pargs").unwrap(),
-        value_t!(matches, "thread_jobs", usize).unwrap_or(1),
-        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
-        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
-        matches.value_of("search_method").unwrap_or("gd"),
-        matches.occurrences_of("sync_afl") > 0,
-        matches.occurrences_of("disable_afl_mutation") == 0,
-        matches.occurrences_of("disable_exploitation") == 0,
-        matches.value_of("cfg_file").unwrap(),
-        matches.value_of("sanopt_target"),
-        matches.occurrences_of("only_directed") > 0,
+    let stop_conditions = StopConditions::new(
+        matches.value_of("max_time").map(|_| value_t_or_exit!(matches, "max_time", u64)),
+        matches.value_of("max_execs").map(|_| value_t_or_exit!(matches, "max_execs", usize)),
+        matches.value_of("stop_on_crash"),
+        matches.value_of("stop_on_targets"),
+        matches.value_of("max_idle").map(|_| value_t_or_exit!(matches, "max_idle", u64)),
     );
+
+    let owned = |v: Option<&str>| v.map(String::from);
+    let opts = FuzzOptions {
+        mode: matches.value_of("mode").unwrap_or("llvm").to_string(),
+        in_dir: matches.value_of("input_dir").unwrap_or("-").to_string(),
+        out_dir: matches.value_of("output_dir").unwrap().to_string(),
+        track_target: matches.value_of("track_target").unwrap_or("-").to_string(),
+        pargs: matches.values_of_lossy("pargs").unwrap(),
+        num_jobs: value_t!(matches, "thread_jobs", usize).unwrap_or(1),
+        mem_limit: value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        time_limit: value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+        search_method: matches.value_of("search_method").unwrap_or("gd").to_string(),
+        sync_afl: matches.occurrences_of("sync_afl") > 0,
+        enable_afl: matches.occurrences_of("disable_afl_mutation") == 0,
+        enable_exploitation: matches.occurrences_of("disable_exploitation") == 0,
+        cfg_file: matches.value_of("cfg_file").unwrap().to_string(),
+        sanopt_targets: matches.values_of_lossy("sanopt_target").unwrap_or_default(),
+        directed_only: matches.occurrences_of("only_directed") > 0,
+        resume: matches.occurrences_of("resume") > 0,
+        distance_metric: owned(matches.value_of("distance_metric")),
+        sync_dir: owned(matches.value_of("sync_dir")),
+        sync_id: owned(matches.value_of("sync_id")),
+        schedule: matches.value_of("schedule").unwrap_or("round_robin").to_string(),
+        dict_file: owned(matches.value_of("dict")),
+        grammar_file: owned(matches.value_of("grammar")),
+        custom_mutator: owned(matches.value_of("custom_mutator")),
+        joint_conds: matches.occurrences_of("joint_conds") > 0,
+        stop_conditions,
+        seed: matches.value_of("seed").map(|_| value_t_or_exit!(matches, "seed", u64)),
+        deterministic: matches.occurrences_of("deterministic") > 0,
+    };
+    let reason = fuzz_main(opts);
+    process::exit(reason.exit_code());
 }
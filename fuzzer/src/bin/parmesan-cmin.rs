@@ -0,0 +1,70 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::cmin_main;
+
+fn main() {
+    let matches = App::new("parmesan-cmin")
+        .version(crate_version!())
+        .about("Reduce a corpus to a subset with the same edge coverage, preferring smaller and faster inputs. With a targets file, the input closest to each target is kept as well.")
+        .arg(Arg::with_name("input_dir")
+            .short("i")
+            .long("input")
+            .value_name("DIR")
+            .help("Corpus to minimize")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("output_dir")
+            .short("o")
+            .long("output")
+            .value_name("DIR")
+            .help("Where to copy the selected inputs, has to be empty")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("track_target")
+            .short("t")
+            .long("track")
+            .value_name("PROM")
+            .help("Sets the target (USE_TRACK) for tracking, including taints, cmps.")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("cfg_file")
+            .short("c")
+            .long("cfg")
+            .value_name("FILE")
+            .help("Targets file, keep the input closest to each target")
+            .takes_value(true))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+            .short("M")
+            .long("memory_limit")
+            .value_name("MEM")
+            .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+            .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+            .short("T")
+            .long("time_limit")
+            .value_name("TIME")
+            .help("time limit for programs, default is 1(s)")
+            .takes_value(true))
+        .get_matches();
+
+    cmin_main(
+        matches.value_of("input_dir").unwrap(),
+        matches.value_of("output_dir").unwrap(),
+        matches.value_of("track_target").unwrap(),
+        matches.values_of_lossy("pargs").unwrap(),
+        matches.value_of("cfg_file"),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+    );
+}
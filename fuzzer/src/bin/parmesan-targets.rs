@@ -0,0 +1,89 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+use angora::acquire_main;
+
+fn main() {
+    let matches = App::new("parmesan-targets")
+        .version(crate_version!())
+        .about("Build a targets file from source locations, using the cmp_locs.json the fast build writes. The locations come from a diff, a list of file:line, sanitizer reports or function names.")
+        .arg(Arg::with_name("locs_file")
+            .short("l")
+            .long("locs")
+            .value_name("FILE")
+            .help("cmp_locs.json of the fast (USE_FAST) build")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("edges_file")
+            .short("e")
+            .long("edges")
+            .value_name("FILE")
+            .help("edges.json of the same build, needed for the distances")
+            .takes_value(true))
+        .arg(Arg::with_name("diff_file")
+            .short("d")
+            .long("diff")
+            .value_name("FILE")
+            .help("Unified diff, e.g. of git diff, the added lines are targets")
+            .takes_value(true))
+        .arg(Arg::with_name("lines_file")
+            .long("lines")
+            .value_name("FILE")
+            .help("File with a file:line location per line")
+            .takes_value(true))
+        .arg(Arg::with_name("report_files")
+            .short("r")
+            .long("report")
+            .value_name("FILE")
+            .help("Sanitizer report, the crashing line is a target")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("report_depth")
+            .long("report_depth")
+            .value_name("NUM")
+            .help("Number of frames of a report that are targets, default is 1")
+            .takes_value(true))
+        .arg(Arg::with_name("functions_file")
+            .short("f")
+            .long("functions")
+            .value_name("FILE")
+            .help("File with a function name per line, all of their cmps are targets")
+            .takes_value(true))
+        .arg(Arg::with_name("group")
+            .short("g")
+            .long("group")
+            .value_name("NAME")
+            .help("Put the targets into a named group, e.g. to merge them with the targets of another tool")
+            .takes_value(true))
+        .arg(Arg::with_name("weight")
+            .short("w")
+            .long("weight")
+            .value_name("WEIGHT")
+            .help("Weight of the group, targets with a higher weight get more effort, default is 1.0")
+            .requires("group")
+            .takes_value(true))
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Where to write the targets file, default is targets.json")
+            .takes_value(true))
+        .get_matches();
+
+    let reports: Vec<&str> = matches.values_of("report_files").map(|v| v.collect()).unwrap_or_default();
+    acquire_main(
+        matches.value_of("locs_file").unwrap(),
+        matches.value_of("edges_file"),
+        matches.value_of("diff_file"),
+        matches.value_of("lines_file"),
+        &reports,
+        value_t!(matches, "report_depth", usize).unwrap_or(1),
+        matches.value_of("functions_file"),
+        matches.value_of("group"),
+        value_t!(matches, "weight", f64).unwrap_or(1.0),
+        matches.value_of("output").unwrap_or("targets.json"),
+    );
+}
@@ -0,0 +1,77 @@
+#[macro_use]
+extern crate clap;
+use clap::{App, Arg};
+
+extern crate angora;
+extern crate angora_common;
+use angora::tmin_main;
+
+fn main() {
+    let matches = App::new("parmesan-tmin")
+        .version(crate_version!())
+        .about("Minimize a crashing input while keeping its crash bucket, or an input while keeping it reaching a target. Uses the taint of the track binary to delete the bytes no condition depends on first.")
+        .arg(Arg::with_name("input")
+            .short("i")
+            .long("input")
+            .value_name("FILE")
+            .help("Input to minimize")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("FILE")
+            .help("Where to write the minimized input")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("track_target")
+            .short("t")
+            .long("track")
+            .value_name("PROM")
+            .help("Sets the target (USE_TRACK) for tracking, including taints, cmps.")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("sanopt_target")
+            .short("s")
+            .long("sanopt")
+            .value_name("PROM")
+            .help("Sets the sanitized target of SanOpt, used to check the crash")
+            .takes_value(true))
+        .arg(Arg::with_name("target_cmp")
+            .short("c")
+            .long("target")
+            .value_name("CMPID")
+            .help("Keep reaching this target cmp instead of keeping the crash")
+            .takes_value(true))
+        .arg(Arg::with_name("pargs")
+            .help("Targeted program (USE_FAST) and arguments. Any \"@@\" will be substituted with the input filename.")
+            .required(true)
+            .multiple(true)
+            .allow_hyphen_values(true)
+            .last(true)
+            .index(1))
+        .arg(Arg::with_name("memory_limit")
+            .short("M")
+            .long("memory_limit")
+            .value_name("MEM")
+            .help("Memory limit for programs, default is 200(MB), set 0 for unlimit memory")
+            .takes_value(true))
+        .arg(Arg::with_name("time_limit")
+            .short("T")
+            .long("time_limit")
+            .value_name("TIME")
+            .help("time limit for programs, default is 1(s)")
+            .takes_value(true))
+        .get_matches();
+
+    tmin_main(
+        matches.value_of("input").unwrap(),
+        matches.value_of("output").unwrap(),
+        matches.value_of("track_target").unwrap(),
+        matches.values_of_lossy("pargs").unwrap(),
+        matches.value_of("sanopt_target"),
+        matches.value_of("target_cmp").map(|c| c.parse().expect("Invalid target cmp id")),
+        value_t!(matches, "memory_limit", u64).unwrap_or(angora_common::config::MEM_LIMIT),
+        value_t!(matches, "time_limit", u64).unwrap_or(angora_common::config::TIME_LIMIT),
+    );
+}
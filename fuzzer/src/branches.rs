@@ -1,7 +1,8 @@
 use crate::executor::StatusType;
 use angora_common::{config::BRANCHES_SIZE, shm::SHM};
 use std::{
-    self,
+    self, fs, io,
+    path::Path,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc, RwLock,
@@ -23,6 +24,10 @@ const ENTRY_SIZE: usize = 4;
 const ENTRY_SIZE: usize = 8;
 type BranchBufPlus = [BranchEntry; BRANCHES_SIZE / ENTRY_SIZE];
 
+static VIRGIN_MAP_FILE: &str = "virgin_branches";
+static TMOUTS_MAP_FILE: &str = "tmouts_branches";
+static CRASHES_MAP_FILE: &str = "crashes_branches";
+
 // Map of bit bucket
 // [1], [2], [3], [4, 7], [8, 15], [16, 31], [32, 127], [128, infinity]
 static COUNT_LOOKUP: [u8; 256] = [
@@ -69,6 +74,41 @@ impl GlobalBranches {
         let d = self.density.load(Ordering::Relaxed);
         (d * 10000 / BRANCHES_SIZE) as f32 / 100.0
     }
+
+    pub fn save_maps(&self, dir: &Path) -> io::Result<()> {
+        fs::write(dir.join(VIRGIN_MAP_FILE), &self.virgin_branches.read().unwrap()[..])?;
+        fs::write(dir.join(TMOUTS_MAP_FILE), &self.tmouts_branches.read().unwrap()[..])?;
+        fs::write(dir.join(CRASHES_MAP_FILE), &self.crashes_branches.read().unwrap()[..])?;
+        Ok(())
+    }
+
+    pub fn load_maps(&self, dir: &Path) -> io::Result<()> {
+        Self::load_map(&self.virgin_branches, &dir.join(VIRGIN_MAP_FILE))?;
+        Self::load_map(&self.tmouts_branches, &dir.join(TMOUTS_MAP_FILE))?;
+        Self::load_map(&self.crashes_branches, &dir.join(CRASHES_MAP_FILE))?;
+        // density only counts virgin branches, see `has_new`
+        let density = self
+            .virgin_branches
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|&&v| v != 255u8)
+            .count();
+        self.density.store(density, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn load_map(map: &RwLock<Box<BranchBuf>>, path: &Path) -> io::Result<()> {
+        let buf = fs::read(path)?;
+        if buf.len() != BRANCHES_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("branch map {:?} has a different size, check `MAP_SIZE_POW2`", path),
+            ));
+        }
+        map.write().unwrap().copy_from_slice(&buf);
+        Ok(())
+    }
 }
 
 pub struct Branches {
@@ -90,7 +130,8 @@ impl Branches {
         self.trace.get_id()
     }
 
-    fn get_path(&self) -> Vec<(usize, u8)> {
+    // Edges hit by the last run and their bucketed hit counts
+    pub fn get_path(&self) -> Vec<(usize, u8)> {
         let mut path = Vec::<(usize, u8)>::new();
         let buf_plus: &BranchBufPlus = cast!(&*self.trace);
         let buf: &BranchBuf = &*self.trace;
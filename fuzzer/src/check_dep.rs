@@ -43,6 +43,10 @@ fn containt_string(f_data: &memmap::Mmap, s: &str) -> bool {
     twoway::find_bytes(&f_data[..], s.as_bytes()).is_some()
 }
 
+pub fn contains_symbol(target: &str, symbol: &str) -> bool {
+    containt_string(&mmap_file(target), symbol)
+}
+
 pub fn check_asan(target: &str) -> bool {
     let f_data = mmap_file(target);
     containt_string(&f_data, "libasan.so") || containt_string(&f_data, "__msan_init")
@@ -83,7 +87,7 @@ pub fn check_dep(in_dir: &str, out_dir: &str, cmd: &CommandOpt) {
     check_io_dir(in_dir, out_dir);
     check_crash_handling();
     check_fast(&cmd.main.0);
-    if !cmd.mode.is_pin_mode() {
+    if !cmd.mode.is_pin_mode() && cmd.has_track() {
         check_track_llvm(&cmd.track.0);
     }
 }
@@ -0,0 +1,103 @@
+// Save the fuzzing state (depot queue, learned CFG and coverage maps) to the
+// output directory, so that a campaign can be continued with `--resume`
+// without tracking all the inputs again.
+
+use crate::{
+    branches::GlobalBranches,
+    depot::{Depot, DepotSnapshot},
+    dyncfg::{cfg::ControlFlowGraph, fparse::CfgSnapshot, metric::DistanceMetric},
+};
+use angora_common::defs;
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+// 2: QPriority is ranked by the schedule policy
+const CHECKPOINT_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct CheckpointState {
+    version: u32,
+    depot: DepotSnapshot,
+    cfg: CfgSnapshot,
+}
+
+fn get_checkpoint_dir(depot: &Depot) -> PathBuf {
+    let dir = depot
+        .dirs
+        .inputs_dir
+        .parent()
+        .expect("Could not get parent directory.");
+    dir.join(defs::CHECKPOINT_DIR)
+}
+
+fn write_checkpoint(dir: &Path, depot: &Depot, gb: &GlobalBranches) -> io::Result<()> {
+    fs::create_dir(dir)?;
+    let state = CheckpointState {
+        version: CHECKPOINT_VERSION,
+        depot: depot.snapshot(),
+        cfg: depot.cfg.read().unwrap().snapshot(),
+    };
+    let f = fs::File::create(dir.join(defs::CHECKPOINT_STATE_FILE))?;
+    serde_json::to_writer(BufWriter::new(f), &state)?;
+    gb.save_maps(dir)
+}
+
+pub fn save_checkpoint(depot: &Depot, gb: &GlobalBranches) {
+    let dir = get_checkpoint_dir(depot);
+    let tmp_dir = dir.with_extension("tmp");
+    let old_dir = dir.with_extension("old");
+    if tmp_dir.exists() {
+        let _ = fs::remove_dir_all(&tmp_dir);
+    }
+    if let Err(e) = write_checkpoint(&tmp_dir, depot, gb) {
+        warn!("Could not write checkpoint: {:?}", e);
+        return;
+    }
+    // Never leave the output directory without a complete checkpoint,
+    // the fuzzer may be killed at any point.
+    if dir.exists() {
+        if old_dir.exists() {
+            let _ = fs::remove_dir_all(&old_dir);
+        }
+        if let Err(e) = fs::rename(&dir, &old_dir) {
+            warn!("Could not replace checkpoint: {:?}", e);
+            return;
+        }
+    }
+    if let Err(e) = fs::rename(&tmp_dir, &dir) {
+        warn!("Could not replace checkpoint: {:?}", e);
+        return;
+    }
+    let _ = fs::remove_dir_all(&old_dir);
+    info!("Saved checkpoint to {:?}", dir);
+}
+
+pub fn load_checkpoint(depot: &Depot, gb: &GlobalBranches, metric: Arc<dyn DistanceMetric>) {
+    let mut dir = get_checkpoint_dir(depot);
+    if !dir.exists() {
+        // We were killed while replacing the checkpoint
+        dir = dir.with_extension("old");
+    }
+    let f = fs::File::open(dir.join(defs::CHECKPOINT_STATE_FILE))
+        .expect("Could not open checkpoint, was the output directory created with checkpoints?");
+    let state: CheckpointState =
+        serde_json::from_reader(BufReader::new(f)).expect("Could not parse checkpoint");
+    if state.version != CHECKPOINT_VERSION {
+        panic!(
+            "Checkpoint version {} is not supported (expected {})",
+            state.version, CHECKPOINT_VERSION
+        );
+    }
+    *depot.cfg.write().unwrap() = ControlFlowGraph::from_snapshot(state.cfg, metric);
+    depot.restore(state.depot);
+    gb.load_maps(&dir).expect("Could not load branch maps from checkpoint");
+    info!(
+        "Resumed from checkpoint {:?} with {} inputs",
+        dir,
+        depot.num_inputs.load(std::sync::atomic::Ordering::Relaxed)
+    );
+}
@@ -0,0 +1,285 @@
+// Reduce a corpus to a subset with the same edge coverage, e.g. before handing
+// the queue of a campaign to the next one. Every input is replayed on the fast
+// binary and the (edge, hit count bucket) tuples of its path are collected.
+// The subset is picked with a greedy set cover: the input with the most tuples
+// that are not covered yet goes first, smaller and faster inputs win the ties.
+// With a targets file, the input closest to each target (fewest CFG edges
+// from its path, then the CFG score of the queue) is kept as well, even if it
+// adds no coverage.
+
+use crate::{
+    command::CommandOpt,
+    cond_stmt::CondStmt,
+    depot,
+    dyncfg::{
+        cfg::{CmpId, ControlFlowGraph, Score, UNDEF_SCORE},
+        fparse::parse_targets_file,
+        metric::parse_distance_metric,
+    },
+    executor::{Executor, StatusType},
+    options::FuzzOptions,
+};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+    time::Instant,
+};
+
+struct Entry {
+    path: PathBuf,
+    len: usize,
+    exec_us: u128,
+    tuples: Vec<(usize, u8)>,
+}
+
+// Indices of the entries that cover all tuples.
+fn set_cover(entries: &[Entry]) -> Vec<usize> {
+    let mut covered = HashSet::new();
+    let mut heap: BinaryHeap<_> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.tuples.len(), Reverse((e.len, e.exec_us, i))))
+        .collect();
+    let mut selected = vec![];
+    while let Some((gain, Reverse(key))) = heap.pop() {
+        if gain == 0 {
+            break;
+        }
+        let entry = &entries[key.2];
+        let new_gain = entry.tuples.iter().filter(|t| !covered.contains(*t)).count();
+        // The gains only shrink, so an entry that is not stale is the best one
+        if new_gain < gain {
+            heap.push((new_gain, Reverse(key)));
+            continue;
+        }
+        covered.extend(entry.tuples.iter().cloned());
+        selected.push(key.2);
+    }
+    selected
+}
+
+// Distance of the input to every target its path can reach: the fewest CFG
+// edges from a cond of the path to the target, ties are broken by the CFG
+// score the queue gives the conds of the path (the lowest one).
+fn target_distances(
+    cfg: &ControlFlowGraph,
+    targets: &[CmpId],
+    conds: &[CondStmt],
+) -> Vec<(CmpId, (u32, Score))> {
+    let mut bbs = HashSet::new();
+    let mut score = UNDEF_SCORE;
+    for cond in conds.iter().filter(|c| !c.base.is_afl()) {
+        if let Some(&bb) = cfg.get_bb_from_cmp(&cond.base.cmpid) {
+            bbs.insert(bb);
+            score = score.min(cfg.score_for_bb_inp(bb, cond.variables.clone()));
+        }
+    }
+    let hops = cfg.hop_distances_from(&bbs);
+    targets
+        .iter()
+        .filter_map(|t| {
+            let bb = cfg.get_bb_from_cmp(t)?;
+            hops.get(bb).map(|h| (*t, (*h, score)))
+        })
+        .collect()
+}
+
+// Keep the input idx if it is closer to a target than the best one so far,
+// or as close and smaller
+fn update_closest(
+    closest: &mut HashMap<CmpId, ((u32, Score), usize, usize)>,
+    distances: Vec<(CmpId, (u32, Score))>,
+    len: usize,
+    idx: usize,
+) {
+    for (target, d) in distances {
+        let best = closest.entry(target).or_insert((d, len, idx));
+        if (d, len) < (best.0, best.1) {
+            *best = (d, len, idx);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn cmin_main(
+    in_dir: &str,
+    out_dir: &str,
+    track_target: &str,
+    pargs: Vec<String>,
+    cfg_file: Option<&str>,
+    mem_limit: u64,
+    time_limit: u64,
+) {
+    pretty_env_logger::init();
+
+    let out_dir = Path::new(out_dir);
+    if out_dir.exists() && fs::read_dir(out_dir).map(|mut d| d.next().is_some()).unwrap_or(true) {
+        error!("Output directory {:?} is not empty", out_dir);
+        process::exit(1);
+    }
+    if let Err(e) = fs::create_dir_all(out_dir) {
+        error!("Could not create output directory {:?}: {}", out_dir, e);
+        process::exit(1);
+    }
+    if !Path::new(in_dir).is_dir() {
+        error!("Input directory {} does not exist", in_dir);
+        process::exit(1);
+    }
+
+    let cfg = cfg_file.map(|f| {
+        if !Path::new(f).is_file() {
+            error!("Targets file {} does not exist", f);
+            process::exit(1);
+        }
+        let data = parse_targets_file(Path::new(f)).unwrap_or_else(|e| {
+            error!("Could not read targets file {}: {:?}", f, e);
+            process::exit(1);
+        });
+        ControlFlowGraph::new(data, parse_distance_metric("harmonic"))
+    });
+
+    let work_dir = env::temp_dir().join(format!("parmesan-cmin-{}", process::id()));
+    fs::create_dir_all(&work_dir).expect("Could not create work directory");
+    let opts = FuzzOptions {
+        track_target: track_target.to_string(),
+        pargs,
+        mem_limit,
+        time_limit,
+        enable_afl: false,
+        enable_exploitation: false,
+        ..Default::default()
+    };
+    let cmd = CommandOpt::new(&opts, &work_dir);
+    let result = minimize(Path::new(in_dir), &work_dir, &cmd, cfg.as_ref());
+    // Clears the tmpfs directory
+    drop(cmd);
+    if let Err(e) = fs::remove_dir_all(&work_dir) {
+        warn!("Could not remove work directory {:?}: {:?}", work_dir, e);
+    }
+
+    let (num_inputs, selected) = result;
+    for path in selected.iter() {
+        fs::copy(path, out_dir.join(path.file_name().unwrap())).expect("Could not copy input");
+    }
+    println!(
+        "Kept {} of {} inputs, written to {:?}",
+        selected.len(),
+        num_inputs,
+        out_dir
+    );
+}
+
+// Returns the number of inputs and the paths of the selected ones
+fn minimize(
+    in_dir: &Path,
+    work_dir: &Path,
+    cmd: &CommandOpt,
+    cfg: Option<&ControlFlowGraph>,
+) -> (usize, Vec<PathBuf>) {
+    let mut executor = Executor::standalone(cmd.specify(1), work_dir);
+    let targets = cfg.map(|c| c.get_all_targets()).unwrap_or_default();
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(in_dir)
+        .expect("Could not read input directory")
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+
+    let mut entries = vec![];
+    // Closest input for every target: (distance, len, index)
+    let mut closest = HashMap::new();
+    for path in paths.iter() {
+        let buf = depot::read_from_file(path);
+        let t = Instant::now();
+        let status = executor.run_status(&buf);
+        let exec_us = t.elapsed().as_micros();
+        if status != StatusType::Normal {
+            warn!("Skipping {:?}: {:?}", path, status);
+            continue;
+        }
+        // Before the track run overwrites the trace
+        let tuples = executor.branches.get_path();
+        let idx = entries.len();
+        if let Some(cfg) = cfg {
+            if let Some(conds) = executor.track_conds(&buf) {
+                update_closest(&mut closest, target_distances(cfg, &targets, &conds), buf.len(), idx);
+            }
+        }
+        entries.push(Entry {
+            path: path.clone(),
+            len: buf.len(),
+            exec_us,
+            tuples,
+        });
+    }
+
+    let mut selected = set_cover(&entries);
+    let num_cover = selected.len();
+    for (_, _, idx) in closest.values() {
+        if !selected.contains(idx) {
+            selected.push(*idx);
+        }
+    }
+    info!(
+        "{} inputs cover all edges, {} more are the closest to a target",
+        num_cover,
+        selected.len() - num_cover
+    );
+    selected.sort();
+    (paths.len(), selected.into_iter().map(|i| entries[i].path.clone()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(len: usize, exec_us: u128, tuples: Vec<(usize, u8)>) -> Entry {
+        Entry {
+            path: PathBuf::new(),
+            len,
+            exec_us,
+            tuples,
+        }
+    }
+
+    #[test]
+    fn cmin_set_cover() {
+        let entries = vec![
+            entry(10, 5, vec![(1, 1), (2, 1)]),
+            entry(20, 5, vec![(1, 1), (2, 1), (3, 1)]),
+            entry(5, 5, vec![(3, 1), (4, 1)]),
+            // Same tuples as the previous one, but slower
+            entry(5, 9, vec![(3, 1), (4, 1)]),
+            entry(5, 5, vec![(4, 1), (4, 2)]),
+        ];
+        assert_eq!(set_cover(&entries), vec![1, 4]);
+        assert_eq!(set_cover(&entries[2..4]), vec![0]);
+    }
+
+    #[test]
+    fn cmin_closest_per_target() {
+        // 0 -> 10 -> 20 (target 1100) and 0 -> 30 -> 40 -> 50 (target 1200)
+        let data = serde_json::from_str(r#"{"targets": [1100, 1200], "edges": [[0,10], [10,20], [0,30], [30,40], [40,50]], "id_mapping": {"10": [1000], "20": [1100], "30": [1300], "40": [1400], "50": [1200]}}"#).unwrap();
+        let cfg = ControlFlowGraph::new(data, parse_distance_metric("harmonic"));
+        let targets = vec![1100, 1200];
+        let cond = |cmpid| {
+            let mut c = CondStmt::new();
+            c.base.cmpid = cmpid;
+            c
+        };
+        let mut closest = HashMap::new();
+        // One hop from 1100, three from 1200
+        update_closest(&mut closest, target_distances(&cfg, &targets, &[cond(1000)]), 10, 0);
+        // One hop from 1200, cannot reach 1100
+        update_closest(&mut closest, target_distances(&cfg, &targets, &[cond(1400)]), 10, 1);
+        assert_eq!(closest[&1100].2, 0);
+        assert_eq!(closest[&1200].2, 1);
+        // The path to 1200 passes 30 as well, it is not closer
+        update_closest(&mut closest, target_distances(&cfg, &targets, &[cond(1300)]), 5, 2);
+        assert_eq!(closest[&1200].2, 1);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{check_dep, search, tmpfs};
+use crate::{
+    check_dep,
+    options::FuzzOptions,
+    sanitizer::{Sanitizer, SanitizerKind},
+    search, tmpfs,
+};
 use angora_common::defs;
 use std::{
     env,
@@ -52,8 +57,16 @@ pub struct CommandOpt {
     pub enable_afl: bool,
     pub enable_exploitation: bool,
     pub directed_targets_file: String,
-    pub sanopt_bin: Option<String>,
+    // The sanitized builds of SanOpt, and the one this runs, if any
+    pub sanitizers: Vec<Sanitizer>,
+    pub sanitizer: Option<SanitizerKind>,
     pub directed_only: bool,
+    // Shared library of the custom mutator
+    pub custom_mutator: Option<String>,
+    // GdSearch keeps the conds before the target that share its bytes
+    pub joint_conds: bool,
+    // Nothing depends on the exec time, so a seed gives the same queue
+    pub deterministic: bool,
 }
 
 pub fn make_absolute(path: &Path) -> PathBuf {
@@ -74,21 +87,11 @@ pub fn make_absolute_str(path_str: &str) -> String {
 }
 
 impl CommandOpt {
-    pub fn new(
-        mode: &str,
-        track_target: &str,
-        pargs: Vec<String>,
-        out_dir: &Path,
-        search_method: &str,
-        mut mem_limit: u64,
-        time_limit: u64,
-        enable_afl: bool,
-        enable_exploitation: bool,
-        directed_targets_file: &str,
-        sanopt_target: Option<&str>,
-        directed_only: bool,
-    ) -> Self {
-        let mode = InstrumentationMode::from(mode);
+    // The command of the target, with its temporary files in out_dir
+    pub fn new(opts: &FuzzOptions, out_dir: &Path) -> Self {
+        let track_target = opts.track_target.as_str();
+        let mut mem_limit = opts.mem_limit;
+        let mode = InstrumentationMode::from(&opts.mode);
         
         let tmp_dir = out_dir.join(TMP_DIR);
         tmpfs::create_tmpfs_dir(&tmp_dir);
@@ -102,7 +105,7 @@ impl CommandOpt {
 
         let track_path = tmp_dir.join(TRACK_FILE).to_str().unwrap().to_owned();
 
-        let has_input_arg = pargs.contains(&"@@".to_string());
+        let has_input_arg = opts.pargs.contains(&"@@".to_string());
 
         let clang_lib = Command::new("llvm-config")
             .arg("--libdir")
@@ -112,12 +115,15 @@ impl CommandOpt {
         let clang_lib = String::from_utf8(clang_lib).unwrap();
         let ld_library = "$LD_LIBRARY_PATH:".to_string() + clang_lib.trim();
 
-        assert_ne!(
-            track_target, "-",
-            "You should set track target with -t PROM in LLVM mode!"
+        let search_method = search::parse_search_method(&opts.search_method);
+        // I2S can fuzz the cmps logged by the fast binary instead
+        assert!(
+            track_target != "-"
+                || (search_method == search::SearchMethod::I2s && !mode.is_pin_mode()),
+            "You should set track target with -t PROM, or search with -r i2s in LLVM mode!"
         );
 
-        let mut tmp_args = pargs.clone();
+        let mut tmp_args = opts.pargs.clone();
         let main_bin = make_absolute_str(&tmp_args[0].clone());
         let main_args: Vec<String> = tmp_args.drain(1..).collect();
         let uses_asan = check_dep::check_asan(&main_bin);
@@ -147,11 +153,13 @@ impl CommandOpt {
             track_args.push(String::from("--"));
             track_args.push(track_target.to_string());
             track_args.extend(main_args.clone());
+        } else if track_target == "-" {
+            track_bin = String::new();
         } else {
             track_bin = make_absolute_str(&track_target);
             track_args = main_args.clone();
         }
-        let sanopt_bin = sanopt_target.map(|s| s.to_string());
+        let sanitizers: Vec<Sanitizer> = opts.sanopt_targets.iter().map(|s| Sanitizer::parse(s)).collect();
 
         Self {
             mode,
@@ -163,20 +171,29 @@ impl CommandOpt {
             forksrv_socket_path,
             track_path,
             is_stdin: !has_input_arg,
-            search_method: search::parse_search_method(search_method),
+            search_method,
             mem_limit,
-            time_limit,
+            time_limit: opts.time_limit,
             uses_asan,
             is_raw: true,
             ld_library,
-            enable_afl,
-            enable_exploitation,
-            directed_targets_file: directed_targets_file.to_string(),
-            sanopt_bin,
-            directed_only,
+            enable_afl: opts.enable_afl,
+            enable_exploitation: opts.enable_exploitation,
+            directed_targets_file: opts.cfg_file.clone(),
+            sanitizers,
+            sanitizer: None,
+            directed_only: opts.directed_only,
+            custom_mutator: opts.custom_mutator.as_deref().map(make_absolute_str),
+            joint_conds: opts.joint_conds,
+            deterministic: opts.deterministic,
         }
     }
 
+    // Without it, the conds are the cmps logged by the fast binary
+    pub fn has_track(&self) -> bool {
+        !self.track.0.is_empty()
+    }
+
     pub fn specify(&self, id: usize) -> Self {
         let mut cmd_opt = self.clone();
         let new_file = format!("{}_{}", &cmd_opt.out_file, id);
@@ -202,16 +219,15 @@ impl CommandOpt {
         cmd_opt
     }
 
-    pub fn sanopt(&self) -> Self {
+    // Runs the i-th sanitized build
+    pub fn sanopt(&self, i: usize) -> Self {
         let mut cmd_opt = self.clone();
         let main_args = self.main.1.clone();
-        let bin = if let Some(optbin) = &self.sanopt_bin {
-            optbin.to_string()
-        } else {
-            self.main.0.clone()
-        };
+        let san = &self.sanitizers[i];
+        let bin = make_absolute_str(&san.bin);
+        let suffix = format!("sanopt_{}", i);
 
-        let new_file =  format!("{}_{}", &self.out_file, "sanopt");
+        let new_file =  format!("{}_{}", &self.out_file, suffix);
         cmd_opt.main = (bin, vec![new_file.to_string()]);
         if !self.is_stdin {
             for arg in &mut cmd_opt.main.1 {
@@ -226,12 +242,13 @@ impl CommandOpt {
             }
         }
 
-        let new_forksrv_socket_path = format!("{}_{}", &self.forksrv_socket_path, "sanopt");
-        let new_track_path = format!("{}_{}", &cmd_opt.track_path, "sanopt");
+        let new_forksrv_socket_path = format!("{}_{}", &self.forksrv_socket_path, suffix);
+        let new_track_path = format!("{}_{}", &cmd_opt.track_path, suffix);
         cmd_opt.out_file = new_file.to_owned();
         cmd_opt.forksrv_socket_path = new_forksrv_socket_path.to_owned();
         cmd_opt.track_path = new_track_path.to_owned();
         cmd_opt.uses_asan = true;
+        cmd_opt.sanitizer = Some(san.kind);
         cmd_opt.mem_limit = 0;
         cmd_opt
     }
@@ -0,0 +1,71 @@
+// The cmps the fast binary reports in a run besides the target cond, see
+// runtime_fast/src/cmp_log.rs. Every cmp calls the runtime while it is on, so
+// the runs are slower.
+
+use super::CondOutput;
+use angora_common::{cmp_log::*, config, cond_stmt_base::CondStmtBase, defs, shm};
+
+pub struct ShmCmpLog {
+    log: shm::SHM<CmpLog>,
+}
+
+impl ShmCmpLog {
+    pub fn new() -> Self {
+        let mut log = shm::SHM::<CmpLog>::new();
+        log.mode = CMP_LOG_OFF;
+        log.len = 0;
+        Self { log }
+    }
+
+    #[inline(always)]
+    pub fn get_id(&self) -> i32 {
+        self.log.get_id()
+    }
+
+    // Log every cmp of the next runs
+    pub fn log_all(&mut self) {
+        self.log.mode = CMP_LOG_ALL;
+        self.log.len = 0;
+    }
+
+    // Only the operands of these conds in the next runs
+    pub fn watch(&mut self, conds: &[CondStmtBase]) {
+        let len = conds.len().min(config::CMP_LOG_MAX_ENTRIES);
+        self.log.entries[..len].copy_from_slice(&conds[..len]);
+        for cond in self.log.entries[..len].iter_mut() {
+            cond.lb1 = u32::MAX;
+        }
+        self.log.len = len as u32;
+        self.log.mode = CMP_LOG_WATCH;
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.log.mode == CMP_LOG_WATCH
+    }
+
+    pub fn off(&mut self) {
+        self.log.mode = CMP_LOG_OFF;
+        self.log.len = 0;
+    }
+
+    // The cmps of the last run, as they were reached
+    pub fn logged(&self) -> Vec<CondStmtBase> {
+        let len = (self.log.len as usize).min(config::CMP_LOG_MAX_ENTRIES);
+        self.log.entries[..len].to_vec()
+    }
+
+    // The output of every watched cond in the last run, like
+    // ShmConds::get_cond_output
+    pub fn outputs(&self) -> Vec<u64> {
+        self.logged()
+            .iter()
+            .map(|cond| {
+                if cond.lb1 == u32::MAX {
+                    defs::UNREACHABLE
+                } else {
+                    cond.get_output().min(defs::UNREACHABLE - 1)
+                }
+            })
+            .collect()
+    }
+}
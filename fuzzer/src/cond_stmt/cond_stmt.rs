@@ -3,7 +3,7 @@ use crate::fuzz_type::FuzzType;
 use angora_common::{cond_stmt_base::CondStmtBase, defs, tag::TagSeg};
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct CondStmt {
     pub base: CondStmtBase,
     pub offsets: Vec<TagSeg>,
@@ -18,6 +18,9 @@ pub struct CondStmt {
     pub state: CondState,
     pub num_minimal_optima: usize,
     pub linear: bool,
+    // SmtSearch had its try
+    #[serde(default)]
+    pub solver_tried: bool,
 }
 
 impl PartialEq for CondStmt {
@@ -53,6 +56,7 @@ impl CondStmt {
             state: CondState::default(),
             num_minimal_optima: 0,
             linear: false,
+            solver_tried: false,
         }
     }
 
@@ -65,6 +69,7 @@ impl CondStmt {
     pub fn get_fuzz_type(&self) -> FuzzType {
         match self.base.op {
             defs::COND_AFL_OP => FuzzType::AFLFuzz,
+            defs::COND_CUSTOM_OP => FuzzType::CustomFuzz,
             defs::COND_LEN_OP => FuzzType::LenFuzz,
             defs::COND_FN_OP => FuzzType::CmpFnFuzz,
             _ => {
@@ -122,6 +127,14 @@ impl CondStmt {
         afl_cond
     }
 
+    pub fn get_custom_cond(id: usize, speed: u32, edge_num: usize) -> Self {
+        let mut custom_cond = Self::get_afl_cond(id, speed, edge_num);
+        custom_cond.base.op = defs::COND_CUSTOM_OP;
+        // Not the same entry as the AFL cond of the input
+        custom_cond.base.order = 1;
+        custom_cond
+    }
+
     pub fn is_done(&self) -> bool {
         self.base.is_done()
     }
@@ -1,9 +1,11 @@
+mod cmp_log;
 mod cond_state;
 mod cond_stmt;
 mod output;
 mod shm_conds;
 
 pub use self::{
+    cmp_log::ShmCmpLog,
     cond_state::{CondState, NextState},
     cond_stmt::CondStmt,
     output::CondOutput,
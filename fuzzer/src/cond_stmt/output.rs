@@ -2,6 +2,7 @@
 get the output(objective) of the conds.
 */
 
+use crate::mut_input::float;
 use angora_common::{cond_stmt_base::CondStmtBase, defs};
 use std;
 
@@ -13,6 +14,10 @@ pub trait CondOutput {
 impl CondOutput for CondStmtBase {
     // relu
     fn get_output(&self) -> u64 {
+        if self.is_float() && (self.size == 4 || self.size == 8) {
+            return get_float_output(self);
+        }
+
         let mut a = self.arg1;
         let mut b = self.arg2;
 
@@ -97,11 +102,7 @@ impl CondOutput for CondStmtBase {
                     a - b
                 }
             },
-            _ => {
-                //TODO : support float.
-                // if self.is_float() {
-                sub_abs(a, b)
-            },
+            _ => sub_abs(a, b),
         };
 
         debug!(
@@ -113,6 +114,87 @@ impl CondOutput for CondStmtBase {
     }
 }
 
+// The args are the bits of the floats, the distances are in ULPs. An
+// ordered predicate is far from true if there is a NaN, an unordered one is
+// true.
+fn get_float_output(cond: &CondStmtBase) -> u64 {
+    let size = cond.size as usize;
+    let fa = float::to_f64(cond.arg1, size);
+    let fb = float::to_f64(cond.arg2, size);
+    let a = float::to_ordered(cond.arg1, size);
+    let b = float::to_ordered(cond.arg2, size);
+
+    let mut op = cond.op & defs::COND_BASIC_MASK;
+    if cond.is_explore() && cond.condition == defs::COND_TRUE_ST {
+        // The negation of an ordered predicate is the unordered one
+        op = defs::COND_FCMP_TRUE - op;
+    }
+
+    let unordered = fa.is_nan() || fb.is_nan();
+    if unordered {
+        return match op {
+            defs::COND_FCMP_FALSE..=defs::COND_FCMP_ORD => u64::MAX,
+            _ => 0,
+        };
+    }
+
+    // -0.0 == 0.0 but they are one ULP apart
+    let eq = fa == fb;
+    let output = match op {
+        defs::COND_FCMP_OEQ | defs::COND_FCMP_UEQ => {
+            if eq {
+                0
+            } else {
+                sub_abs(a, b)
+            }
+        },
+        defs::COND_FCMP_ONE | defs::COND_FCMP_UNE => {
+            if eq {
+                1
+            } else {
+                0
+            }
+        },
+        defs::COND_FCMP_OGT | defs::COND_FCMP_UGT => {
+            if fa > fb {
+                0
+            } else {
+                b.saturating_sub(a) + EPS
+            }
+        },
+        defs::COND_FCMP_OGE | defs::COND_FCMP_UGE => {
+            if fa >= fb {
+                0
+            } else {
+                b - a
+            }
+        },
+        defs::COND_FCMP_OLT | defs::COND_FCMP_ULT => {
+            if fa < fb {
+                0
+            } else {
+                a.saturating_sub(b) + EPS
+            }
+        },
+        defs::COND_FCMP_OLE | defs::COND_FCMP_ULE => {
+            if fa <= fb {
+                0
+            } else {
+                a - b
+            }
+        },
+        defs::COND_FCMP_ORD | defs::COND_FCMP_TRUE => 0,
+        _ => 1,
+    };
+
+    debug!(
+        "id: {}, fcmp: {} -> {}, size:{}, condition: {}, arg({} {}), output: {}",
+        cond.cmpid, cond.op, op, cond.size, cond.condition, fa, fb, output
+    );
+
+    output
+}
+
 fn sub_abs(arg1: u64, arg2: u64) -> u64 {
     if arg1 < arg2 {
         arg2 - arg1
@@ -183,4 +265,30 @@ mod tests {
         );
         assert_eq!(translate_signed_value(255, 1), 127);
     }
+
+    #[test]
+    fn test_float_output() {
+        let mut cond = CondStmtBase::default();
+        cond.size = 8;
+        cond.op = defs::COND_FCMP_OEQ;
+        cond.condition = defs::COND_FALSE_ST;
+        cond.arg1 = 1.0f64.to_bits();
+        cond.arg2 = (1.0f64 + 2.0 * std::f64::EPSILON).to_bits();
+        assert_eq!(cond.get_output(), 2);
+        cond.arg2 = (-0.0f64).to_bits();
+        cond.arg1 = 0.0f64.to_bits();
+        assert_eq!(cond.get_output(), 0);
+
+        // 1.0 < 2.0 is true, its negation 1.0 >= 2.0 is wanted
+        cond.op = defs::COND_FCMP_OLT;
+        cond.condition = defs::COND_TRUE_ST;
+        cond.arg1 = 1.0f64.to_bits();
+        cond.arg2 = 2.0f64.to_bits();
+        assert!(cond.get_output() > 0);
+        // A NaN makes the unordered negation true
+        cond.arg2 = std::f64::NAN.to_bits();
+        assert_eq!(cond.get_output(), 0);
+        cond.condition = defs::COND_FALSE_ST;
+        assert_eq!(cond.get_output(), u64::MAX);
+    }
 }
@@ -51,6 +51,29 @@ impl ShmConds {
         }
     }
 
+    // Set another cmp until the old one is restored, e.g. to read the
+    // operands of a checksum check.
+    pub fn replace(&mut self, base: &CondStmtBase) -> CondStmtBase {
+        let old = *self.cond;
+        *self.cond = *base;
+        self.set_len(1);
+        self.reset_reachable_state();
+        old
+    }
+
+    pub fn restore(&mut self, base: CondStmtBase) {
+        *self.cond = base;
+    }
+
+    // The operands of the cmp in the last run, None if it was not reached
+    pub fn get_args(&self) -> Option<(u64, u64)> {
+        if self.is_cond_reachable() {
+            Some((self.cond.arg1, self.cond.arg2))
+        } else {
+            None
+        }
+    }
+
     pub fn clear(&mut self) {
         self.cond.cmpid = 0;
         self.cond.order = 0;
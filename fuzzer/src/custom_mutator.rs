@@ -0,0 +1,158 @@
+// Protocol-specific mutators loaded from a shared library (`--custom_mutator`),
+// with a C ABI modeled on the custom mutators of AFL++. See
+// docs/custom_mutator.md for the functions the library exports. Every
+// executor has its own instance, so the functions are never called from two
+// threads with the same data.
+
+use libloading::{Library, Symbol};
+use std::{
+    os::raw::{c_uint, c_void},
+    ptr, slice,
+};
+
+type InitFn = unsafe extern "C" fn(seed: c_uint) -> *mut c_void;
+type FuzzFn = unsafe extern "C" fn(
+    data: *mut c_void,
+    buf: *mut u8,
+    buf_size: usize,
+    out_buf: *mut *mut u8,
+    add_buf: *mut u8,
+    add_buf_size: usize,
+    max_size: usize,
+) -> usize;
+type PostProcessFn =
+    unsafe extern "C" fn(data: *mut c_void, buf: *mut u8, buf_size: usize, out_buf: *mut *mut u8) -> usize;
+type InitTrimFn = unsafe extern "C" fn(data: *mut c_void, buf: *mut u8, buf_size: usize) -> i32;
+type TrimFn = unsafe extern "C" fn(data: *mut c_void, out_buf: *mut *mut u8) -> usize;
+type PostTrimFn = unsafe extern "C" fn(data: *mut c_void, success: u8) -> i32;
+type DeinitFn = unsafe extern "C" fn(data: *mut c_void);
+
+pub struct CustomMutator {
+    data: *mut c_void,
+    fuzz_fn: FuzzFn,
+    post_process_fn: Option<PostProcessFn>,
+    trim_fns: Option<(InitTrimFn, TrimFn, PostTrimFn)>,
+    deinit_fn: Option<DeinitFn>,
+    // The functions point into the library, so it is dropped last
+    _lib: Library,
+}
+
+unsafe fn get_fn<T: Copy>(lib: &Library, name: &[u8]) -> Option<T> {
+    lib.get::<T>(name).ok().map(|s: Symbol<T>| *s)
+}
+
+// The output buffer belongs to the library and is only valid until the next
+// call, so it is copied.
+unsafe fn copy_out(out_buf: *mut u8, len: usize) -> Vec<u8> {
+    if out_buf.is_null() || len == 0 {
+        return vec![];
+    }
+    slice::from_raw_parts(out_buf, len).to_vec()
+}
+
+impl CustomMutator {
+    pub fn load(path: &str, seed: u32) -> Result<Self, String> {
+        unsafe {
+            let lib = Library::new(path).map_err(|e| e.to_string())?;
+            let init_fn: InitFn =
+                get_fn(&lib, b"parmesan_custom_init\0").ok_or("parmesan_custom_init is missing")?;
+            let fuzz_fn: FuzzFn =
+                get_fn(&lib, b"parmesan_custom_fuzz\0").ok_or("parmesan_custom_fuzz is missing")?;
+            let post_process_fn = get_fn(&lib, b"parmesan_custom_post_process\0");
+            let trim_fns = match (
+                get_fn(&lib, b"parmesan_custom_init_trim\0"),
+                get_fn(&lib, b"parmesan_custom_trim\0"),
+                get_fn(&lib, b"parmesan_custom_post_trim\0"),
+            ) {
+                (Some(i), Some(t), Some(p)) => Some((i, t, p)),
+                (None, None, None) => None,
+                _ => return Err("the trim functions have to be exported together".to_string()),
+            };
+            let deinit_fn = get_fn(&lib, b"parmesan_custom_deinit\0");
+            let data = init_fn(seed);
+            if data.is_null() {
+                return Err("parmesan_custom_init failed".to_string());
+            }
+            Ok(Self {
+                data,
+                fuzz_fn,
+                post_process_fn,
+                trim_fns,
+                deinit_fn,
+                _lib: lib,
+            })
+        }
+    }
+
+    // Empty if the mutator has no new input
+    pub fn fuzz(&mut self, buf: &[u8], add_buf: &[u8], max_size: usize) -> Vec<u8> {
+        // The mutator may change the buffers in place
+        let mut buf = buf.to_vec();
+        let mut add_buf = add_buf.to_vec();
+        let mut out_buf = ptr::null_mut();
+        unsafe {
+            let len = (self.fuzz_fn)(
+                self.data,
+                buf.as_mut_ptr(),
+                buf.len(),
+                &mut out_buf,
+                add_buf.as_mut_ptr(),
+                add_buf.len(),
+                max_size,
+            );
+            copy_out(out_buf, len.min(max_size))
+        }
+    }
+
+    // The input the target gets, None without a post-process hook
+    pub fn post_process(&mut self, buf: &[u8]) -> Option<Vec<u8>> {
+        let post_process_fn = self.post_process_fn?;
+        let mut buf = buf.to_vec();
+        let mut out_buf = ptr::null_mut();
+        unsafe {
+            let len = post_process_fn(self.data, buf.as_mut_ptr(), buf.len(), &mut out_buf);
+            Some(copy_out(out_buf, len))
+        }
+    }
+
+    // Number of trimming steps for the input, None if the mutator does not trim
+    pub fn init_trim(&mut self, buf: &[u8]) -> Option<usize> {
+        let (init_trim_fn, _, _) = self.trim_fns?;
+        let mut buf = buf.to_vec();
+        let steps = unsafe { init_trim_fn(self.data, buf.as_mut_ptr(), buf.len()) };
+        if steps > 0 {
+            Some(steps as usize)
+        } else {
+            None
+        }
+    }
+
+    // The candidate of the current step
+    pub fn trim(&mut self) -> Vec<u8> {
+        let (_, trim_fn, _) = self.trim_fns.expect("The mutator does not trim");
+        let mut out_buf = ptr::null_mut();
+        unsafe {
+            let len = trim_fn(self.data, &mut out_buf);
+            copy_out(out_buf, len)
+        }
+    }
+
+    // Whether the candidate was kept. Returns the next step, None when done.
+    pub fn post_trim(&mut self, success: bool) -> Option<usize> {
+        let (_, _, post_trim_fn) = self.trim_fns.expect("The mutator does not trim");
+        let next = unsafe { post_trim_fn(self.data, success as u8) };
+        if next >= 0 {
+            Some(next as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for CustomMutator {
+    fn drop(&mut self) {
+        if let Some(deinit_fn) = self.deinit_fn {
+            unsafe { deinit_fn(self.data) };
+        }
+    }
+}
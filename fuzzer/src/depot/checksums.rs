@@ -0,0 +1,26 @@
+use super::*;
+use crate::track::Checksum;
+use std::{collections::HashMap, sync::MutexGuard};
+
+// The checksum checks found while tracking the inputs, by input id
+impl Depot {
+    pub(super) fn lock_checksums(&self) -> MutexGuard<'_, HashMap<usize, Vec<Checksum>>> {
+        match self.checksums.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    pub fn add_checksums(&self, id: usize, checksums: Vec<Checksum>) {
+        if !checksums.is_empty() {
+            self.lock_checksums().insert(id, checksums);
+        }
+    }
+
+    pub fn get_checksums(&self, id: usize) -> Vec<Checksum> {
+        self.lock_checksums().get(&id).cloned().unwrap_or_default()
+    }
+}
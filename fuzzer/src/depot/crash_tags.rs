@@ -0,0 +1,24 @@
+use super::*;
+use crate::sanitizer::SanitizerKind;
+use std::{collections::HashMap, sync::MutexGuard};
+
+// The sanitizer of the SanOpt build that caught each crash, by crash id
+impl Depot {
+    pub(super) fn lock_crash_tags(&self) -> MutexGuard<'_, HashMap<usize, SanitizerKind>> {
+        match self.crash_tags.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    pub fn tag_crash(&self, id: usize, kind: SanitizerKind) {
+        self.lock_crash_tags().insert(id, kind);
+    }
+
+    pub fn get_crash_tag(&self, id: usize) -> Option<SanitizerKind> {
+        self.lock_crash_tags().get(&id).copied()
+    }
+}
@@ -1,21 +1,40 @@
 use super::*;
-use crate::{cond_stmt::CondStmt, executor::StatusType};
-use crate::dyncfg::cfg::ControlFlowGraph;
-use rand;
+use crate::{cond_stmt::CondStmt, executor::StatusType, grammar::Tree, rng, sanitizer::SanitizerKind, track::Checksum};
+use crate::dyncfg::cfg::{CmpId, ControlFlowGraph};
 use std::{
+    collections::HashMap,
     fs,
     io::prelude::*,
     mem,
     path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicUsize, Ordering},
-        Mutex, RwLock,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, RwLock,
     },
+    time::Instant,
 };
 // https://crates.io/crates/priority-queue
 use angora_common::config;
 use priority_queue::PriorityQueue;
 
+// Everything needed to continue fuzzing the inputs in the depot after a restart.
+#[derive(Serialize, Deserialize)]
+pub struct DepotSnapshot {
+    queue: Vec<(CondStmt, QPriority)>,
+    num_inputs: usize,
+    num_hangs: usize,
+    num_crashes: usize,
+    #[serde(default)]
+    targets: HashMap<CmpId, TargetInfo>,
+    // Learned while tracking, the conds are not tracked again when resuming
+    #[serde(default)]
+    auto_dict: Vec<Vec<u8>>,
+    #[serde(default)]
+    checksums: HashMap<usize, Vec<Checksum>>,
+    #[serde(default)]
+    crash_tags: HashMap<usize, SanitizerKind>,
+}
+
 pub struct Depot {
     pub queue: Mutex<PriorityQueue<CondStmt, QPriority>>,
     pub num_inputs: AtomicUsize,
@@ -23,17 +42,44 @@ pub struct Depot {
     pub num_crashes: AtomicUsize,
     pub dirs: DepotDir,
     pub cfg: RwLock<ControlFlowGraph>,
+    pub targets: Mutex<HashMap<CmpId, TargetInfo>>,
+    pub start_time: Instant,
+    // Conds taken out of the queue by the scheduler until they are returned
+    pub leased: Mutex<HashMap<CondStmt, QPriority>>,
+    pub schedule: Arc<dyn SchedulePolicy>,
+    pub dict: Dictionary,
+    pub checksums: Mutex<HashMap<usize, Vec<Checksum>>>,
+    pub crash_tags: Mutex<HashMap<usize, SanitizerKind>>,
+    // Trees of the inputs that were read or saved last, in grammar mode
+    pub trees: Mutex<HashMap<usize, Tree>>,
+    // The tracked conds of the inputs are saved for the peers
+    pub keeps_conds: AtomicBool,
 }
 
 impl Depot {
-    pub fn new(in_dir: PathBuf, out_dir: &Path, cfg: RwLock<ControlFlowGraph>) -> Self {
+    pub fn new(
+        in_dir: PathBuf,
+        out_dir: &Path,
+        cfg: RwLock<ControlFlowGraph>,
+        schedule: Arc<dyn SchedulePolicy>,
+        dict: Dictionary,
+    ) -> Self {
         Self {
             queue: Mutex::new(PriorityQueue::new()),
             num_inputs: AtomicUsize::new(0),
             num_hangs: AtomicUsize::new(0),
             num_crashes: AtomicUsize::new(0),
             dirs: DepotDir::new(in_dir, out_dir),
-            cfg
+            cfg,
+            targets: Mutex::new(HashMap::new()),
+            start_time: Instant::now(),
+            leased: Mutex::new(HashMap::new()),
+            schedule,
+            dict,
+            checksums: Mutex::new(HashMap::new()),
+            crash_tags: Mutex::new(HashMap::new()),
+            trees: Mutex::new(HashMap::new()),
+            keeps_conds: AtomicBool::new(false),
         }
     }
 
@@ -67,13 +113,15 @@ impl Depot {
             StatusType::Timeout => {
                 Self::save_input(&status, buf, &self.num_hangs, cmpid, &self.dirs.hangs_dir)
             },
-            StatusType::Crash => Self::save_input(
-                &status,
-                buf,
-                &self.num_crashes,
-                cmpid,
-                &self.dirs.crashes_dir,
-            ),
+            StatusType::Crash => {
+                Self::save_input(
+                    &status,
+                    buf,
+                    &self.num_crashes,
+                    cmpid,
+                    &self.dirs.crashes_dir,
+                )
+            },
             _ => 0,
         }
     }
@@ -83,7 +131,7 @@ impl Depot {
     }
 
     pub fn next_random(&self) -> usize {
-        rand::random::<usize>() % self.num_inputs.load(Ordering::Relaxed)
+        rng::random::<usize>() % self.num_inputs.load(Ordering::Relaxed)
     }
 
     pub fn get_input_buf(&self, id: usize) -> Vec<u8> {
@@ -91,7 +139,35 @@ impl Depot {
         read_from_file(&path)
     }
 
-    pub fn get_entry(&self) -> Option<(CondStmt, QPriority)> {
+    pub fn snapshot(&self) -> DepotSnapshot {
+        let queue = {
+            let q = match self.queue.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => {
+                    warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                    poisoned.into_inner()
+                },
+            };
+            let mut queue: Vec<_> = q.iter().map(|(c, p)| (c.clone(), *p)).collect();
+            // The conds are in the state they were leased in
+            queue.extend(self.lock_leased().iter().map(|(c, p)| (c.clone(), *p)));
+            queue
+        };
+        // Read the counters after the queue, so every input that a cond
+        // belongs to is counted and will not be overwritten after resuming.
+        DepotSnapshot {
+            queue,
+            num_inputs: self.num_inputs.load(Ordering::Relaxed),
+            num_hangs: self.num_hangs.load(Ordering::Relaxed),
+            num_crashes: self.num_crashes.load(Ordering::Relaxed),
+            targets: self.get_target_infos(),
+            auto_dict: self.dict.auto_tokens(),
+            checksums: self.lock_checksums().clone(),
+            crash_tags: self.lock_crash_tags().clone(),
+        }
+    }
+
+    pub fn restore(&self, snapshot: DepotSnapshot) {
         let mut q = match self.queue.lock() {
             Ok(guard) => guard,
             Err(poisoned) => {
@@ -99,15 +175,125 @@ impl Depot {
                 poisoned.into_inner()
             },
         };
-        q.peek()
-            .and_then(|x| Some((x.0.clone(), x.1.clone())))
-            .and_then(|x| {
-                if !x.1.is_done() {
-                    let q_inc = x.1.inc(x.0.base.op);
-                    q.change_priority(&(x.0), q_inc);
-                }
-                Some(x)
-            })
+        for (cond, priority) in snapshot.queue {
+            q.push(cond, priority);
+        }
+        self.num_inputs.store(snapshot.num_inputs, Ordering::Relaxed);
+        self.num_hangs.store(snapshot.num_hangs, Ordering::Relaxed);
+        self.num_crashes.store(snapshot.num_crashes, Ordering::Relaxed);
+        self.restore_target_infos(snapshot.targets);
+        self.dict.add_auto_tokens(snapshot.auto_dict);
+        self.lock_checksums().extend(snapshot.checksums);
+        self.lock_crash_tags().extend(snapshot.crash_tags);
+    }
+
+    fn lock_leased(&self) -> MutexGuard<'_, HashMap<CondStmt, QPriority>> {
+        match self.leased.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    // Take up to n of the best conds out of the queue, so no other thread
+    // gets them until they are returned.
+    pub fn lease_entries(&self, n: usize) -> Vec<(CondStmt, QPriority)> {
+        let mut q = match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        let mut leased = self.lock_leased();
+        let mut batch = vec![];
+        while batch.len() < n {
+            match q.peek() {
+                Some((_, p)) if !p.is_done() => {},
+                _ => break,
+            }
+            let (cond, priority) = q.pop().unwrap();
+            leased.insert(cond.clone(), priority);
+            batch.push((cond, priority));
+        }
+        batch
+    }
+
+    // Rank the conds in the queue again, for schedules whose ranks change
+    // while the conds wait. Called by the main thread now and then, as it
+    // rebuilds the heap.
+    pub fn rerank_queue(&self) {
+        if !self.schedule.reranks() {
+            return;
+        }
+        let weights: HashMap<CmpId, f64> = {
+            let cfg = self.cfg.read().unwrap();
+            cfg.get_all_targets()
+                .into_iter()
+                .map(|t| (t, cfg.get_target_weight(t)))
+                .collect()
+        };
+        let mut q = match self.queue.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        };
+        for (cond, p) in q.iter_mut() {
+            *p = self.schedule.rerank(*p, weights.get(&cond.base.cmpid).cloned());
+        }
+    }
+
+    // Put a leased cond back into the queue after it has been fuzzed,
+    // num_new is the number of new paths it found.
+    pub fn return_entry(&self, cond: CondStmt, priority: QPriority, num_new: usize) {
+        let weight = {
+            let cfg = self.cfg.read().unwrap();
+            if cfg.is_target(cond.base.cmpid) {
+                Some(cfg.get_target_weight(cond.base.cmpid))
+            } else {
+                None
+            }
+        };
+        let priority = match weight {
+            Some(w) => self.schedule.fuzzed_target(priority, cond.base.op, num_new, w),
+            None => self.schedule.fuzzed(priority, cond.base.op, num_new),
+        };
+        self.release_entry(cond, priority);
+    }
+
+    // Put a leased cond back into the queue with the given priority
+    pub fn release_entry(&self, mut cond: CondStmt, mut priority: QPriority) {
+        {
+            let mut q = match self.queue.lock() {
+                Ok(guard) => guard,
+                Err(poisoned) => {
+                    warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                    poisoned.into_inner()
+                },
+            };
+            // Marked as done by add_entries while it was leased
+            if self.lock_leased().remove(&cond).is_some_and(|p| p.is_done()) {
+                cond.mark_as_done();
+                priority = QPriority::done();
+            }
+            q.push(cond.clone(), priority);
+        }
+        self.update_entry(cond);
+    }
+
+    // None if the cond is not leased
+    fn is_explored_while_leased(&self, cond: &CondStmt) -> Option<bool> {
+        self.lock_leased()
+            .get_key_value(cond)
+            .map(|(c, _)| c.base.condition != cond.base.condition)
+    }
+
+    pub fn num_leased(&self) -> usize {
+        self.lock_leased().len()
     }
 
     pub fn add_entries(&self, conds: Vec<CondStmt>) {
@@ -149,13 +335,18 @@ impl Depot {
                             // If the cond is faster than the older one, we prefer the faster,
                             if config::PREFER_FAST_COND && v.0.speed > cond.speed {
                                 mem::swap(v.0, &mut cond);
-                                let priority = QPriority::init_distance(cond.base.op, distance);
+                                let priority = self.schedule.init(cond.base.op, distance);
                                 q.change_priority(&cond, priority);
                             }
                         }
                     }
+                } else if let Some(explored) = self.is_explored_while_leased(&cond) {
+                    // Being fuzzed, the other direction has been explored now
+                    if explored {
+                        self.lock_leased().insert(cond, QPriority::done());
+                    }
                 } else {
-                    let priority = QPriority::init_distance(cond.base.op, distance);
+                    let priority = self.schedule.init(cond.base.op, distance);
                     q.push(cond, priority);
                 }
             }
@@ -176,7 +367,7 @@ impl Depot {
                 let cfg = self.cfg.read().unwrap();
                 if let Some(&bbid) = cfg.get_bb_from_cmp(&cond.base.cmpid) {
                     let distance = cfg.score_for_bb(bbid);
-                    let p = v.1.new_distance(distance);
+                    let p = self.schedule.new_distance(*v.1, distance);
                     q.change_priority(&cond, p);
                 }
                 else {
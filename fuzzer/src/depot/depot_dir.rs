@@ -10,6 +10,10 @@ pub struct DepotDir {
     pub hangs_dir: PathBuf,
     pub crashes_dir: PathBuf,
     pub seeds_dir: PathBuf,
+    // Only created in grammar mode
+    pub trees_dir: PathBuf,
+    // Only created when syncing with peers
+    pub conds_dir: PathBuf,
 }
 
 impl DepotDir {
@@ -19,15 +23,20 @@ impl DepotDir {
         let hangs_dir = out_dir.join(defs::HANGS_DIR);
         let crashes_dir = out_dir.join(defs::CRASHES_DIR);
 
-        fs::create_dir(&crashes_dir).unwrap();
-        fs::create_dir(&hangs_dir).unwrap();
-        fs::create_dir(&inputs_dir).unwrap();
+        // The directories already exist if we resume from a checkpoint.
+        for dir in &[&crashes_dir, &hangs_dir, &inputs_dir] {
+            if !dir.is_dir() {
+                fs::create_dir(dir).unwrap();
+            }
+        }
 
         Self {
             inputs_dir,
             hangs_dir,
             crashes_dir,
             seeds_dir,
+            trees_dir: out_dir.join(defs::TREES_DIR),
+            conds_dir: out_dir.join(defs::CONDS_DIR),
         }
     }
 }
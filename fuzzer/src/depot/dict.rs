@@ -0,0 +1,246 @@
+// Tokens that are spliced into the inputs: the ones of the `-x` dictionary
+// (AFL format), and the ones learned while tracking from the constants that
+// the input is compared with, the magic bytes of strcmp/memcmp-style calls and
+// the operands of integer equalities.
+
+use crate::cond_stmt::CondStmt;
+use angora_common::{config, defs};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::Path,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+#[derive(Default)]
+struct AutoDict {
+    tokens: Vec<Vec<u8>>,
+    seen: HashSet<Vec<u8>>,
+}
+
+#[derive(Default)]
+pub struct Dictionary {
+    user: Vec<Vec<u8>>,
+    auto: RwLock<AutoDict>,
+}
+
+impl Dictionary {
+    // A dictionary file, or a directory with one token per file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut user = vec![];
+        if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                let p = entry?.path();
+                if p.is_file() {
+                    user.push(fs::read(p)?);
+                }
+            }
+        } else {
+            let content = fs::read(path)?;
+            for (i, line) in content.split(|b| *b == b'\n').enumerate() {
+                match parse_line(line) {
+                    Ok(Some(token)) => user.push(token),
+                    Ok(None) => {},
+                    Err(e) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("line {}: {}", i + 1, e),
+                        ))
+                    },
+                }
+            }
+        }
+        user.retain(|t| !t.is_empty() && t.len() <= config::MAX_DICT_TOKEN_LEN);
+        user.sort();
+        user.dedup();
+        Ok(Self {
+            user,
+            auto: Default::default(),
+        })
+    }
+
+    fn read_auto(&self) -> RwLockReadGuard<'_, AutoDict> {
+        match self.auto.read() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Lock poisoned. Results can be incorrect! Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    fn write_auto(&self) -> RwLockWriteGuard<'_, AutoDict> {
+        match self.auto.write() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Lock poisoned. Results can be incorrect! Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    pub fn add_auto_tokens(&self, tokens: Vec<Vec<u8>>) {
+        let mut auto = self.write_auto();
+        for token in tokens {
+            if auto.tokens.len() >= config::MAX_AUTO_DICT_SIZE {
+                break;
+            }
+            if is_useful_token(&token) && !self.user.contains(&token) && auto.seen.insert(token.clone()) {
+                auto.tokens.push(token);
+            }
+        }
+    }
+
+    // Learn the constants the tracked conds compare their input with
+    pub fn add_from_conds(&self, conds: &[CondStmt]) {
+        let tokens = conds.iter().flat_map(cond_tokens).collect();
+        self.add_auto_tokens(tokens);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.user.is_empty() && self.read_auto().tokens.is_empty()
+    }
+
+    // User tokens first
+    pub fn tokens(&self) -> Vec<Vec<u8>> {
+        let mut tokens = self.user.clone();
+        tokens.extend(self.read_auto().tokens.iter().cloned());
+        tokens
+    }
+
+    pub fn auto_tokens(&self) -> Vec<Vec<u8>> {
+        self.read_auto().tokens.clone()
+    }
+
+    pub fn num_user(&self) -> usize {
+        self.user.len()
+    }
+
+    pub fn num_auto(&self) -> usize {
+        self.read_auto().tokens.len()
+    }
+
+    // In AFL format, so it can be passed with `-x` to the next campaign
+    pub fn save_auto(&self, path: &Path) -> io::Result<()> {
+        let mut f = io::BufWriter::new(fs::File::create(path)?);
+        for (i, token) in self.read_auto().tokens.iter().enumerate() {
+            writeln!(f, "auto_{}=\"{}\"", i, escape(token))?;
+        }
+        f.flush()
+    }
+}
+
+// `name="value"`, `"value"` or `"value"@level`, with `\\`, `\"` and `\xNN`
+// escapes in the value. Blank lines and `#` comments have no token.
+fn parse_line(line: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let line = trim(line);
+    if line.is_empty() || line[0] == b'#' {
+        return Ok(None);
+    }
+    let begin = line.iter().position(|b| *b == b'"').ok_or("missing opening quote")?;
+    let mut token = vec![];
+    let mut i = begin + 1;
+    loop {
+        match line.get(i) {
+            None => return Err("missing closing quote".to_string()),
+            Some(b'"') => break,
+            Some(b'\\') => {
+                match line.get(i + 1) {
+                    Some(b'\\') => token.push(b'\\'),
+                    Some(b'"') => token.push(b'"'),
+                    Some(b'x') => {
+                        let hex = line
+                            .get(i + 2..i + 4)
+                            .and_then(|h| std::str::from_utf8(h).ok())
+                            .and_then(|h| u8::from_str_radix(h, 16).ok())
+                            .ok_or("invalid \\x escape")?;
+                        token.push(hex);
+                        i += 2;
+                    },
+                    _ => return Err("invalid escape".to_string()),
+                }
+                i += 2;
+            },
+            Some(b) => {
+                token.push(*b);
+                i += 1;
+            },
+        }
+    }
+    Ok(Some(token))
+}
+
+fn trim(line: &[u8]) -> &[u8] {
+    let begin = line.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(line.len());
+    let end = line.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(begin, |e| e + 1);
+    &line[begin..end]
+}
+
+fn escape(token: &[u8]) -> String {
+    token
+        .iter()
+        .map(|b| match b {
+            b'\\' => "\\\\".to_string(),
+            b'"' => "\\\"".to_string(),
+            0x20..=0x7e => (*b as char).to_string(),
+            _ => format!("\\x{:02x}", b),
+        })
+        .collect()
+}
+
+// Too short or a run of one byte value, havoc finds it anyway
+fn is_useful_token(token: &[u8]) -> bool {
+    token.len() >= config::MIN_AUTO_TOKEN_LEN
+        && token.len() <= config::MAX_AUTO_TOKEN_LEN
+        && token.iter().any(|b| *b != token[0])
+}
+
+fn cond_tokens(cond: &CondStmt) -> Vec<Vec<u8>> {
+    let size = cond.base.size as usize;
+    if cond.base.op == defs::COND_FN_OP {
+        // The magic bytes come first, see `FnFuzz`
+        if size <= cond.variables.len() {
+            return vec![cond.variables[..size].to_vec()];
+        }
+        return vec![];
+    }
+    let op = cond.base.op & defs::COND_BASIC_MASK;
+    let is_eq = op == defs::COND_ICMP_EQ_OP || op == defs::COND_ICMP_NE_OP || op == defs::COND_SW_OP;
+    // Only comparisons with a constant
+    if !cond.base.is_explore() || !is_eq || (cond.base.lb1 > 0 && cond.base.lb2 > 0) {
+        return vec![];
+    }
+    if size < 2 || cond.variables.len() != size {
+        return vec![];
+    }
+    let le = cond.variables.clone();
+    let be = le.iter().rev().cloned().collect();
+    vec![le, be]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dict_parse_line() {
+        assert_eq!(parse_line(b"kw1=\"GIF89a\""), Ok(Some(b"GIF89a".to_vec())));
+        assert_eq!(parse_line(b"  \"\\x89PNG\\\\\\\"\"@2 "), Ok(Some(b"\x89PNG\\\"".to_vec())));
+        assert_eq!(parse_line(b"# comment"), Ok(None));
+        assert_eq!(parse_line(b""), Ok(None));
+        assert!(parse_line(b"kw=\"open").is_err());
+        assert!(parse_line(b"kw=\"\\xZZ\"").is_err());
+        let token = b"a\"\\\x00\xff".to_vec();
+        let line = format!("t=\"{}\"", escape(&token));
+        assert_eq!(parse_line(line.as_bytes()), Ok(Some(token)));
+    }
+
+    #[test]
+    fn dict_auto_tokens() {
+        let dict = Dictionary::default();
+        dict.add_auto_tokens(vec![b"IHDR".to_vec(), b"IHDR".to_vec(), b"aaaa".to_vec(), b"x".to_vec()]);
+        assert_eq!(dict.tokens(), vec![b"IHDR".to_vec()]);
+        assert!(!dict.is_empty());
+    }
+}
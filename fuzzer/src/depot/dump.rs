@@ -1,10 +1,23 @@
 use super::*;
+use crate::dyncfg::fparse::write_targets_file;
 use angora_common::defs;
 use std::{fs, io::prelude::*};
 
+impl Depot {
+    // Write the CFG learned so far in the format of the targets file (`-c`).
+    pub fn dump_cfg(&self) {
+        let dir = self.dirs.inputs_dir.parent().unwrap();
+        let cfg_file = self.cfg.read().unwrap().to_cfg_file();
+        if let Err(e) = write_targets_file(&dir.join(defs::LEARNED_CFG_FILE), &cfg_file) {
+            warn!("Could not dump learned cfg: {:?}", e);
+        }
+    }
+}
+
 impl Drop for Depot {
     fn drop(&mut self) {
         info!("dump constraints and chart..");
+        self.dump_cfg();
         let dir = self.dirs.inputs_dir.parent().unwrap();
 
         let mut log_q = fs::File::create(dir.join(defs::COND_QUEUE_FILE)).unwrap();
@@ -1,9 +1,23 @@
+mod checksums;
+mod crash_tags;
 mod depot;
 mod depot_dir;
+mod dict;
 mod dump;
 mod file;
 mod qpriority;
+mod schedule;
 mod sync;
+mod targets;
+mod trees;
 
-pub use self::{depot::Depot, file::*, sync::*};
-use self::{depot_dir::DepotDir, qpriority::QPriority};
+pub use self::{
+    depot::{Depot, DepotSnapshot},
+    dict::Dictionary,
+    file::*,
+    qpriority::{QPriority, INIT_DISTANCE},
+    schedule::{parse_schedule, SchedulePolicy},
+    sync::*,
+    targets::TargetInfo,
+};
+use self::depot_dir::DepotDir;
@@ -1,76 +1,43 @@
-use angora_common::defs;
-use std::{self, cmp::Ordering, fmt};
-
-const INIT_PRIORITY: u16 = 0;
-const AFL_INIT_PRIORITY: u16 = 0;
-const DONE_PRIORITY: u16 = std::u16::MAX;
-const INIT_DISTANCE: u32 = std::u32::MAX;
+use std::{cmp::Ordering, fmt};
+
+const DONE_RANK: u64 = u64::MAX;
+pub const INIT_DISTANCE: u32 = u32::MAX;
+
+// Scheduling state of a cond in the queue. The rank is set by the
+// SchedulePolicy, the cond with the lowest rank is fuzzed first and the
+// distance breaks ties.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct QPriority {
+    pub rank: u64,
+    pub distance: u32,
+    // Rounds the cond has been fuzzed, and the ones that found new paths
+    pub rounds: u32,
+    pub rewards: u32,
+}
 
-#[derive(Eq, PartialEq, Clone, Copy, Debug)]
-pub struct QPriority(u16, u32);
 impl QPriority {
-    pub fn inc(&self, op: u32) -> Self {
-        if op == defs::COND_AFL_OP {
-            self.afl_inc()
-        } else {
-            self.base_inc()
+    pub fn new(rank: u64, distance: u32) -> Self {
+        QPriority {
+            rank,
+            distance,
+            rounds: 0,
+            rewards: 0,
         }
     }
 
-    pub fn new_distance(&self, distance: u32) -> Self {
-        QPriority(self.0, distance)
-    }
-
-    fn base_inc(&self) -> Self {
-        QPriority(self.0 + 1, self.1)
-    }
-
-    fn afl_inc(&self) -> Self {
-        QPriority(self.0 + 2, self.1)
-    }
-
-    pub fn init(op: u32) -> Self {
-        Self::init_distance(op, INIT_DISTANCE)
-    }
-
-    pub fn init_distance(op: u32, distance: u32) -> Self {
-        if op == defs::COND_AFL_OP {
-            Self::afl_init(distance)
-        } else {
-            Self::base_init(distance)
-        }
-    }
-
-
-    fn base_init(distance: u32) -> Self {
-        QPriority(INIT_PRIORITY, distance)
-    }
-
-    fn afl_init(distance: u32) -> Self {
-        QPriority(AFL_INIT_PRIORITY, distance)
-    }
-
     pub fn done() -> Self {
-        QPriority(DONE_PRIORITY, INIT_DISTANCE)
+        Self::new(DONE_RANK, INIT_DISTANCE)
     }
 
     pub fn is_done(&self) -> bool {
-        self.0 == DONE_PRIORITY
+        self.rank == DONE_RANK
     }
 }
 
 // Make the queue get smallest priority first.
 impl Ord for QPriority {
     fn cmp(&self, other: &QPriority) -> Ordering {
-        match self.0.cmp(&other.0) {
-            Ordering::Greater => Ordering::Less,
-            Ordering::Less => Ordering::Greater,
-            Ordering::Equal => match self.1.cmp(&other.1) {
-                Ordering::Greater => Ordering::Less,
-                Ordering::Less => Ordering::Greater,
-                Ordering::Equal => Ordering::Equal,
-            }
-        }
+        (other.rank, other.distance).cmp(&(self.rank, self.distance))
     }
 }
 
@@ -82,6 +49,6 @@ impl PartialOrd for QPriority {
 
 impl fmt::Display for QPriority {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.rank)
     }
 }
@@ -0,0 +1,315 @@
+// In which order the conds in the depot queue are fuzzed. A policy ranks the
+// conds, and ranks a cond again after every round it has been fuzzed.
+
+use super::qpriority::{QPriority, INIT_DISTANCE};
+use angora_common::{config, defs};
+use std::{
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+// Ranks are fractions of a round, so the policies can fuzz some conds more often
+const RANK_SCALE: f64 = 1000.0;
+
+pub trait SchedulePolicy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Priority of a new cond, new conds are fuzzed first.
+    fn init(&self, _op: u32, distance: u32) -> QPriority {
+        QPriority::new(0, distance)
+    }
+
+    // Priority after a round of fuzzing the cond, which found num_new new paths.
+    fn fuzzed(&self, p: QPriority, op: u32, num_new: usize) -> QPriority;
+
+    // Like fuzzed, for a target with the given weight. A round advances the
+    // rank of a heavier target less, so it gets more of the rounds.
+    fn fuzzed_target(&self, p: QPriority, op: u32, num_new: usize, weight: f64) -> QPriority {
+        let next = self.fuzzed(p, op, num_new);
+        if weight <= 0.0 || weight == 1.0 {
+            return next;
+        }
+        // Spread a fractional increment over the rounds
+        let inc = next.rank.saturating_sub(p.rank) as f64 / weight;
+        let r = p.rounds as f64;
+        let inc = ((r + 1.0) * inc).floor() - (r * inc).floor();
+        QPriority {
+            rank: p.rank.saturating_add(inc as u64),
+            ..next
+        }
+    }
+
+    // Whether the ranks of the conds waiting in the queue change as the other
+    // conds are fuzzed, they are ranked again with rerank every
+    // RERANK_INTERVAL seconds then.
+    fn reranks(&self) -> bool {
+        false
+    }
+
+    // Priority of a cond waiting in the queue, weight is the one of a target
+    fn rerank(&self, p: QPriority, _weight: Option<f64>) -> QPriority {
+        p
+    }
+
+    fn new_distance(&self, p: QPriority, distance: u32) -> QPriority {
+        QPriority { distance, ..p }
+    }
+
+    // Shown in the stats
+    fn describe(&self) -> String {
+        self.name().to_string()
+    }
+}
+
+fn next_round(p: QPriority, num_new: usize) -> QPriority {
+    QPriority {
+        rounds: p.rounds.saturating_add(1),
+        rewards: p.rewards.saturating_add((num_new > 0) as u32),
+        ..p
+    }
+}
+
+// Round-robin over the conds that have been fuzzed the least often, the
+// closest to the targets first. AFL conds count twice.
+pub struct RoundRobinSchedule;
+
+impl SchedulePolicy for RoundRobinSchedule {
+    fn name(&self) -> &'static str {
+        "round_robin"
+    }
+
+    fn fuzzed(&self, p: QPriority, op: u32, num_new: usize) -> QPriority {
+        let inc = if op == defs::COND_AFL_OP { 2 } else { 1 };
+        QPriority {
+            rank: p.rank + inc,
+            ..next_round(p, num_new)
+        }
+    }
+}
+
+// Simulated annealing power schedule of AFLGo. The energy of a cond is
+//   (1 - d) * (1 - T) + 0.5 * T
+// with d the distance normalized to [0, 1] and the temperature T = 20^(-t/t_x)
+// decaying over the campaign time t. A round of a cond with energy e advances
+// its rank by 1 / e: all conds are fuzzed alike at first, the closest ones
+// get most of the rounds later.
+pub struct AnnealingSchedule {
+    start_time: Instant,
+    min_distance: AtomicU32,
+    max_distance: AtomicU32,
+}
+
+impl AnnealingSchedule {
+    pub fn new() -> Self {
+        Self {
+            start_time: Instant::now(),
+            min_distance: AtomicU32::new(u32::MAX),
+            max_distance: AtomicU32::new(0),
+        }
+    }
+
+    fn temperature(&self) -> f64 {
+        let t = self.start_time.elapsed().as_secs_f64();
+        20f64.powf(-t / config::ANNEALING_EXPLOIT_TIME as f64)
+    }
+
+    fn observe(&self, distance: u32) {
+        if distance != INIT_DISTANCE {
+            self.min_distance.fetch_min(distance, Ordering::Relaxed);
+            self.max_distance.fetch_max(distance, Ordering::Relaxed);
+        }
+    }
+
+    fn energy(&self, distance: u32) -> f64 {
+        let min = self.min_distance.load(Ordering::Relaxed);
+        let max = self.max_distance.load(Ordering::Relaxed);
+        let d = if distance == INIT_DISTANCE || min >= max {
+            1.0
+        } else {
+            (distance.saturating_sub(min) as f64 / (max - min) as f64).min(1.0)
+        };
+        let t = self.temperature();
+        ((1.0 - d) * (1.0 - t) + 0.5 * t).max(config::ANNEALING_MIN_ENERGY)
+    }
+}
+
+impl SchedulePolicy for AnnealingSchedule {
+    fn name(&self) -> &'static str {
+        "annealing"
+    }
+
+    fn init(&self, _op: u32, distance: u32) -> QPriority {
+        self.observe(distance);
+        QPriority::new(0, distance)
+    }
+
+    fn fuzzed(&self, p: QPriority, _op: u32, num_new: usize) -> QPriority {
+        let inc = (RANK_SCALE / self.energy(p.distance)) as u64;
+        QPriority {
+            rank: p.rank.saturating_add(inc),
+            ..next_round(p, num_new)
+        }
+    }
+
+    fn new_distance(&self, p: QPriority, distance: u32) -> QPriority {
+        self.observe(distance);
+        QPriority { distance, ..p }
+    }
+
+    fn describe(&self) -> String {
+        format!("{} (temperature: {:.2})", self.name(), self.temperature())
+    }
+}
+
+// UCB1 multi-armed bandit, every cond is an arm and a round that found new
+// paths is a reward. The cond with the highest upper confidence bound
+//   rewards / rounds + c * sqrt(2 * ln(total rounds) / rounds)
+// has the lowest rank.
+pub struct BanditSchedule {
+    total_rounds: AtomicU64,
+    total_rewards: AtomicU64,
+}
+
+impl BanditSchedule {
+    pub fn new() -> Self {
+        Self {
+            total_rounds: AtomicU64::new(0),
+            total_rewards: AtomicU64::new(0),
+        }
+    }
+
+    fn ucb(&self, rounds: u32, rewards: u32) -> f64 {
+        let total = self.total_rounds.load(Ordering::Relaxed).max(1) as f64;
+        let n = rounds.max(1) as f64;
+        rewards as f64 / n + config::BANDIT_EXPLORATION * (2.0 * total.ln() / n).sqrt()
+    }
+
+    // The rank is not a sum of rounds, a target scales the bound instead
+    fn rank(&self, p: QPriority, weight: Option<f64>) -> QPriority {
+        let mut rank = RANK_SCALE / (self.ucb(p.rounds, p.rewards) + 1e-3);
+        if let Some(w) = weight.filter(|w| *w > 0.0) {
+            rank /= w;
+        }
+        QPriority {
+            rank: rank as u64,
+            ..p
+        }
+    }
+}
+
+impl SchedulePolicy for BanditSchedule {
+    fn name(&self) -> &'static str {
+        "bandit"
+    }
+
+    fn fuzzed(&self, p: QPriority, op: u32, num_new: usize) -> QPriority {
+        self.fuzzed_target(p, op, num_new, 1.0)
+    }
+
+    fn fuzzed_target(&self, p: QPriority, _op: u32, num_new: usize, weight: f64) -> QPriority {
+        self.total_rounds.fetch_add(1, Ordering::Relaxed);
+        if num_new > 0 {
+            self.total_rewards.fetch_add(1, Ordering::Relaxed);
+        }
+        self.rank(next_round(p, num_new), Some(weight))
+    }
+
+    // The bound of every cond grows with the total rounds, not only the
+    // bound of the cond that was fuzzed
+    fn reranks(&self) -> bool {
+        true
+    }
+
+    fn rerank(&self, p: QPriority, weight: Option<f64>) -> QPriority {
+        if p.is_done() || p.rounds == 0 {
+            return p;
+        }
+        self.rank(p, weight)
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "{} (rewards: {} / {})",
+            self.name(),
+            self.total_rewards.load(Ordering::Relaxed),
+            self.total_rounds.load(Ordering::Relaxed)
+        )
+    }
+}
+
+pub fn parse_schedule(s: &str) -> Arc<dyn SchedulePolicy> {
+    match s {
+        "round_robin" => Arc::new(RoundRobinSchedule),
+        "annealing" => Arc::new(AnnealingSchedule::new()),
+        "bandit" => Arc::new(BanditSchedule::new()),
+        _ => Arc::new(RoundRobinSchedule),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_round_robin() {
+        let s = RoundRobinSchedule;
+        let a = s.fuzzed(s.init(0, 10), 0, 0);
+        let b = s.init(0, 20);
+        // Fuzzed less often first, then the closest
+        assert!(b > a);
+        assert!(s.fuzzed(b, 0, 1) < a);
+        assert_eq!(s.fuzzed(b, defs::COND_AFL_OP, 1).rank, 2);
+        assert_eq!(s.fuzzed(b, 0, 1).rewards, 1);
+
+        // A target with weight 2 advances every other round
+        let mut t = s.init(0, 10);
+        for _ in 0..4 {
+            t = s.fuzzed_target(t, 0, 0, 2.0);
+        }
+        assert_eq!((t.rank, t.rounds), (2, 4));
+        assert_eq!(s.fuzzed_target(b, 0, 0, 0.5).rank, 2);
+    }
+
+    #[test]
+    fn schedule_annealing() {
+        let s = AnnealingSchedule::new();
+        let (near, far) = (s.init(0, 10), s.init(0, 1000));
+        assert!(s.temperature() > 0.99);
+        // Hot: all conds get about the same energy, whatever their distance
+        assert!(s.fuzzed(far, 0, 0).rank - s.fuzzed(near, 0, 0).rank <= 1);
+        // Cold: the close conds get more rounds
+        let mut cold = AnnealingSchedule::new();
+        cold.start_time -= std::time::Duration::from_secs(10 * config::ANNEALING_EXPLOIT_TIME);
+        cold.init(0, 10);
+        cold.init(0, 1000);
+        assert!(cold.fuzzed(near, 0, 0).rank < cold.fuzzed(far, 0, 0).rank);
+    }
+
+    #[test]
+    fn schedule_bandit() {
+        let s = BanditSchedule::new();
+        let mut lucky = s.init(0, 10);
+        let mut unlucky = s.init(0, 10);
+        for _ in 0..10 {
+            lucky = s.fuzzed(lucky, 0, 1);
+            unlucky = s.fuzzed(unlucky, 0, 0);
+        }
+        assert!(lucky > unlucky);
+        assert!(s.init(0, 10) > lucky);
+        let heavy = s.fuzzed_target(s.init(0, 10), 0, 0, 4.0);
+        assert!(heavy > s.fuzzed(s.init(0, 10), 0, 0));
+        // A cond that waits gets a larger bound as the others are fuzzed
+        let waiting = s.fuzzed(s.init(0, 10), 0, 0);
+        for _ in 0..100 {
+            s.fuzzed(s.init(0, 10), 0, 0);
+        }
+        assert!(s.rerank(waiting, None) > waiting);
+        assert_eq!(s.rerank(s.init(0, 10), None), s.init(0, 10));
+        for name in &["round_robin", "annealing", "bandit"] {
+            assert_eq!(parse_schedule(name).name(), *name);
+        }
+    }
+}
@@ -1,10 +1,14 @@
 use super::*;
-use crate::executor::Executor;
+use crate::{
+    cond_stmt::CondStmt,
+    dyncfg::fparse::{parse_targets_file, write_targets_file},
+    executor::Executor,
+};
 use angora_common::{config, defs};
 use std::{
     collections::HashMap,
-    fs,
-    path::Path,
+    fs, io,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -14,21 +18,19 @@ use std::{
 pub fn sync_depot(executor: &mut Executor, running: Arc<AtomicBool>, dir: &Path) {
     executor.local_stats.clear();
     let seed_dir = dir.read_dir().expect("read_dir call failed");
-    for entry in seed_dir {
-        if let Ok(entry) = entry {
-            if !running.load(Ordering::SeqCst) {
-                break;
-            }
-            let path = &entry.path();
-            if path.is_file() {
-                let file_len =
-                    fs::metadata(path).expect("Could not fetch metadata.").len() as usize;
-                if file_len < config::MAX_INPUT_LEN {
-                    let buf = read_from_file(path);
-                    executor.run_sync(&buf);
-                } else {
-                    warn!("Seed discarded, too long: {:?}", path);
-                }
+    for entry in seed_dir.flatten() {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        let path = &entry.path();
+        if path.is_file() {
+            let file_len =
+                fs::metadata(path).expect("Could not fetch metadata.").len() as usize;
+            if file_len < config::MAX_INPUT_LEN {
+                let buf = read_from_file(path);
+                executor.run_sync(&buf);
+            } else {
+                warn!("Seed discarded, too long: {:?}", path);
             }
         }
     }
@@ -47,17 +49,15 @@ pub fn sync_afl(
     executor.local_stats.clear();
 
     if let Ok(entries) = sync_dir.read_dir() {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let entry_path = entry.path();
-                if entry_path.is_dir() {
-                    let file_name = entry.file_name().into_string();
-                    if let Ok(name) = file_name {
-                        if !name.contains(defs::ANGORA_DIR_NAME) && !name.starts_with(".") {
-                            let path = entry_path.join("queue");
-                            if path.is_dir() {
-                                sync_one_afl_dir(executor, running.clone(), &path, &name, sync_ids);
-                            }
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                let file_name = entry.file_name().into_string();
+                if let Ok(name) = file_name {
+                    if !name.contains(defs::ANGORA_DIR_NAME) && !name.starts_with(".") {
+                        let path = entry_path.join("queue");
+                        if path.is_dir() {
+                            sync_one_afl_dir(executor, running.clone(), &path, &name, sync_ids);
                         }
                     }
                 }
@@ -96,23 +96,21 @@ fn sync_one_afl_dir(
     let seed_dir = sync_dir
         .read_dir()
         .expect("read_dir call failed while syncing afl ..");
-    for entry in seed_dir {
-        if let Ok(entry) = entry {
-            if !running.load(Ordering::SeqCst) {
-                break;
-            }
-            let path = &entry.path();
-            if path.is_file() {
-                if let Some(id) = get_afl_id(&entry) {
-                    if id >= min_id {
-                        let file_len = fs::metadata(path).unwrap().len() as usize;
-                        if file_len < config::MAX_INPUT_LEN {
-                            let buf = read_from_file(path);
-                            executor.run_sync(&buf);
-                        }
-                        if id > max_id {
-                            max_id = id;
-                        }
+    for entry in seed_dir.flatten() {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        let path = &entry.path();
+        if path.is_file() {
+            if let Some(id) = get_afl_id(&entry) {
+                if id >= min_id {
+                    let file_len = fs::metadata(path).unwrap().len() as usize;
+                    if file_len < config::MAX_INPUT_LEN {
+                        let buf = read_from_file(path);
+                        executor.run_sync(&buf);
+                    }
+                    if id > max_id {
+                        max_id = id;
                     }
                 }
             }
@@ -121,3 +119,288 @@ fn sync_one_afl_dir(
 
     sync_ids.insert(sync_name.to_string(), max_id + 1);
 }
+
+// The tracked conds of the inputs, as `conds/id:NNNNNN`, so the peers do not
+// track the inputs again. They are only saved while syncing.
+impl Depot {
+    pub fn keep_conds(&self) {
+        match fs::create_dir_all(&self.dirs.conds_dir) {
+            Ok(_) => self.keeps_conds.store(true, Ordering::Relaxed),
+            Err(e) => warn!("Could not create conds directory: {:?}", e),
+        }
+    }
+
+    pub fn save_conds(&self, id: usize, conds: &[CondStmt]) {
+        if !self.keeps_conds.load(Ordering::Relaxed) {
+            return;
+        }
+        let path = get_file_name(&self.dirs.conds_dir, id);
+        let tmp_path = path.with_extension("tmp");
+        let res = serde_json::to_vec(conds)
+            .map_err(io::Error::from)
+            .and_then(|data| fs::write(&tmp_path, data))
+            .and_then(|_| fs::rename(&tmp_path, &path));
+        if let Err(e) = res {
+            warn!("Could not save the conds of input {}: {:?}", id, e);
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct PeerSyncState {
+    num_exported: usize,
+    // Id of the next input to import from every peer
+    imported: HashMap<String, usize>,
+}
+
+// Sync with other ParmeSan instances through a shared directory. Every
+// instance owns `<sync_dir>/<sync_id>` and only reads the directories of the
+// others:
+//   queue/id:NNNNNN  its inputs, renamed into place once written completely
+//   conds/id:NNNNNN  the conds it tracked on them, as JSON, written before
+//                    the input
+//   cfg.json         its learned CFG, magic bytes and solved targets, as a
+//                    targets file
+//   sync_state.json  how far it has exported and imported, to resume
+// The inputs of a peer are run like the seeds, the ones with a new path are
+// saved to the local depot with the conds of the peer, so their conds are
+// fuzzed here too. Only the inputs without conds are tracked.
+pub struct PeerSync {
+    sync_dir: PathBuf,
+    id: String,
+    dir: PathBuf,
+    state: PeerSyncState,
+    // The input with the last id may still be written by a fuzzing thread,
+    // so inputs are exported in the round after they were counted.
+    num_counted: usize,
+}
+
+impl PeerSync {
+    pub fn new(sync_dir: &Path, id: &str, depot: &Depot) -> Self {
+        let dir = sync_dir.join(id);
+        fs::create_dir_all(dir.join(defs::INPUTS_DIR)).expect("Could not create sync directory");
+        fs::create_dir_all(dir.join(defs::CONDS_DIR)).expect("Could not create sync directory");
+        depot.keep_conds();
+        let state: PeerSyncState = fs::read(dir.join(defs::SYNC_STATE_FILE))
+            .ok()
+            .and_then(|buf| serde_json::from_slice(&buf).ok())
+            .unwrap_or_default();
+        let num_counted = state.num_exported;
+        Self {
+            sync_dir: sync_dir.to_path_buf(),
+            id: id.to_string(),
+            dir,
+            state,
+            num_counted,
+        }
+    }
+
+    pub fn sync(&mut self, executor: &mut Executor, depot: &Depot, running: Arc<AtomicBool>) {
+        executor.rebind_forksrv();
+        executor.local_stats.clear();
+
+        if let Err(e) = self.export(depot) {
+            warn!("Could not export to sync directory: {:?}", e);
+        }
+        if let Ok(entries) = self.sync_dir.read_dir() {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let path = entry.path();
+                if name == self.id || name.starts_with('.') || !path.is_dir() {
+                    continue;
+                }
+                self.import_cfg(depot, &path);
+                self.import_inputs(executor, running.clone(), &path, &name);
+            }
+        }
+        if let Err(e) = self.save_state() {
+            warn!("Could not save sync state: {:?}", e);
+        }
+        let n: usize = executor.local_stats.num_inputs.into();
+        info!("sync {} file from other instances.", n);
+
+        executor.update_log();
+    }
+
+    fn export(&mut self, depot: &Depot) -> io::Result<()> {
+        let queue_dir = self.dir.join(defs::INPUTS_DIR);
+        let conds_dir = self.dir.join(defs::CONDS_DIR);
+        for id in self.state.num_exported..self.num_counted {
+            let conds_path = get_file_name(&depot.dirs.conds_dir, id);
+            if conds_path.is_file() {
+                let tmp_path = conds_dir.join(format!(".{}.tmp", id));
+                fs::copy(conds_path, &tmp_path)?;
+                fs::rename(tmp_path, get_file_name(&conds_dir, id))?;
+            }
+            let tmp_path = queue_dir.join(format!(".{}.tmp", id));
+            fs::copy(get_file_name(&depot.dirs.inputs_dir, id), &tmp_path)?;
+            fs::rename(tmp_path, get_file_name(&queue_dir, id))?;
+        }
+        self.state.num_exported = self.num_counted;
+        self.num_counted = depot.num_inputs.load(Ordering::Relaxed);
+
+        let cfg_file = depot.cfg.read().unwrap().to_cfg_file();
+        let tmp_path = self.dir.join(format!("{}.tmp", defs::SYNC_CFG_FILE));
+        write_targets_file(&tmp_path, &cfg_file)?;
+        fs::rename(tmp_path, self.dir.join(defs::SYNC_CFG_FILE))
+    }
+
+    fn import_cfg(&self, depot: &Depot, peer_dir: &Path) {
+        let path = peer_dir.join(defs::SYNC_CFG_FILE);
+        if !path.is_file() {
+            return;
+        }
+        let data = match parse_targets_file(&path) {
+            Ok(d) => d,
+            Err(e) => {
+                warn!("Could not read cfg of peer {:?}: {:?}", peer_dir, e);
+                return;
+            },
+        };
+        let mut cfg = depot.cfg.write().unwrap();
+        for edge in data.edges {
+            if !cfg.has_edge(edge) {
+                cfg.add_edge(edge);
+            }
+        }
+        for (callsite, edges) in data.callsite_edges {
+            for edge in edges {
+                cfg.set_edge_indirect(edge, callsite);
+            }
+        }
+        for (edge, fixed) in data.magic_bytes {
+            cfg.add_magic_bytes(edge, fixed);
+        }
+        for cmp in data.solved_targets {
+            if cfg.is_target(cmp) && !cfg.is_solved(cmp) {
+                info!("Target {} was solved by {:?}", cmp, peer_dir);
+                cfg.remove_target(cmp);
+            }
+        }
+    }
+
+    fn import_inputs(&mut self, executor: &mut Executor, running: Arc<AtomicBool>, peer_dir: &Path, name: &str) {
+        let mut next_id = *self.state.imported.get(name).unwrap_or(&0);
+        let mut inputs: Vec<(usize, PathBuf)> = match peer_dir.join(defs::INPUTS_DIR).read_dir() {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|e| get_input_id(&e).map(|id| (id, e.path())))
+                .filter(|(id, _)| *id >= next_id)
+                .collect(),
+            Err(_) => return,
+        };
+        inputs.sort();
+
+        for (id, path) in inputs {
+            if !running.load(Ordering::SeqCst) {
+                break;
+            }
+            let file_len = fs::metadata(&path).map(|m| m.len() as usize).unwrap_or(0);
+            if file_len < config::MAX_INPUT_LEN {
+                let conds = read_peer_conds(peer_dir, id);
+                executor.run_sync_with_conds(&read_from_file(&path), conds);
+            }
+            next_id = id + 1;
+        }
+        self.state.imported.insert(name.to_string(), next_id);
+    }
+
+    fn save_state(&self) -> io::Result<()> {
+        let tmp_path = self.dir.join(format!("{}.tmp", defs::SYNC_STATE_FILE));
+        fs::write(&tmp_path, serde_json::to_vec(&self.state)?)?;
+        fs::rename(tmp_path, self.dir.join(defs::SYNC_STATE_FILE))
+    }
+}
+
+// None if the peer did not export them, the input is tracked then
+fn read_peer_conds(peer_dir: &Path, id: usize) -> Option<Vec<CondStmt>> {
+    let data = fs::read(get_file_name(&peer_dir.join(defs::CONDS_DIR), id)).ok()?;
+    match serde_json::from_slice(&data) {
+        Ok(conds) => Some(conds),
+        Err(e) => {
+            warn!("Could not parse the conds of input {} of peer {:?}: {:?}", id, peer_dir, e);
+            None
+        },
+    }
+}
+
+// "id:000042" -> 42, other files are skipped
+fn get_input_id(f: &fs::DirEntry) -> Option<usize> {
+    let name = f.file_name().into_string().ok()?;
+    name.strip_prefix("id:")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dyncfg::{cfg::ControlFlowGraph, metric::parse_distance_metric};
+    use angora_common::tag::TagSeg;
+    use std::{env, process, sync::RwLock};
+
+    #[test]
+    fn peer_sync_cfg() {
+        let tmp = env::temp_dir().join(format!("parmesan-peer-sync-{}", process::id()));
+        let new_depot = |name: &str| {
+            let data = serde_json::from_str(r#"{"targets": [1100, 1200], "edges": [[0,10], [10,20]], "id_mapping": {"10": [1000], "20": [1100], "40": [1200]}}"#).unwrap();
+            let cfg = ControlFlowGraph::new(data, parse_distance_metric("harmonic"));
+            fs::create_dir_all(tmp.join(name)).unwrap();
+            Depot::new(tmp.join("seeds"), &tmp.join(name), RwLock::new(cfg), parse_schedule("round_robin"), Default::default())
+        };
+        let (a, b) = (new_depot("a"), new_depot("b"));
+        {
+            let mut cfg = a.cfg.write().unwrap();
+            cfg.add_edge((20, 30));
+            cfg.add_edge((30, 40));
+            cfg.set_magic_bytes((20, 30), &vec![7, 8], &vec![TagSeg { sign: false, begin: 1, end: 2 }]);
+            cfg.remove_target(1100);
+        }
+
+        let sync_dir = tmp.join("sync");
+        let mut sync_a = PeerSync::new(&sync_dir, "a", &a);
+        sync_a.export(&a).unwrap();
+        let sync_b = PeerSync::new(&sync_dir, "b", &b);
+        sync_b.import_cfg(&b, &sync_dir.join("a"));
+
+        let cfg = b.cfg.read().unwrap();
+        assert!(cfg.has_edge((30, 40)));
+        assert_eq!(cfg.get_magic_bytes((20, 30)), vec![(1, 8)]);
+        assert!(cfg.is_solved(1100));
+        assert!(cfg.has_path_to_target(1000));
+        drop(cfg);
+        drop((a, b));
+        fs::remove_dir_all(&tmp).unwrap();
+    }
+
+    #[test]
+    fn peer_sync_conds() {
+        let tmp = env::temp_dir().join(format!("parmesan-peer-conds-{}", process::id()));
+        let data = serde_json::from_str(r#"{"targets": [], "edges": [], "id_mapping": {}}"#).unwrap();
+        let cfg = ControlFlowGraph::new(data, parse_distance_metric("harmonic"));
+        fs::create_dir_all(tmp.join("a")).unwrap();
+        let a = Depot::new(tmp.join("seeds"), &tmp.join("a"), RwLock::new(cfg), parse_schedule("round_robin"), Default::default());
+
+        let sync_dir = tmp.join("sync");
+        let mut sync_a = PeerSync::new(&sync_dir, "a", &a);
+        let mut cond = CondStmt::new();
+        cond.base.cmpid = 42;
+        cond.offsets = vec![TagSeg { sign: false, begin: 0, end: 4 }];
+        // Input 0 was tracked, input 1 was saved before it was
+        for id in 0..2 {
+            fs::write(get_file_name(&a.dirs.inputs_dir, id), [id as u8]).unwrap();
+        }
+        a.save_conds(0, &[cond.clone()]);
+        a.num_inputs.store(2, Ordering::Relaxed);
+        sync_a.export(&a).unwrap();
+        sync_a.export(&a).unwrap();
+
+        let peer_dir = sync_dir.join("a");
+        assert!(get_file_name(&peer_dir.join(defs::INPUTS_DIR), 1).is_file());
+        // The executor skips the track run when the conds are given
+        let conds = read_peer_conds(&peer_dir, 0).unwrap();
+        assert_eq!(conds, vec![cond.clone()]);
+        assert_eq!(conds[0].offsets, cond.offsets);
+        assert!(read_peer_conds(&peer_dir, 1).is_none());
+        drop(a);
+        fs::remove_dir_all(&tmp).unwrap();
+    }
+}
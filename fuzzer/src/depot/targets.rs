@@ -0,0 +1,62 @@
+use super::*;
+use crate::dyncfg::cfg::CmpId;
+use std::{collections::HashMap, sync::MutexGuard};
+
+// What happened at a target of the directed campaign.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TargetInfo {
+    // Seconds after the fuzzer was started
+    pub first_reached: Option<u64>,
+    // Inputs in the queue whose track run hit the target, and the first one
+    #[serde(default)]
+    pub num_inputs: usize,
+    #[serde(default)]
+    pub first_input: Option<usize>,
+    #[serde(default)]
+    last_input: Option<usize>,
+    // Crashes the sanitized builds caught while fuzzing the target
+    pub num_crashes: usize,
+}
+
+impl Depot {
+    fn lock_targets(&self) -> MutexGuard<'_, HashMap<CmpId, TargetInfo>> {
+        match self.targets.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    pub fn reach_target(&self, cmpid: CmpId, id: usize) {
+        let mut targets = self.lock_targets();
+        let info = targets.entry(cmpid).or_default();
+        if info.first_reached.is_none() {
+            info!("Reached target {} with input {}", cmpid, id);
+            info.first_reached = Some(self.start_time.elapsed().as_secs());
+        }
+        if info.last_input != Some(id) {
+            info.last_input = Some(id);
+            info.first_input.get_or_insert(id);
+            info.num_inputs += 1;
+        }
+    }
+
+    // Only for the crashes of a sanitized build, which runs target conds only
+    pub fn crash_at_target(&self, cmpid: CmpId) {
+        if !self.cfg.read().unwrap().is_target(cmpid) {
+            return;
+        }
+        let mut targets = self.lock_targets();
+        targets.entry(cmpid).or_default().num_crashes += 1;
+    }
+
+    pub fn get_target_infos(&self) -> HashMap<CmpId, TargetInfo> {
+        self.lock_targets().clone()
+    }
+
+    pub fn restore_target_infos(&self, infos: HashMap<CmpId, TargetInfo>) {
+        *self.lock_targets() = infos;
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+use crate::grammar::Tree;
+use angora_common::config;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::PathBuf,
+    sync::MutexGuard,
+};
+
+// Derivation trees of the inputs in grammar mode, as `trees/id:NNNNNN`. The
+// trees are read for every round, so the last ones are kept parsed.
+impl Depot {
+    fn lock_trees(&self) -> MutexGuard<'_, HashMap<usize, Tree>> {
+        match self.trees.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+                poisoned.into_inner()
+            },
+        }
+    }
+
+    fn cache_tree(&self, id: usize, tree: &Tree) {
+        let mut trees = self.lock_trees();
+        if trees.len() >= config::GRAMMAR_TREE_CACHE_SIZE {
+            trees.clear();
+        }
+        trees.insert(id, tree.clone());
+    }
+
+    fn tree_path(&self, id: usize) -> PathBuf {
+        get_file_name(&self.dirs.trees_dir, id)
+    }
+
+    pub fn save_tree(&self, id: usize, tree: &Tree) {
+        let path = self.tree_path(id);
+        let tmp_path = path.with_extension("tmp");
+        let res = fs::create_dir_all(&self.dirs.trees_dir)
+            .and_then(|_| serde_json::to_vec(tree).map_err(io::Error::from))
+            .and_then(|data| fs::write(&tmp_path, data))
+            .and_then(|_| fs::rename(&tmp_path, &path));
+        match res {
+            Ok(_) => self.cache_tree(id, tree),
+            Err(e) => warn!("Could not save the tree of input {}: {:?}", id, e),
+        }
+    }
+
+    pub fn get_tree(&self, id: usize) -> Option<Tree> {
+        if let Some(tree) = self.lock_trees().get(&id) {
+            return Some(tree.clone());
+        }
+        let data = fs::read(self.tree_path(id)).ok()?;
+        match serde_json::from_slice(&data) {
+            Ok(tree) => {
+                self.cache_tree(id, &tree);
+                Some(tree)
+            },
+            Err(e) => {
+                warn!("Could not parse the tree of input {}: {:?}", id, e);
+                None
+            },
+        }
+    }
+
+    pub fn has_tree(&self, id: usize) -> bool {
+        self.tree_path(id).exists()
+    }
+}
@@ -1,12 +1,11 @@
 use std::f64;
-use math::mean;
 use petgraph::graphmap::DiGraphMap;
-use std::collections::{HashSet, HashMap};
-use std::time::Instant;
-use petgraph::visit::{Reversed, Bfs, Dfs};
+use std::collections::{HashSet, HashMap, VecDeque, hash_map::Entry};
 use petgraph::{Incoming, Outgoing};
 use angora_common::tag::TagSeg;
-use super::fparse::CfgFile;
+use std::sync::Arc;
+use super::fparse::{CfgFile, CfgSnapshot, TargetGroup};
+use super::metric::{DistanceMetric, HarmonicMetric};
 
 pub type CmpId = u32;
 pub type BbId = u32;
@@ -15,8 +14,8 @@ pub type Edge = (BbId, BbId);
 pub type Score = u32;
 pub type FixedBytes = Vec<(usize, u8)>;
 
-const TARGET_SCORE: Score = 0;
-const UNDEF_SCORE: Score = std::u32::MAX;
+pub const TARGET_SCORE: Score = 0;
+pub const UNDEF_SCORE: Score = std::u32::MAX;
 
 
 #[derive(Clone)]
@@ -31,6 +30,9 @@ pub struct ControlFlowGraph {
     callsite_dominators: HashMap<CallSiteId, HashSet<CmpId>>,
     dominator_cmps: HashSet<CmpId>,
     magic_bytes: HashMap<Edge, FixedBytes>,
+    target_weights: HashMap<CmpId, f64>,
+    target_groups: HashMap<String, TargetGroup>,
+    metric: Arc<dyn DistanceMetric>,
 }
 
 
@@ -38,27 +40,43 @@ pub struct ControlFlowGraph {
 // A CFG of branches (CMPs)
 impl ControlFlowGraph {
     //pub fn new(targets: HashSet<CmpId>) -> ControlFlowGraph {
-    pub fn new(data: CfgFile) -> ControlFlowGraph {
+    pub fn new(data: CfgFile, metric: Arc<dyn DistanceMetric>) -> ControlFlowGraph {
         let mut dominator_cmps = HashSet::new();
         for s in data.callsite_dominators.values() {
             dominator_cmps.extend(s)
         }
+        // The targets of the groups do not have to be listed again
+        let mut targets = data.targets;
+        for g in data.target_groups.values() {
+            targets.extend(g.targets.difference(&data.solved_targets));
+        }
         let mut result = ControlFlowGraph {
             graph: DiGraphMap::new(),
-            targets: data.targets,
+            targets,
             id_mapping: data.id_mapping.clone(),
             reverse_id_mapping: Self::reverse_id_mapping(data.id_mapping),
-            solved_targets: HashSet::new(),
+            solved_targets: data.solved_targets,
             indirect_edges: HashSet::new(),
             callsite_edges: HashMap::new(),
             callsite_dominators: data.callsite_dominators,
             dominator_cmps,
-            magic_bytes: HashMap::new(),
+            magic_bytes: data.magic_bytes.into_iter().collect(),
+            target_weights: data.target_weights,
+            target_groups: data.target_groups,
+            metric,
         };
 
-        for e in data.edges {
+        // Sorted, the order of the successors must not change between runs
+        let mut edges: Vec<Edge> = data.edges.into_iter().collect();
+        edges.sort_unstable();
+        for e in edges {
             result.init_add_edge(e);
         }
+        for (callsite, edges) in data.callsite_edges {
+            for e in edges {
+                result.set_edge_indirect(e, callsite);
+            }
+        }
         result.init_prop_targets();
 
         info!("INIT CFG: dominators: {:?}", result.dominator_cmps);
@@ -79,12 +97,88 @@ impl ControlFlowGraph {
             callsite_dominators: HashMap::new(),
             dominator_cmps: HashSet::new(),
             magic_bytes: HashMap::new(),
+            target_weights: HashMap::new(),
+            target_groups: HashMap::new(),
+            metric: Arc::new(HarmonicMetric),
         };
 
         result
     }
 
 
+    pub fn from_snapshot(data: CfgSnapshot, metric: Arc<dyn DistanceMetric>) -> ControlFlowGraph {
+        let mut dominator_cmps = HashSet::new();
+        for s in data.callsite_dominators.values() {
+            dominator_cmps.extend(s)
+        }
+        let mut result = ControlFlowGraph {
+            graph: DiGraphMap::new(),
+            targets: data.targets,
+            id_mapping: data.id_mapping.clone(),
+            reverse_id_mapping: Self::reverse_id_mapping(data.id_mapping),
+            solved_targets: data.solved_targets,
+            indirect_edges: data.indirect_edges,
+            callsite_edges: data.callsite_edges,
+            callsite_dominators: data.callsite_dominators,
+            dominator_cmps,
+            magic_bytes: data.magic_bytes.into_iter().collect(),
+            target_weights: data.target_weights,
+            target_groups: data.target_groups,
+            metric,
+        };
+
+        // Scores were already propagated when the snapshot was taken,
+        // unless the campaign is resumed with a different metric.
+        if data.metric == result.metric.name() {
+            for (src, dst, score) in data.edges {
+                result.graph.add_edge(src, dst, score);
+            }
+        } else {
+            for (src, dst, _) in data.edges {
+                result.init_add_edge((src, dst));
+            }
+            result.init_prop_targets();
+        }
+
+        result
+    }
+
+    pub fn snapshot(&self) -> CfgSnapshot {
+        CfgSnapshot {
+            edges: self.graph.all_edges().map(|(a, b, s)| (a, b, *s)).collect(),
+            targets: self.targets.clone(),
+            id_mapping: self.id_mapping.clone(),
+            solved_targets: self.solved_targets.clone(),
+            indirect_edges: self.indirect_edges.clone(),
+            callsite_edges: self.callsite_edges.clone(),
+            callsite_dominators: self.callsite_dominators.clone(),
+            magic_bytes: self.magic_bytes.iter().map(|(e, f)| (*e, f.clone())).collect(),
+            target_weights: self.target_weights.clone(),
+            target_groups: self.target_groups.clone(),
+            metric: self.metric.name().to_string(),
+        }
+    }
+
+    // Export the static CFG together with everything learned while fuzzing,
+    // so the next campaign can start from it.
+    pub fn to_cfg_file(&self) -> CfgFile {
+        CfgFile {
+            targets: self.targets.clone(),
+            edges: self.graph.all_edges().map(|(a, b, _)| (a, b)).collect(),
+            id_mapping: self.id_mapping.clone(),
+            callsite_dominators: self.callsite_dominators.clone(),
+            callsite_edges: self.callsite_edges.clone(),
+            magic_bytes: self.magic_bytes.iter().map(|(e, f)| (*e, f.clone())).collect(),
+            solved_targets: self.solved_targets.clone(),
+            target_weights: self.target_weights.clone(),
+            target_groups: self.target_groups.clone(),
+        }
+    }
+
+    pub fn metric_name(&self) -> &'static str {
+        self.metric.name()
+    }
+
     pub fn add_edge(&mut self, edge: Edge) -> bool {
         let result = !self.has_edge(edge);
         self.handle_new_edge(edge);
@@ -107,11 +201,13 @@ impl ControlFlowGraph {
     }
 
     fn init_prop_targets(&mut self) {
-        // should be possible without cloning
-        for target in &self.targets.clone() {
-            if let Some(&bb) = self.get_bb_from_cmp(&target) {
-                self.propagate_score(bb);
-            }
+        let mut target_bbs: Vec<BbId> = self.targets.iter()
+            .filter_map(|t| self.get_bb_from_cmp(t))
+            .cloned()
+            .collect();
+        target_bbs.sort_unstable();
+        for bb in target_bbs {
+            self.propagate_score(bb);
         }
     }
 
@@ -135,6 +231,11 @@ impl ControlFlowGraph {
         self.magic_bytes.insert(edge, fixed);
     }
 
+    // Magic bytes learned elsewhere, e.g. by a peer, the local ones are kept
+    pub fn add_magic_bytes(&mut self, edge: Edge, fixed: FixedBytes) {
+        self.magic_bytes.entry(edge).or_insert(fixed);
+    }
+
     pub fn get_magic_bytes(&self, edge: Edge) -> FixedBytes {
         if let Some(fixed) = self.magic_bytes.get(&edge) {
             return fixed.clone();
@@ -172,6 +273,61 @@ impl ControlFlowGraph {
         self.targets.contains(&cmp) || self.solved_targets.contains(&cmp)
     }
 
+    // The weight given to the target itself, else the highest of its groups
+    pub fn get_target_weight(&self, cmp: CmpId) -> f64 {
+        if let Some(w) = self.target_weights.get(&cmp) {
+            return *w;
+        }
+        self.target_groups
+            .values()
+            .filter(|g| g.targets.contains(&cmp))
+            .map(|g| g.weight)
+            .fold(None, |acc: Option<f64>, w| Some(acc.map_or(w, |a| a.max(w))))
+            .unwrap_or(1.0)
+    }
+
+    // Names of the groups the target is in
+    pub fn get_target_groups(&self, cmp: CmpId) -> Vec<String> {
+        let mut groups: Vec<String> = self
+            .target_groups
+            .iter()
+            .filter(|(_, g)| g.targets.contains(&cmp))
+            .map(|(name, _)| name.clone())
+            .collect();
+        groups.sort();
+        groups
+    }
+
+    pub fn is_solved(&self, cmp: CmpId) -> bool {
+        self.solved_targets.contains(&cmp)
+    }
+
+    // Both the remaining and the solved targets
+    pub fn get_all_targets(&self) -> Vec<CmpId> {
+        self.targets.iter().chain(self.solved_targets.iter()).cloned().collect()
+    }
+
+    // Number of edges on the shortest path from the closest of the sources
+    // to every reachable basic block
+    pub fn hop_distances_from(&self, sources: &HashSet<BbId>) -> HashMap<BbId, u32> {
+        let mut distances = HashMap::new();
+        let mut worklist = VecDeque::new();
+        for &bb in sources {
+            distances.insert(bb, 0);
+            worklist.push_back(bb);
+        }
+        while let Some(node) = worklist.pop_front() {
+            let d = distances[&node] + 1;
+            for n in self.graph.neighbors_directed(node, Outgoing) {
+                if let Entry::Vacant(e) = distances.entry(n) {
+                    e.insert(d);
+                    worklist.push_back(n);
+                }
+            }
+        }
+        distances
+    }
+
     pub fn get_bb_from_cmp(&self, cmp: &CmpId) -> Option<&BbId> {
         return self.reverse_id_mapping.get(cmp);
     }
@@ -200,35 +356,31 @@ impl ControlFlowGraph {
     }
 
 
+    // Update the scores of the predecessors of bb, walking the reversed graph
+    // breadth-first. Each node is visited at most once, and the walk stops at
+    // nodes whose incoming edges already have the right score.
     fn propagate_score(&mut self, bb: BbId) {
-        // Check how long it takes to clone the full graph. Result: On my machine the cloning part of adding all the edges to the graph at the beginning takes around 25% of the total time. Definitely expensive but probably not worth to change it right now.
-        #[cfg(test)]
-        let now = Instant::now();
-        let graph_copy = &self.graph.clone();
-        #[cfg(test)]
-        println!("time to copy with node size {:?} and edge size {:?}: {}", &self.graph.node_count(), &self.graph.edge_count(), now.elapsed().as_micros());
-        #[cfg(test)]
-        let now2 = Instant::now();
-        let rev_graph = Reversed(graph_copy);
-        let mut visitor = Bfs::new(rev_graph, bb);
-
-        while let Some(visited) = visitor.next(rev_graph) {
-            let new_score = self._score_for_bb(visited);
-            let mut predecessors = vec![];
-            {
-                let neighbors = self.graph.neighbors_directed(visited, Incoming);
-                for n in neighbors {
-                    predecessors.push(n);
-                }
-            }
+        let mut visited = HashSet::new();
+        let mut worklist = VecDeque::new();
+        visited.insert(bb);
+        worklist.push_back(bb);
+
+        while let Some(node) = worklist.pop_front() {
+            let new_score = self._score_for_bb(node);
+            let predecessors: Vec<BbId> = self.graph.neighbors_directed(node, Incoming).collect();
             for p in predecessors {
-                self.graph.add_edge(p, visited, new_score);
+                if let Some(score) = self.graph.edge_weight_mut(p, node) {
+                    if *score == new_score {
+                        continue;
+                    }
+                    *score = new_score;
+                }
+                if visited.insert(p) {
+                    worklist.push_back(p);
+                }
             }
         }
-        #[cfg(test)]
-        println!("time for rest of propagate: {}", now2.elapsed().as_micros());
     }
-    
 
     pub fn has_edge(&self, edge: Edge) -> bool {
         let (a,b) = edge;
@@ -252,44 +404,6 @@ impl ControlFlowGraph {
         return rev_mapping;
     }
 
-    fn aggregate_score(ovals: Vec<Score>) -> Score {
-        //Self::score_greedy(ovals)
-        //Self::score_coverage(ovals)
-        Self::score_harmonic_mean(ovals)
-    }
-
-    fn score_harmonic_mean(ovals: Vec<Score>) -> Score {
-        if ovals.len() == 0 {
-            return UNDEF_SCORE;
-        }
-        let vals = ovals.into_iter().filter(|v| *v != UNDEF_SCORE);
-        let fvals : Vec<f64> = vals.into_iter().map(|x| x as f64).collect();
-        if fvals.is_empty() {
-            return UNDEF_SCORE;
-        }
-        return mean::harmonic(fvals.as_slice()) as u32;
-    }
-
-    #[allow(dead_code)]
-    fn score_greedy(ovals: Vec<Score>) -> Score {
-        let vals = ovals.into_iter().filter(|v| *v != UNDEF_SCORE);
-        if let Some(v) = vals.min() {
-            v + 1
-        } else {
-            UNDEF_SCORE
-        }
-    }
-
-    #[allow(dead_code)]
-    fn score_coverage(ovals: Vec<Score>) -> Score {
-        if ovals.len() == 0 {
-            return UNDEF_SCORE;
-        }
-        let vals = ovals.into_iter().filter(|v| *v != UNDEF_SCORE);
-        let vals_norm = vals.into_iter().map(|v| if v == TARGET_SCORE {1} else {v});
-        vals_norm.sum()
-    }
-
     fn has_path_to_target_bb(&self, start: BbId) -> bool {
         let distance = &self.score_for_bb(start);
         // If distance is not UNDEF we know it has a path if the scores have propagated properly
@@ -333,11 +447,13 @@ impl ControlFlowGraph {
         if let Some(cmp_set) = &self.id_mapping.get(&bb) {
             has_cmp = true;
             num_cmps = cmp_set.len() as u32;
-            for cmp in *cmp_set {
-                if self.targets.contains(&cmp) {
-                    debug!("Calculate score for target: {}", cmp);
-                    return TARGET_SCORE;
-                }
+            let target_score = cmp_set.iter()
+                .filter(|cmp| self.targets.contains(cmp))
+                .map(|cmp| self.metric.target_score(self.get_target_weight(*cmp)))
+                .min();
+            if let Some(score) = target_score {
+                debug!("Calculate score for target bb: {}", bb);
+                return score;
             }
         }
         let mut neighbors = self.graph.neighbors_directed(bb, Outgoing);
@@ -354,9 +470,13 @@ impl ControlFlowGraph {
                 scores.push(*s);
             }
         }
-        let aggregate = Self::aggregate_score(scores.clone());
+        let aggregate = self.metric.aggregate(scores);
         // increase distance by number of cmpids when passing by.
-        if has_cmp && aggregate != UNDEF_SCORE {aggregate+num_cmps} else {aggregate}
+        if has_cmp && aggregate != UNDEF_SCORE {
+            aggregate.saturating_add(num_cmps).min(UNDEF_SCORE - 1)
+        } else {
+            aggregate
+        }
     }
 
     fn _should_count_edge(&self, edge: Edge, inp: &Vec<u8>) -> bool {
@@ -387,9 +507,6 @@ impl ControlFlowGraph {
 mod tests {
     use super::*;
     use std::iter::FromIterator;
-    use crate::itertools::Itertools;
-    use rand::thread_rng;
-    use rand::seq::SliceRandom;
 
     fn test_new(targets: HashSet<CmpId>, id_mapping: HashMap<BbId, HashSet<CmpId>>) -> ControlFlowGraph {
         let result = ControlFlowGraph {
@@ -403,6 +520,9 @@ mod tests {
             callsite_dominators: HashMap::new(),
             dominator_cmps: HashSet::new(),
             magic_bytes: HashMap::new(),
+            target_weights: HashMap::new(),
+            target_groups: HashMap::new(),
+            metric: Arc::new(HarmonicMetric),
         };
 
         result
@@ -463,33 +583,6 @@ mod tests {
         }
     }
 
-    // Test whether or not the graph clone in propagate_score is too time consuming.
-    #[test]
-    fn cfg_clone_time() {
-        // Create CFG
-        let num_nodes = 1000000;
-        let target_vec = vec![num_nodes*10];
-        let targets: HashSet<CmpId> = HashSet::from_iter(target_vec.iter().cloned());
-        let id_mapping: HashMap<BbId, HashSet<CmpId>> = [(num_nodes-1, vec![num_nodes*10].into_iter().collect())].iter().cloned().collect();
-
-        let mut cfg = test_new(targets, id_mapping);
-        let nodes: Vec<BbId> = (0..num_nodes).collect();
-        
-        let mut edges = vec![];
-        for (a,b) in nodes.clone().into_iter().tuple_windows() {
-            edges.push((a,b));
-        }
-
-        edges.shuffle(&mut thread_rng());
-        
-        let now = Instant::now();
-        for e in edges.clone() {
-            cfg.init_add_edge(e);
-        }
-        cfg.init_prop_targets();
-        println!("total time: {}", now.elapsed().as_micros());
-    }
-
     // Test whether or not has_path_to_target_bb works
     #[test]
     fn cfg_path_to_target() {
@@ -514,6 +607,147 @@ mod tests {
         assert_eq!(cfg.has_path_to_target_bb(80), false);
         assert_eq!(cfg.has_path_to_target_bb(30), true);
         assert_eq!(cfg.has_path_to_target_bb(140), true);
+
+        let hops = cfg.hop_distances_from(&vec![0, 140].into_iter().collect());
+        assert_eq!(hops.get(&0), Some(&0));
+        assert_eq!(hops.get(&180), Some(&2));
+        assert_eq!(hops.get(&110), Some(&4));
+        assert_eq!(hops.get(&10000), None);
+    }
+
+    // Test whether a snapshot restores the learned edges and scores
+    #[test]
+    fn cfg_snapshot_roundtrip() {
+        let target_vec = vec![1200];
+        let targets = HashSet::from_iter(target_vec.iter().cloned());
+        let id_mapping: HashMap<BbId, HashSet<CmpId>> = [(10, vec![1000].into_iter().collect()), (50, vec![1100].into_iter().collect()), (80, vec![1200].into_iter().collect())].iter().cloned().collect();
+
+        let mut cfg = test_new(targets, id_mapping);
+        let edges = vec![(0,10), (10, 20), (20,30), (30,40), (40,50), (10,60), (60,70), (70,80)];
+        for e in edges.clone() {
+            cfg.init_add_edge(e);
+        }
+        cfg.init_prop_targets();
+        cfg.set_edge_indirect((10, 60), 10);
+        cfg.set_magic_bytes((10, 60), &vec![1, 2, 3], &vec![TagSeg { sign: false, begin: 0, end: 2 }]);
+
+        let json = serde_json::to_string(&cfg.snapshot()).unwrap();
+        let restored = ControlFlowGraph::from_snapshot(serde_json::from_str(&json).unwrap(), Arc::new(HarmonicMetric));
+
+        for (from, to) in edges {
+            assert_eq!(cfg.graph.edge_weight(from, to), restored.graph.edge_weight(from, to));
+        }
+        assert_eq!(cfg.score_for_bb(0), restored.score_for_bb(0));
+        assert_eq!(restored.get_bb_from_cmp(&1100), Some(&50));
+        assert_eq!(restored.get_magic_bytes((10, 60)).len(), 2);
+        assert!(restored.is_target(1200));
+    }
+
+    // Test whether an exported targets file keeps the learned edges
+    #[test]
+    fn cfg_export_targets_file() {
+        let data: CfgFile = serde_json::from_str(r#"{"targets": [1100, 1200], "edges": [[0,10], [10,20]], "id_mapping": {"10": [1000], "20": [1100], "40": [1200]}}"#).unwrap();
+        let mut cfg = ControlFlowGraph::new(data, Arc::new(HarmonicMetric));
+        cfg.add_edge((20, 30));
+        cfg.add_edge((30, 40));
+        cfg.set_edge_indirect((20, 30), 20);
+        cfg.set_magic_bytes((20, 30), &vec![7, 8], &vec![TagSeg { sign: false, begin: 1, end: 2 }]);
+        cfg.remove_target(1100);
+
+        let json = serde_json::to_string(&cfg.to_cfg_file()).unwrap();
+        let imported = ControlFlowGraph::new(serde_json::from_str(&json).unwrap(), Arc::new(HarmonicMetric));
+
+        assert!(imported.has_edge((30, 40)));
+        assert_eq!(imported.get_magic_bytes((20, 30)), vec![(1, 8)]);
+        assert!(imported.is_target(1100));
+        assert!(imported.has_path_to_target(1000));
+        assert_eq!(imported.score_for_bb(0), cfg.score_for_bb(0));
+    }
+
+    // Test whether the weighted metric prefers the paths to heavier targets
+    #[test]
+    fn cfg_weighted_targets() {
+        use super::super::metric::{parse_distance_metric, WeightedMetric};
+        let data: CfgFile = serde_json::from_str(r#"{"targets": [1100, 1200], "edges": [[0,10], [0,20], [10,30], [20,40]], "id_mapping": {"10": [1000], "20": [2000], "30": [1100], "40": [1200]}, "target_weights": {"1100": 4.0}}"#).unwrap();
+        let harmonic = ControlFlowGraph::new(data.clone(), parse_distance_metric("harmonic"));
+        assert_eq!(harmonic.score_for_bb(10), harmonic.score_for_bb(20));
+
+        let weighted = ControlFlowGraph::new(data, parse_distance_metric("weighted"));
+        assert!(weighted.score_for_bb(10) < weighted.score_for_bb(20));
+        assert_eq!(weighted.metric_name(), "weighted");
+
+        // Resuming with another metric recomputes the scores
+        let restored = ControlFlowGraph::from_snapshot(harmonic.snapshot(), Arc::new(WeightedMetric));
+        assert_eq!(restored.score_for_bb(0), weighted.score_for_bb(0));
+    }
+
+    #[test]
+    fn cfg_target_groups() {
+        use super::super::metric::parse_distance_metric;
+        let data: CfgFile = serde_json::from_str(r#"{"targets": [1100], "edges": [[0,10], [0,20], [10,30], [20,40]], "id_mapping": {"10": [1000], "20": [2000], "30": [1100], "40": [1200]}, "target_weights": {"1100": 0.5}, "target_groups": {"patch": {"targets": [1100, 1200], "weight": 3.0}, "ubsan": {"targets": [1200]}}}"#).unwrap();
+        assert!(data.has_weights());
+        let cfg = ControlFlowGraph::new(data, parse_distance_metric("weighted"));
+        // Targets of a group are targets, their own weight beats the group's
+        assert!(cfg.is_target(1200));
+        assert_eq!(cfg.get_target_weight(1200), 3.0);
+        assert_eq!(cfg.get_target_weight(1100), 0.5);
+        assert_eq!(cfg.get_target_groups(1200), vec!["patch", "ubsan"]);
+        assert!(cfg.score_for_bb(20) < cfg.score_for_bb(10));
+        let restored = ControlFlowGraph::from_snapshot(cfg.snapshot(), parse_distance_metric("weighted"));
+        assert_eq!(restored.get_target_groups(1100), vec!["patch"]);
     }
 }
 
+// Run with `cargo +nightly bench --features unstable`
+#[cfg(all(test, feature = "unstable"))]
+mod benches {
+    use super::*;
+    use crate::itertools::Itertools;
+    use rand::thread_rng;
+    use rand::seq::SliceRandom;
+    use test::Bencher;
+
+    const NUM_NODES: BbId = 100000;
+
+    // A long chain of basic blocks with the target at the end
+    fn chain_cfg(num_nodes: BbId) -> (ControlFlowGraph, Vec<Edge>) {
+        let mut cfg = ControlFlowGraph::empty_new();
+        cfg.targets.insert(num_nodes * 10);
+        cfg.id_mapping.insert(num_nodes - 1, vec![num_nodes * 10].into_iter().collect());
+        cfg.reverse_id_mapping = ControlFlowGraph::reverse_id_mapping(cfg.id_mapping.clone());
+        let mut edges: Vec<Edge> = (0..num_nodes).tuple_windows().collect();
+        edges.shuffle(&mut thread_rng());
+        (cfg, edges)
+    }
+
+    #[bench]
+    fn cfg_init_propagation(b: &mut Bencher) {
+        let (cfg, edges) = chain_cfg(NUM_NODES);
+        b.iter(|| {
+            let mut cfg = cfg.clone();
+            for e in &edges {
+                cfg.init_add_edge(*e);
+            }
+            cfg.init_prop_targets();
+            cfg
+        });
+    }
+
+    // New edges found while fuzzing, most of them do not change any score.
+    // This used to clone the whole graph for every edge.
+    #[bench]
+    fn cfg_add_edges(b: &mut Bencher) {
+        let (mut cfg, edges) = chain_cfg(NUM_NODES);
+        for e in &edges {
+            cfg.init_add_edge(*e);
+        }
+        cfg.init_prop_targets();
+        b.iter(|| {
+            let mut cfg = cfg.clone();
+            for i in 0..1000 {
+                cfg.add_edge((NUM_NODES + i, i * 10));
+            }
+            cfg
+        });
+    }
+}
@@ -2,9 +2,9 @@ use std::path::Path;
 use std::collections::{HashSet, HashMap};
 use std::io;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, BufWriter};
 
-use super::cfg::{CmpId, BbId, CallSiteId, Edge};
+use super::cfg::{CmpId, BbId, CallSiteId, Edge, Score, FixedBytes};
 use serde::de;
 use serde::de::{Deserialize, Deserializer};
 use std::hash::Hash;
@@ -20,6 +20,58 @@ pub struct CfgFile {
     pub id_mapping: HashMap<BbId, HashSet<CmpId>>,
     #[serde(default, deserialize_with = "de_int_key")]
     pub callsite_dominators: HashMap<CallSiteId, HashSet<CmpId>>,
+    // Learned at runtime, see `ControlFlowGraph::to_cfg_file`
+    #[serde(default, deserialize_with = "de_int_key")]
+    pub callsite_edges: HashMap<CallSiteId, HashSet<Edge>>,
+    #[serde(default)]
+    pub magic_bytes: Vec<(Edge, FixedBytes)>,
+    #[serde(default)]
+    pub solved_targets: HashSet<CmpId>,
+    // Targets default to 1.0, or the highest weight of their groups
+    #[serde(default, deserialize_with = "de_int_key")]
+    pub target_weights: HashMap<CmpId, f64>,
+    // Named sets of targets, e.g. "recent patch" or "ubsan checks"
+    #[serde(default)]
+    pub target_groups: HashMap<String, TargetGroup>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TargetGroup {
+    pub targets: HashSet<CmpId>,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+impl CfgFile {
+    // Whether some targets are worth more than others
+    pub fn has_weights(&self) -> bool {
+        !self.target_weights.is_empty() || self.target_groups.values().any(|g| g.weight != 1.0)
+    }
+}
+
+// Full state of a ControlFlowGraph, including everything learned at runtime.
+// Used by the checkpoint, so edge scores do not have to be propagated again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CfgSnapshot {
+    pub edges: Vec<(BbId, BbId, Score)>,
+    pub targets: HashSet<CmpId>,
+    pub id_mapping: HashMap<BbId, HashSet<CmpId>>,
+    pub solved_targets: HashSet<CmpId>,
+    pub indirect_edges: HashSet<Edge>,
+    pub callsite_edges: HashMap<CallSiteId, HashSet<Edge>>,
+    pub callsite_dominators: HashMap<CallSiteId, HashSet<CmpId>>,
+    pub magic_bytes: Vec<(Edge, FixedBytes)>,
+    #[serde(default)]
+    pub target_weights: HashMap<CmpId, f64>,
+    #[serde(default)]
+    pub target_groups: HashMap<String, TargetGroup>,
+    // Edge scores are only valid for the metric they were computed with
+    #[serde(default)]
+    pub metric: String,
 }
 
 fn de_int_key<'de, D, K, V>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
@@ -45,3 +97,9 @@ pub fn parse_targets_file(path: &Path) -> io::Result<CfgFile> {
 
     return Ok(result);
 }
+
+pub fn write_targets_file(path: &Path, data: &CfgFile) -> io::Result<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(writer, data)?;
+    Ok(())
+}
@@ -0,0 +1,164 @@
+// How the distance of a basic block to the targets is computed from the
+// distances of its successors in the CFG.
+
+use super::cfg::{Score, TARGET_SCORE, UNDEF_SCORE};
+use math::mean;
+use std::sync::Arc;
+
+// Scale of the target scores in the weighted metric,
+// a target with weight 1.0 is this far away from itself.
+const WEIGHTED_TARGET_SCORE: f64 = 10.0;
+
+pub trait DistanceMetric: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Aggregate the scores of the successors, UNDEF_SCORE means no path to a target.
+    fn aggregate(&self, ovals: Vec<Score>) -> Score;
+
+    // Score of a basic block containing a target with the given weight.
+    fn target_score(&self, _weight: f64) -> Score {
+        TARGET_SCORE
+    }
+}
+
+fn defined_scores(ovals: Vec<Score>) -> Vec<f64> {
+    ovals
+        .into_iter()
+        .filter(|v| *v != UNDEF_SCORE)
+        .map(|x| x as f64)
+        .collect()
+}
+
+pub struct HarmonicMetric;
+
+impl DistanceMetric for HarmonicMetric {
+    fn name(&self) -> &'static str {
+        "harmonic"
+    }
+
+    fn aggregate(&self, ovals: Vec<Score>) -> Score {
+        let fvals = defined_scores(ovals);
+        if fvals.is_empty() {
+            return UNDEF_SCORE;
+        }
+        mean::harmonic(fvals.as_slice()) as Score
+    }
+}
+
+// Only the closest successor counts.
+pub struct GreedyMetric;
+
+impl DistanceMetric for GreedyMetric {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn aggregate(&self, ovals: Vec<Score>) -> Score {
+        let vals = ovals.into_iter().filter(|v| *v != UNDEF_SCORE);
+        if let Some(v) = vals.min() {
+            v.saturating_add(1).min(UNDEF_SCORE - 1)
+        } else {
+            UNDEF_SCORE
+        }
+    }
+}
+
+// Sum of the successors, so blocks leading to many targets are preferred.
+pub struct CoverageMetric;
+
+impl DistanceMetric for CoverageMetric {
+    fn name(&self) -> &'static str {
+        "coverage"
+    }
+
+    fn aggregate(&self, ovals: Vec<Score>) -> Score {
+        let vals: Vec<Score> = ovals
+            .into_iter()
+            .filter(|v| *v != UNDEF_SCORE)
+            .map(|v| if v == TARGET_SCORE { 1 } else { v })
+            .collect();
+        if vals.is_empty() {
+            return UNDEF_SCORE;
+        }
+        vals.into_iter()
+            .fold(0 as Score, |acc, v| acc.saturating_add(v))
+            .min(UNDEF_SCORE - 1)
+    }
+}
+
+// Like AFLGo's function-level distance: the average over all successors.
+pub struct ArithmeticMetric;
+
+impl DistanceMetric for ArithmeticMetric {
+    fn name(&self) -> &'static str {
+        "arithmetic"
+    }
+
+    fn aggregate(&self, ovals: Vec<Score>) -> Score {
+        let fvals = defined_scores(ovals);
+        if fvals.is_empty() {
+            return UNDEF_SCORE;
+        }
+        mean::arithmetic(fvals.as_slice()) as Score
+    }
+}
+
+// Harmonic mean, but targets with a higher weight (`target_weights` in the
+// targets file) are closer to themselves than the others.
+pub struct WeightedMetric;
+
+impl DistanceMetric for WeightedMetric {
+    fn name(&self) -> &'static str {
+        "weighted"
+    }
+
+    fn aggregate(&self, ovals: Vec<Score>) -> Score {
+        HarmonicMetric.aggregate(ovals)
+    }
+
+    fn target_score(&self, weight: f64) -> Score {
+        if weight <= 0.0 {
+            return UNDEF_SCORE - 1;
+        }
+        (WEIGHTED_TARGET_SCORE / weight).round().min((UNDEF_SCORE - 1) as f64) as Score
+    }
+}
+
+pub fn parse_distance_metric(m: &str) -> Arc<dyn DistanceMetric> {
+    match m {
+        "harmonic" => Arc::new(HarmonicMetric),
+        "greedy" => Arc::new(GreedyMetric),
+        "coverage" => Arc::new(CoverageMetric),
+        "arithmetic" => Arc::new(ArithmeticMetric),
+        "weighted" => Arc::new(WeightedMetric),
+        _ => Arc::new(HarmonicMetric),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric_aggregate() {
+        let scores = vec![2, 4, UNDEF_SCORE];
+        assert_eq!(HarmonicMetric.aggregate(scores.clone()), 2);
+        assert_eq!(GreedyMetric.aggregate(scores.clone()), 3);
+        assert_eq!(CoverageMetric.aggregate(scores.clone()), 6);
+        assert_eq!(ArithmeticMetric.aggregate(scores.clone()), 3);
+        assert_eq!(GreedyMetric.aggregate(vec![UNDEF_SCORE - 1]), UNDEF_SCORE - 1);
+        for name in &["harmonic", "greedy", "coverage", "arithmetic", "weighted"] {
+            let metric = parse_distance_metric(name);
+            assert_eq!(metric.name(), *name);
+            assert_eq!(metric.aggregate(vec![UNDEF_SCORE]), UNDEF_SCORE);
+            assert_eq!(metric.aggregate(vec![]), UNDEF_SCORE);
+        }
+    }
+
+    #[test]
+    fn metric_weighted_target() {
+        assert_eq!(HarmonicMetric.target_score(2.0), TARGET_SCORE);
+        assert!(WeightedMetric.target_score(2.0) < WeightedMetric.target_score(1.0));
+        assert!(WeightedMetric.target_score(0.5) > WeightedMetric.target_score(1.0));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod cfg;
 pub mod fparse;
+pub mod metric;
 
 #[cfg(test)]
 mod tests {
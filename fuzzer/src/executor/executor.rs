@@ -3,14 +3,18 @@ use super::{limit::SetLimit, *};
 use crate::{
     branches, command,
     cond_stmt::{self, NextState},
-    depot, stats, track,
-    dyncfg::cfg::{CmpId},
+    custom_mutator::CustomMutator,
+    depot, rng,
+    sanitizer::SanitizerKind,
+    stats,
+    track::{self, Checksum},
+    dyncfg::cfg::{CmpId, ControlFlowGraph},
 };
-use angora_common::{config, defs, tag::TagSeg};
+use angora_common::{cond_stmt_base::CondStmtBase, config, defs, tag::TagSeg};
 
 use std::{
-    env,
-    collections::HashMap,
+    env, mem,
+    collections::{HashMap, HashSet},
     path::Path,
     process::{Command, Stdio},
     sync::{
@@ -26,6 +30,9 @@ pub struct Executor {
     pub cmd: command::CommandOpt,
     pub branches: branches::Branches,
     pub t_conds: cond_stmt::ShmConds,
+    cmp_log: cond_stmt::ShmCmpLog,
+    // The outputs of the watched conds in the last run with the target cond
+    watched_outputs: Vec<u64>,
     envs: HashMap<String, String>,
     forksrv: Option<Forksrv>,
     depot: Arc<depot::Depot>,
@@ -34,9 +41,17 @@ pub struct Executor {
     invariable_cnt: usize,
     pub last_f: u64,
     pub has_new_path: bool,
+    // Id of the input saved to the queue by the last run
+    pub last_saved: Option<usize>,
+    // The conds a peer tracked on the input being synced
+    peer_conds: Option<Vec<cond_stmt::CondStmt>>,
     pub global_stats: Arc<RwLock<stats::ChartStats>>,
     pub local_stats: stats::LocalStats,
     is_directed: bool,
+    custom: Option<CustomMutator>,
+    // The checksum checks of the input being fuzzed, and the input
+    checksums: Vec<Checksum>,
+    checksum_base: Vec<u8>,
 }
 
 impl Executor {
@@ -49,17 +64,14 @@ impl Executor {
         // ** Share Memory **
         let branches = branches::Branches::new(global_branches);
         let t_conds = cond_stmt::ShmConds::new();
+        let cmp_log = cond_stmt::ShmCmpLog::new();
 
         // ** Envs **
         let mut envs = HashMap::new();
-        envs.insert(
-            defs::ASAN_OPTIONS_VAR.to_string(),
-            defs::ASAN_OPTIONS_CONTENT.to_string(),
-        );
-        envs.insert(
-            defs::MSAN_OPTIONS_VAR.to_string(),
-            defs::MSAN_OPTIONS_CONTENT.to_string(),
-        );
+        for kind in SanitizerKind::all() {
+            let (var, options) = kind.options();
+            envs.insert(var.to_string(), options.to_string());
+        }
         envs.insert(
             defs::BRANCHES_SHM_ENV_VAR.to_string(),
             branches.get_id().to_string(),
@@ -68,6 +80,10 @@ impl Executor {
             defs::COND_STMT_ENV_VAR.to_string(),
             t_conds.get_id().to_string(),
         );
+        envs.insert(
+            defs::CMP_LOG_ENV_VAR.to_string(),
+            cmp_log.get_id().to_string(),
+        );
         envs.insert(
             defs::LD_LIBRARY_PATH_VAR.to_string(),
             cmd.ld_library.clone(),
@@ -93,11 +109,17 @@ impl Executor {
         ));
 
         let is_directed = cmd.directed_only;
+        let custom = cmd.custom_mutator.as_ref().map(|lib| {
+            CustomMutator::load(lib, rng::random())
+                .unwrap_or_else(|e| panic!("Could not load custom mutator {}: {}", lib, e))
+        });
 
         Self {
             cmd,
             branches,
             t_conds,
+            cmp_log,
+            watched_outputs: vec![],
             envs,
             forksrv,
             depot,
@@ -106,12 +128,34 @@ impl Executor {
             invariable_cnt: 0,
             last_f: defs::UNREACHABLE,
             has_new_path: false,
+            last_saved: None,
+            peer_conds: None,
             global_stats,
             local_stats: Default::default(),
             is_directed,
+            custom,
+            checksums: vec![],
+            checksum_base: vec![],
         }
     }
 
+    // An executor outside of a fuzzing campaign, used by the tools. Its depot
+    // and coverage are thrown away.
+    pub fn standalone(cmd: command::CommandOpt, work_dir: &Path) -> Self {
+        let depot = Arc::new(depot::Depot::new(
+            work_dir.join(defs::SEEDS_DIR),
+            work_dir,
+            RwLock::new(ControlFlowGraph::empty_new()),
+            depot::parse_schedule("round_robin"),
+            Default::default(),
+        ));
+        let global_branches = Arc::new(branches::GlobalBranches::new(RwLock::new(
+            ControlFlowGraph::empty_new(),
+        )));
+        let stats = Arc::new(RwLock::new(stats::ChartStats::new()));
+        Self::new(cmd, global_branches, depot, stats)
+    }
+
     pub fn set_directed(&mut self, b: bool) {
         self.is_directed = b;
     }
@@ -192,11 +236,16 @@ impl Executor {
         buf: &Vec<u8>,
         cond: &mut cond_stmt::CondStmt,
     ) -> (StatusType, u64) {
+        let fixed = self.fix_checksums(buf);
+        let buf = fixed.as_ref().unwrap_or(buf);
         self.run_init();
         self.t_conds.set(cond);
         let mut status = self.run_inner(buf);
 
         let output = self.t_conds.get_cond_output();
+        if self.cmp_log.is_watching() {
+            self.watched_outputs = self.cmp_log.outputs();
+        }
         let mut explored = false;
         let mut skip = false;
         skip |= self.check_explored(cond, status, output, &mut explored);
@@ -233,12 +282,22 @@ impl Executor {
             );
             // crash or hang
             if self.branches.has_new(unmem_status).0 {
-                self.depot.save(unmem_status, &buf, cmpid);
+                self.save(unmem_status, &buf, cmpid);
             }
         }
         skip
     }
 
+    // Crashes are tagged with the sanitizer of the build that caught them
+    fn save(&self, status: StatusType, buf: &Vec<u8>, cmpid: u32) -> usize {
+        let id = self.depot.save(status, buf, cmpid);
+        if let (StatusType::Crash, Some(kind)) = (status, self.cmd.sanitizer) {
+            self.depot.tag_crash(id, kind);
+            self.depot.crash_at_target(cmpid);
+        }
+        id
+    }
+
     fn do_if_has_new(&mut self, buf: &Vec<u8>, status: StatusType, _explored: bool, cmpid: u32) {
         // new edge: one byte in bitmap
         let (has_new_path, has_new_edge, edge_num) = self.branches.has_new(status);
@@ -246,16 +305,22 @@ impl Executor {
         if has_new_path {
             self.has_new_path = true;
             self.local_stats.find_new(&status);
-            let id = self.depot.save(status, &buf, cmpid);
+            let id = self.save(status, &buf, cmpid);
 
             if status == StatusType::Normal {
+                self.last_saved = Some(id);
                 self.local_stats.avg_edge_num.update(edge_num as f32);
-                let speed = self.count_time();
+                let mut speed = self.count_time();
                 let speed_ratio = self.local_stats.avg_exec_time.get_ratio(speed as f32);
                 self.local_stats.avg_exec_time.update(speed as f32);
+                if self.cmd.deterministic {
+                    speed = 0;
+                }
 
                 // Avoid track slow ones
-                if (!has_new_edge && speed_ratio > 10 && id > 10) || (speed_ratio > 25 && id > 10) {
+                if !self.cmd.deterministic
+                    && ((!has_new_edge && speed_ratio > 10 && id > 10) || (speed_ratio > 25 && id > 10))
+                {
                     warn!(
                         "Skip tracking id {}, speed: {}, speed_ratio: {}, has_new_edge: {}",
                         id, speed, speed_ratio, has_new_edge
@@ -264,7 +329,10 @@ impl Executor {
                 }
                 let crash_or_tmout = self.try_unlimited_memory(buf, cmpid);
                 if !crash_or_tmout {
-                    let cond_stmts = self.track(id, buf, speed);
+                    let cond_stmts = match self.peer_conds.take() {
+                        Some(conds) => self.import_conds(id, buf, speed, conds),
+                        None => self.track(id, buf, speed),
+                    };
                     if cond_stmts.len() > 0 {
                         self.depot.add_entries(cond_stmts);
                         if self.cmd.enable_afl {
@@ -273,27 +341,160 @@ impl Executor {
                                     id, speed, edge_num,
                                 )]);
                         }
+                        if self.custom.is_some() {
+                            self.depot
+                                .add_entries(vec![cond_stmt::CondStmt::get_custom_cond(
+                                    id, speed, edge_num,
+                                )]);
+                        }
                     }
+                    self.repair_checksums(id, buf, cmpid);
                 }
             }
         }
     }
 
     pub fn run(&mut self, buf: &Vec<u8>, cond: &mut cond_stmt::CondStmt) -> StatusType {
+        let fixed = self.fix_checksums(buf);
+        let buf = fixed.as_ref().unwrap_or(buf);
         self.run_init();
         let status = self.run_inner(buf);
         self.do_if_has_new(buf, status, false, 0);
         self.check_timeout(status, cond)
     }
 
+    // Only run the input, without saving it or tracking new paths.
+    pub fn run_status(&mut self, buf: &Vec<u8>) -> StatusType {
+        self.run_init();
+        let status = self.run_inner(buf);
+        if status == StatusType::Error {
+            self.rebind_forksrv();
+        }
+        status
+    }
+
+    // All conds on the path of the input and their taint, nothing is filtered
+    // or added to the CFG. None if the track binary crashed or timed out.
+    pub fn track_conds(&mut self, buf: &Vec<u8>) -> Option<Vec<cond_stmt::CondStmt>> {
+        if !self.cmd.has_track() || self.run_track(buf) != StatusType::Normal {
+            return None;
+        }
+        match track::read_and_parse(
+            Path::new(&self.cmd.track_path),
+            self.cmd.mode.is_pin_mode(),
+            self.cmd.enable_exploitation,
+        ) {
+            Ok(parsed) => Some(parsed.conds),
+            Err(e) => {
+                warn!("parse track file error!! {:?}", e);
+                None
+            }
+        }
+    }
+
+    // The outputs of these conds are read from the same runs as the target
+    // cond, until the search is done
+    pub fn watch_conds(&mut self, conds: &[CondStmtBase]) {
+        self.cmp_log.watch(conds);
+        self.watched_outputs.clear();
+    }
+
+    // Empty if no cond is watched or the target cond has not been run yet
+    pub fn watched_outputs(&self) -> &[u64] {
+        &self.watched_outputs
+    }
+
     pub fn run_sync(&mut self, buf: &Vec<u8>) {
         self.run_init();
         let status = self.run_inner(buf);
         self.do_if_has_new(buf, status, false, 0);
     }
 
+    // An input of a peer. If it comes with the conds the peer tracked on it,
+    // it is not tracked again.
+    pub fn run_sync_with_conds(
+        &mut self,
+        buf: &Vec<u8>,
+        conds: Option<Vec<cond_stmt::CondStmt>>,
+    ) {
+        self.peer_conds = conds;
+        self.run_sync(buf);
+        self.peer_conds = None;
+    }
+
+    // The checksum checks of the input that the next runs are repaired for
+    pub fn set_checksums(&mut self, checksums: Vec<Checksum>, base: &[u8]) {
+        self.checksums = checksums;
+        self.checksum_base = if self.checksums.is_empty() {
+            vec![]
+        } else {
+            base.to_vec()
+        };
+    }
+
+    // Write the values the checksum checks expect to the input, as if they
+    // were patched out of the target. The value of a check is read from its
+    // operands in a run with it as the cond, unless the bytes it is computed
+    // from are the same as in the tracked input. None if nothing changed.
+    fn fix_checksums(&mut self, buf: &[u8]) -> Option<Vec<u8>> {
+        if self.checksums.is_empty() {
+            return None;
+        }
+        let checksums = mem::take(&mut self.checksums);
+        let mut fixed = buf.to_vec();
+        let mut changed = false;
+        for ck in checksums.iter() {
+            let value = if ck.is_span_unchanged(&self.checksum_base, &fixed) {
+                ck.value
+            } else {
+                let old = self.t_conds.replace(&ck.cond);
+                self.local_stats.num_exec.count();
+                let status = self.run_inner(&fixed);
+                let args = self.t_conds.get_args();
+                self.t_conds.restore(old);
+                if status == StatusType::Error {
+                    self.rebind_forksrv();
+                }
+                match args {
+                    Some((arg1, arg2)) => ck.computed(arg1, arg2),
+                    None => continue,
+                }
+            };
+            changed |= ck.fix(&mut fixed, value);
+        }
+        self.checksums = checksums;
+        if changed {
+            Some(fixed)
+        } else {
+            None
+        }
+    }
+
+    // The tracked input fails its checksum checks, run it again with the
+    // values the track binary saw.
+    fn repair_checksums(&mut self, id: usize, buf: &[u8], cmpid: u32) {
+        let mut fixed = buf.to_vec();
+        let mut changed = false;
+        for ck in self.depot.get_checksums(id).iter() {
+            changed |= ck.fix(&mut fixed, ck.value);
+        }
+        if !changed {
+            return;
+        }
+        let last_saved = self.last_saved;
+        self.local_stats.num_exec.count();
+        let status = self.run_inner(&fixed);
+        // The repaired input passes the checks, so this does not recurse again
+        self.do_if_has_new(&fixed, status, false, cmpid);
+        if status == StatusType::Error {
+            self.rebind_forksrv();
+        }
+        self.last_saved = last_saved;
+    }
+
     fn run_init(&mut self) {
         self.has_new_path = false;
+        self.last_saved = None;
         self.local_stats.num_exec.count();
     }
 
@@ -355,14 +556,12 @@ impl Executor {
         used_us / 3
     }
 
-    fn track(&mut self, id: usize, buf: &Vec<u8>, speed: u32) -> Vec<cond_stmt::CondStmt> {
+    fn run_track(&mut self, buf: &Vec<u8>) -> StatusType {
         self.envs.insert(
             defs::TRACK_OUTPUT_VAR.to_string(),
             self.cmd.track_path.clone(),
         );
 
-        let t_now: stats::TimeIns = Default::default();
-
         self.write_test(buf);
 
         compiler_fence(Ordering::SeqCst);
@@ -373,7 +572,29 @@ impl Executor {
             config::TIME_LIMIT_TRACK,
         );
         compiler_fence(Ordering::SeqCst);
+        ret_status
+    }
+
+    // The cmps of a run of the fast binary, in the order they were reached
+    fn log_cmps(&mut self, buf: &Vec<u8>) -> (StatusType, Vec<CondStmtBase>) {
+        self.cmp_log.log_all();
+        let status = self.run_inner(buf);
+        let logged = self.cmp_log.logged();
+        self.cmp_log.off();
+        if status == StatusType::Error {
+            self.rebind_forksrv();
+        }
+        (status, logged)
+    }
 
+    fn track(&mut self, id: usize, buf: &Vec<u8>, speed: u32) -> Vec<cond_stmt::CondStmt> {
+        let t_now: stats::TimeIns = Default::default();
+
+        let (ret_status, logged) = if self.cmd.has_track() {
+            (self.run_track(buf), vec![])
+        } else {
+            self.log_cmps(buf)
+        };
         if ret_status != StatusType::Normal {
             error!(
                 "Crash or hang while tracking! -- {:?},  id: {}",
@@ -382,13 +603,53 @@ impl Executor {
             return vec![];
         }
 
-        let (mut cond_list, ind_edges_list) = track::load_track_data(
-            Path::new(&self.cmd.track_path),
-            id as u32,
-            speed,
-            self.cmd.mode.is_pin_mode(),
-            self.cmd.enable_exploitation,
-        );
+        let (cond_list, ind_edges_list) = if self.cmd.has_track() {
+            track::load_track_data(
+                Path::new(&self.cmd.track_path),
+                id as u32,
+                speed,
+                self.cmd.mode.is_pin_mode(),
+                self.cmd.enable_exploitation,
+            )
+        } else {
+            (track::load_logged_cmps(&logged, id as u32, speed, buf), vec![])
+        };
+        self.depot.save_conds(id, &cond_list);
+
+        let cond_list = self.process_conds(id, buf, cond_list, ind_edges_list);
+        self.local_stats.track_time += t_now.into();
+        cond_list
+    }
+
+    fn import_conds(
+        &mut self,
+        id: usize,
+        buf: &Vec<u8>,
+        speed: u32,
+        mut cond_list: Vec<cond_stmt::CondStmt>,
+    ) -> Vec<cond_stmt::CondStmt> {
+        for cond in cond_list.iter_mut() {
+            cond.base.belong = id as u32;
+            cond.speed = speed;
+        }
+        self.depot.save_conds(id, &cond_list);
+        self.process_conds(id, buf, cond_list, vec![])
+    }
+
+    // Learn from the conds of a new input, the ones returned are fuzzed
+    fn process_conds(
+        &mut self,
+        id: usize,
+        buf: &Vec<u8>,
+        mut cond_list: Vec<cond_stmt::CondStmt>,
+        ind_edges_list: Vec<(u32, u32)>,
+    ) -> Vec<cond_stmt::CondStmt> {
+        self.depot.dict.add_from_conds(&cond_list);
+        let checksums = track::find_checksums(&mut cond_list, buf);
+        if !checksums.is_empty() {
+            info!("{} checksum checks in input {}", checksums.len(), id);
+            self.depot.add_checksums(id, checksums);
+        }
 
         let mut ind_dominator_offsets : HashMap<CmpId, Vec<TagSeg>> = HashMap::new();
         let mut ind_cond_list = vec![];
@@ -442,12 +703,17 @@ impl Executor {
         }
 
 
+        let mut reached_targets = HashSet::new();
         for cond in cond_list.iter_mut() {
             let dyncfg = self.depot.cfg.read().unwrap();
             if dyncfg.is_target(cond.base.cmpid) {
                 cond.set_target(true);
+                reached_targets.insert(cond.base.cmpid);
             }
         }
+        for cmpid in reached_targets {
+            self.depot.reach_target(cmpid, id);
+        }
 
         // Add fixed conds to result
         cond_list.append(&mut ind_cond_list);
@@ -458,17 +724,30 @@ impl Executor {
             cond_list.retain(|x| dyncfg.has_path_to_target(x.base.cmpid));
         }
 
-        self.local_stats.track_time += t_now.into();
         cond_list
     }
 
+    // Tokens of the user and the auto dictionary
+    pub fn dict_tokens(&self) -> Vec<Vec<u8>> {
+        self.depot.dict.tokens()
+    }
+
     pub fn random_input_buf(&self) -> Vec<u8> {
         let id = self.depot.next_random();
         self.depot.get_input_buf(id)
     }
 
+    pub fn custom_mutator(&mut self) -> Option<&mut CustomMutator> {
+        self.custom.as_mut()
+    }
+
+    // The post-process hook of the custom mutator (e.g. fixing checksums)
+    // changes what the target reads, the saved input is the one before it.
     fn write_test(&mut self, buf: &Vec<u8>) {
-        self.fd.write_buf(buf);
+        match self.custom.as_mut().and_then(|c| c.post_process(buf)) {
+            Some(processed) if !processed.is_empty() => self.fd.write_buf(&processed),
+            _ => self.fd.write_buf(buf),
+        }
         if self.cmd.is_stdin {
             self.fd.rewind();
         }
@@ -498,7 +777,7 @@ impl Executor {
         let ret = match child.wait_timeout(timeout).unwrap() {
             Some(status) => {
                 if let Some(status_code) = status.code() {
-                    if (self.cmd.uses_asan && status_code == defs::MSAN_ERROR_CODE)
+                    if (self.cmd.uses_asan && SanitizerKind::is_error_code(status_code))
                         || (self.cmd.mode.is_pin_mode() && status_code > 128)
                     {
                         StatusType::Crash
@@ -527,6 +806,8 @@ impl Executor {
             .sync_from_local(&mut self.local_stats);
 
         self.t_conds.clear();
+        self.cmp_log.off();
+        self.watched_outputs.clear();
         self.tmout_cnt = 0;
         self.invariable_cnt = 0;
         self.last_f = defs::UNREACHABLE;
@@ -1,4 +1,5 @@
 use super::{limit::SetLimit, *};
+use crate::sanitizer::SanitizerKind;
 use angora_common::defs::*;
 use byteorder::{LittleEndian, ReadBytesExt};
 use libc;
@@ -139,7 +140,7 @@ impl Forksrv {
                 };
                 let exit_code = unsafe { libc::WEXITSTATUS(status) };
                 let signaled = unsafe { libc::WIFSIGNALED(status) };
-                if signaled || (self.uses_asan && exit_code == MSAN_ERROR_CODE) {
+                if signaled || (self.uses_asan && SanitizerKind::is_error_code(exit_code)) {
                     debug!("Crash code: {}", status);
                     StatusType::Crash
                 } else {
@@ -1,19 +1,31 @@
 use crate::{
-    branches::GlobalBranches, command::CommandOpt, cond_stmt::NextState, depot::Depot,
-    executor::Executor, fuzz_type::FuzzType, search::*, stats,
+    branches::GlobalBranches,
+    command::CommandOpt,
+    cond_stmt::NextState,
+    depot::Depot,
+    executor::Executor,
+    fuzz_type::FuzzType,
+    grammar::{self, Grammar},
+    rng,
+    scheduler::Scheduler,
+    search::*,
+    stats,
 };
-use rand::prelude::*;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc, RwLock,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn fuzz_loop(
     running: Arc<AtomicBool>,
     cmd_opt: CommandOpt,
     depot: Arc<Depot>,
     global_branches: Arc<GlobalBranches>,
     global_stats: Arc<RwLock<stats::ChartStats>>,
+    scheduler: Arc<Scheduler>,
+    thread_id: usize,
+    grammar: Option<Arc<Grammar>>,
 ) {
     let search_method = cmd_opt.search_method;
     let mut executor = Executor::new(
@@ -23,31 +35,23 @@ pub fn fuzz_loop(
         global_stats.clone(),
     );
 
-    let san_cmd_opt = cmd_opt.sanopt();
-    debug!("san_cmd_opt: {:?}", san_cmd_opt);
-    let mut sanitized_executor = Executor::new(
-        san_cmd_opt,
-        global_branches.clone(),
-        depot.clone(),
-        global_stats.clone(),
-    );
-
-
-    while running.load(Ordering::Relaxed) {
-        let entry = match depot.get_entry() {
-            Some(e) => e,
-            None => break,
-        };
-
-        let mut cond = entry.0;
-        let priority = entry.1;
-
-        if priority.is_done() {
-            break;
-        }
+    // One executor for every sanitized build
+    let mut sanitized_executors: Vec<Executor> = (0..cmd_opt.sanitizers.len())
+        .map(|i| {
+            let san_cmd_opt = cmd_opt.sanopt(i);
+            debug!("san_cmd_opt: {:?}", san_cmd_opt);
+            Executor::new(
+                san_cmd_opt,
+                global_branches.clone(),
+                depot.clone(),
+                global_stats.clone(),
+            )
+        })
+        .collect();
 
+    while let Some((mut cond, priority)) = scheduler.next(thread_id, &running) {
         if cond.is_done() {
-            depot.update_entry(cond);
+            scheduler.finish(thread_id, cond, priority, 0, 0);
             continue;
         }
 
@@ -57,7 +61,7 @@ pub fn fuzz_loop(
 
         /*
         if config::ENABLE_PREFER_FAST_COND && cond.base.op == defs::COND_AFL_OP {
-            let mut rng = thread_rng();
+            let mut rng = rng::thread_rng();
             let speed_ratio = depot.get_speed_ratio(belong_input);
             if speed_ratio > 1 {
                 // [2, 3] -> 2
@@ -73,28 +77,40 @@ pub fn fuzz_loop(
         */
 
         let buf = depot.get_input_buf(belong_input);
+        let checksums = depot.get_checksums(belong_input);
+        executor.set_checksums(checksums.clone(), &buf);
+        for san_executor in sanitized_executors.iter_mut() {
+            san_executor.set_checksums(checksums.clone(), &buf);
+        }
+        // In grammar mode, inputs without a tree are fuzzed as bytes
+        let grammar_tree = grammar
+            .as_ref()
+            .and_then(|g| depot.get_tree(belong_input).map(|t| (g.clone(), t)));
+        let num_inputs = depot.num_inputs.load(Ordering::Relaxed);
 
-        {
+        let (num_exec, num_new) = {
             let fuzz_type = cond.get_fuzz_type();
-            if cond.is_target && cmd_opt.sanopt_bin.is_some() {
-                info!("Using Sanopt Executor");
+            // A target cond is fuzzed on the first sanitized build that covers it
+            let san = if cond.is_target {
+                cmd_opt.sanitizers.iter().position(|s| s.covers(cond.base.cmpid))
             } else {
-                info!("Using normal Executor");
-            }
-            let mut cur_executor = if cond.is_target && cmd_opt.sanopt_bin.is_some() { info!("Using Sanopt Executor");&mut sanitized_executor} else { info!("Using normal Executor"); &mut executor};
-
-            match fuzz_type {
-                FuzzType::ExploreFuzz => {
-                    // Avoid sanitized binary when exploring
-                },
-                FuzzType::LenFuzz => {
-                    cur_executor = &mut executor
+                None
+            };
+            let mut cur_executor = match san {
+                Some(i) => {
+                    info!("Using Sanopt Executor ({})", cmd_opt.sanitizers[i].kind);
+                    &mut sanitized_executors[i]
                 },
-                 _ => {
-                    cur_executor = if cond.is_target &&  cmd_opt.sanopt_bin.is_some() { info!("Using Sanopt Executor"); &mut sanitized_executor} else {info!("Using normal Executor"); &mut executor};
+                None => {
+                    info!("Using normal Executor");
+                    &mut executor
                 },
+            };
+
+            if let FuzzType::LenFuzz = fuzz_type {
+                cur_executor = &mut executor;
             }
-            
+
 
             let handler = SearchHandler::new(running.clone(), &mut cur_executor, &mut cond, buf);
             match fuzz_type {
@@ -102,14 +118,22 @@ pub fn fuzz_loop(
                     if handler.cond.is_time_expired() {
                         handler.cond.next_state();
                     }
-                    if handler.cond.state.is_one_byte() {
+                    // The byte-level searches would break the structure
+                    let structural = grammar_tree
+                        .as_ref()
+                        .filter(|(g, t)| !grammar::is_free_taint(&t.nodes(g), &handler.cond.offsets));
+                    if let Some((g, t)) = structural {
+                        GrammarFuzz::new(handler, g, &depot, t.clone()).run_cond();
+                    } else if SmtSearch::should_run(handler.cond) {
+                        SmtSearch::new(handler).run();
+                    } else if handler.cond.state.is_one_byte() {
                         OneByteFuzz::new(handler).run();
                     } else if handler.cond.state.is_det() {
                         DetFuzz::new(handler).run();
                     } else {
                         match search_method {
                             SearchMethod::Gd => {
-                                GdSearch::new(handler).run(&mut thread_rng());
+                                GdSearch::new(handler).run(&mut rng::thread_rng());
                             },
                             SearchMethod::Random => {
                                 RandomSearch::new(handler).run();
@@ -120,6 +144,18 @@ pub fn fuzz_loop(
                             SearchMethod::Mb => {
                                 MbSearch::new(handler).run();
                             },
+                            SearchMethod::I2s => {
+                                let mut i2s = I2sSearch::new(handler);
+                                i2s.run();
+                                // The gradient of the tainted bytes gets the rest of the budget
+                                let handler = i2s.handler;
+                                if !handler.cond.is_done()
+                                    && handler.cond.is_tainted()
+                                    && !handler.is_stopped_or_skip()
+                                {
+                                    GdSearch::new(handler).run(&mut rng::thread_rng());
+                                }
+                            },
                         }
                     }
                 },
@@ -133,7 +169,14 @@ pub fn fuzz_loop(
                     }
                 },
                 FuzzType::AFLFuzz => {
-                    AFLFuzz::new(handler).run();
+                    if let Some((g, t)) = grammar_tree.as_ref() {
+                        GrammarFuzz::new(handler, g, &depot, t.clone()).run_havoc();
+                    } else {
+                        AFLFuzz::new(handler).run();
+                    }
+                },
+                FuzzType::CustomFuzz => {
+                    CustomFuzz::new(handler).run();
                 },
                 FuzzType::LenFuzz => {
                     LenFuzz::new(handler).run();
@@ -143,10 +186,17 @@ pub fn fuzz_loop(
                 },
                 FuzzType::OtherFuzz => {
                     warn!("Unknown fuzz type!!");
+                    drop(handler);
                 },
             }
+            let stats = &cur_executor.local_stats;
+            (stats.num_exec.into(), stats.num_inputs.into())
+        };
+        if let Some((g, t)) = grammar_tree.as_ref() {
+            grammar::patch_new_inputs(&depot, g, t, num_inputs);
         }
 
-        depot.update_entry(cond);
+        scheduler.finish(thread_id, cond, priority, num_exec, num_new);
     }
+    scheduler.release(thread_id);
 }
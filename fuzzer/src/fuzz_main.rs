@@ -1,6 +1,6 @@
 use crate::stats::*;
-use crate::dyncfg::{cfg::ControlFlowGraph, fparse::parse_targets_file};
-use angora_common::defs;
+use crate::dyncfg::{cfg::ControlFlowGraph, fparse::parse_targets_file, metric::parse_distance_metric};
+use angora_common::{config, defs};
 use chrono::prelude::Local;
 use std::{
     collections::HashMap,
@@ -14,56 +14,75 @@ use std::{
     thread, time,
 };
 
-use crate::{bind_cpu, branches, check_dep, command, depot, executor, fuzz_loop, stats};
+use crate::{
+    bind_cpu, branches, check_dep, checkpoint, command, depot, executor, fuzz_loop,
+    grammar::{self, Grammar},
+    options::FuzzOptions,
+    rng,
+    scheduler::Scheduler,
+    stats,
+    stop::{Progress, StopReason},
+    triage,
+};
 use ctrlc;
 use libc;
 use pretty_env_logger;
 
-pub fn fuzz_main(
-    mode: &str,
-    in_dir: &str,
-    out_dir: &str,
-    track_target: &str,
-    pargs: Vec<String>,
-    num_jobs: usize,
-    mem_limit: u64,
-    time_limit: u64,
-    search_method: &str,
-    sync_afl: bool,
-    enable_afl: bool,
-    enable_exploitation: bool,
-    cfg_input_file: &str,
-    sanopt_target: Option<&str>,
-    directed_only: bool,
-) {
+pub fn fuzz_main(mut opts: FuzzOptions) -> StopReason {
     pretty_env_logger::init();
 
-    let (seeds_dir, angora_out_dir) = initialize_directories(in_dir, out_dir, sync_afl);
-    let parmesan_info = parse_targets_file(Path::new(&cfg_input_file)).expect("Could not read cfg targets file");
-    let cfg = ControlFlowGraph::new(parmesan_info);
+    let seed = opts.seed.unwrap_or_else(rand::random);
+    rng::set_seed(seed);
+    rng::seed_thread(0);
+    info!("RNG seed: {}", seed);
+    if opts.deterministic && opts.num_jobs > 1 {
+        warn!("Deterministic mode runs a single fuzzing thread, ignoring -j {}", opts.num_jobs);
+        opts.num_jobs = 1;
+    }
+    if opts.deterministic && (opts.schedule == "annealing" || opts.sync_afl || opts.sync_dir.is_some()) {
+        warn!("The annealing schedule and syncing depend on the time, the run is not deterministic");
+    }
 
-    let command_option = command::CommandOpt::new(
-        mode,
-        track_target,
-        pargs,
-        &angora_out_dir,
-        search_method,
-        mem_limit,
-        time_limit,
-        enable_afl,
-        enable_exploitation,
-        cfg_input_file,
-        sanopt_target,
-        directed_only,
-    );
+    let (seeds_dir, angora_out_dir) = initialize_directories(&opts.in_dir, &opts.out_dir, opts.sync_afl, opts.resume);
+    let parmesan_info = parse_targets_file(Path::new(&opts.cfg_file)).expect("Could not read cfg targets file");
+    // Weighted targets need the metric that tells them apart
+    let metric = parse_distance_metric(opts.distance_metric.as_deref().unwrap_or(if parmesan_info.has_weights() {
+        "weighted"
+    } else {
+        "harmonic"
+    }));
+    // The learned CFG is restored from the checkpoint when resuming
+    let cfg = if opts.resume {
+        ControlFlowGraph::empty_new()
+    } else {
+        ControlFlowGraph::new(parmesan_info, metric.clone())
+    };
+
+    let command_option = command::CommandOpt::new(&opts, &angora_out_dir);
     info!("{:?}", command_option);
 
-    check_dep::check_dep(in_dir, out_dir, &command_option);
+    check_dep::check_dep(&opts.in_dir, &opts.out_dir, &command_option);
 
-    let depot = Arc::new(depot::Depot::new(seeds_dir, &angora_out_dir, RwLock::new(cfg.clone())));
+    let dict = match &opts.dict_file {
+        Some(f) => depot::Dictionary::load(Path::new(f)).expect("Could not read dictionary"),
+        None => Default::default(),
+    };
+    info!("{} tokens in the dictionary", dict.num_user());
+    let grammar = opts.grammar_file.as_ref().map(|f| {
+        Arc::new(Grammar::load(Path::new(f)).unwrap_or_else(|e| panic!("Could not load grammar: {}", e)))
+    });
+
+    let depot = Arc::new(depot::Depot::new(
+        seeds_dir,
+        &angora_out_dir,
+        RwLock::new(cfg.clone()),
+        depot::parse_schedule(&opts.schedule),
+        dict,
+    ));
     info!("{:?}", depot.dirs);
 
     let stats = Arc::new(RwLock::new(stats::ChartStats::new()));
+    stats.write().unwrap().set_seed(seed);
     let global_branches = Arc::new(branches::GlobalBranches::new(RwLock::new(cfg)));
     let fuzzer_stats = create_stats_file_and_write_pid(&angora_out_dir);
     let running = Arc::new(AtomicBool::new(true));
@@ -76,7 +95,20 @@ pub fn fuzz_main(
         stats.clone(),
     );
 
-    depot::sync_depot(&mut executor, running.clone(), &depot.dirs.seeds_dir);
+    // Before the seeds are run, so their conds are exported too
+    let peer_sync = opts.sync_dir.as_ref().map(|dir| {
+        let sync_id = opts.sync_id.as_ref().expect("Sync id is required with a sync directory");
+        depot::PeerSync::new(Path::new(dir), sync_id, &depot)
+    });
+
+    if opts.resume {
+        checkpoint::load_checkpoint(&depot, &global_branches, metric);
+    } else {
+        depot::sync_depot(&mut executor, running.clone(), &depot.dirs.seeds_dir);
+        if let Some(g) = grammar.as_ref() {
+            grammar::seed_depot(&mut executor, &depot, g, running.clone());
+        }
+    }
 
     if depot.empty() {
         error!("Failed to find any branches during dry run.");
@@ -89,35 +121,53 @@ pub fn fuzz_main(
     }
 
     // Enable ParmeSan directed-only mode
-    executor.set_directed(directed_only);
+    executor.set_directed(opts.directed_only);
 
+    let scheduler = Arc::new(Scheduler::new(depot.clone(), opts.num_jobs));
     let (handles, child_count) = init_cpus_and_run_fuzzing_threads(
-        num_jobs,
+        opts.num_jobs,
         &running,
         &command_option,
         &global_branches,
         &depot,
         &stats,
+        &scheduler,
+        &grammar,
+    );
+
+    let triage_handle = triage::triage_loop(
+        triage::Triage::new(&command_option, &depot.dirs.crashes_dir),
+        depot.clone(),
+        running.clone(),
     );
 
-    let log_file = match fs::File::create(angora_out_dir.join(defs::ANGORA_LOG_FILE)) {
+    let log_file = match fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(opts.resume)
+        .truncate(!opts.resume)
+        .open(angora_out_dir.join(defs::ANGORA_LOG_FILE))
+    {
         Ok(a) => a,
         Err(e) => {
             error!("FATAL: Could not create log file: {:?}", e);
             panic!();
         }
     };
-    main_thread_sync_and_log(
+    let reason = main_thread_sync_and_log(
         log_file,
-        out_dir,
-        sync_afl,
+        &opts,
+        peer_sync,
         running.clone(),
         &mut executor,
         &depot,
         &global_branches,
         &stats,
+        &scheduler,
         child_count,
     );
+    running.store(false, Ordering::SeqCst);
+    warn!("Stopped fuzzing: {}", reason);
 
     for handle in handles {
         if handle.join().is_err() {
@@ -125,13 +175,33 @@ pub fn fuzz_main(
         }
     }
 
+    // All fuzzing threads have returned their conds to the depot
+    checkpoint::save_checkpoint(&depot, &global_branches);
+    if let Err(e) = depot.dict.save_auto(&angora_out_dir.join(defs::AUTO_DICT_FILE)) {
+        warn!("Could not save auto dictionary: {:?}", e);
+    }
+
+    match triage_handle.join() {
+        Ok(mut triage) => {
+            triage.triage_crashes(depot.num_crashes.load(Ordering::Relaxed), &depot);
+            info!("Triaged crashes into {} buckets", triage.num_buckets());
+        },
+        Err(_) => error!("Error happened in triage thread!"),
+    }
+
     match fs::remove_file(&fuzzer_stats) {
         Ok(_) => (),
         Err(e) => warn!("Could not remove fuzzer stats file: {:?}", e),
     };
+    reason
 }
 
-fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathBuf, PathBuf) {
+fn initialize_directories(
+    in_dir: &str,
+    out_dir: &str,
+    sync_afl: bool,
+    resume: bool,
+) -> (PathBuf, PathBuf) {
     let angora_out_dir = if sync_afl {
         gen_path_afl(out_dir)
     } else {
@@ -139,12 +209,15 @@ fn initialize_directories(in_dir: &str, out_dir: &str, sync_afl: bool) -> (PathB
     };
 
     let restart = in_dir == "-";
-    if !restart {
+    if !restart && !resume {
         fs::create_dir(&angora_out_dir).expect("Output directory has existed!");
     }
 
     let out_dir = &angora_out_dir;
-    let seeds_dir = if restart {
+    let seeds_dir = if resume {
+        // Inputs are not replayed, the checkpoint already has their conds
+        out_dir.join(defs::INPUTS_DIR)
+    } else if restart {
         let orig_out_dir = out_dir.with_extension(Local::now().to_rfc3339());
         fs::rename(&out_dir, orig_out_dir.clone()).unwrap();
         fs::create_dir(&out_dir).unwrap();
@@ -195,6 +268,8 @@ fn init_cpus_and_run_fuzzing_threads(
     global_branches: &Arc<branches::GlobalBranches>,
     depot: &Arc<depot::Depot>,
     stats: &Arc<RwLock<stats::ChartStats>>,
+    scheduler: &Arc<Scheduler>,
+    grammar: &Option<Arc<Grammar>>,
 ) -> (Vec<thread::JoinHandle<()>>, Arc<AtomicUsize>) {
     let child_count = Arc::new(AtomicUsize::new(0));
     let mut handlers = vec![];
@@ -213,13 +288,16 @@ fn init_cpus_and_run_fuzzing_threads(
         let d = depot.clone();
         let b = global_branches.clone();
         let s = stats.clone();
+        let sc = scheduler.clone();
+        let g = grammar.clone();
         let cid = if bind_cpus { free_cpus[thread_id] } else { 0 };
         let handler = thread::spawn(move || {
             c.fetch_add(1, Ordering::SeqCst);
+            rng::seed_thread(thread_id as u64 + 1);
             if bind_cpus {
                 bind_cpu::bind_thread_to_cpu_core(cid);
             }
-            fuzz_loop::fuzz_loop(r, cmd, d, b, s);
+            fuzz_loop::fuzz_loop(r, cmd, d, b, s, sc, thread_id, g);
         });
         handlers.push(handler);
     }
@@ -228,45 +306,107 @@ fn init_cpus_and_run_fuzzing_threads(
 
 fn main_thread_sync_and_log(
     mut log_file: fs::File,
-    out_dir: &str,
-    sync_afl: bool,
+    opts: &FuzzOptions,
+    mut peer_sync: Option<depot::PeerSync>,
     running: Arc<AtomicBool>,
     executor: &mut executor::Executor,
     depot: &Arc<depot::Depot>,
     global_branches: &Arc<branches::GlobalBranches>,
     stats: &Arc<RwLock<stats::ChartStats>>,
+    scheduler: &Scheduler,
     child_count: Arc<AtomicUsize>,
-) {
+) -> StopReason {
     let mut last_explore_num = stats.read().unwrap().get_explore_num();
-    let sync_dir = Path::new(out_dir);
+    let sync_dir = Path::new(&opts.out_dir);
     let mut synced_ids = HashMap::new();
-    if sync_afl {
+    if opts.sync_afl {
         depot::sync_afl(executor, running.clone(), sync_dir, &mut synced_ids);
     }
+    if let Some(p) = peer_sync.as_mut() {
+        p.sync(executor, depot, running.clone());
+    }
     let mut sync_counter = 1;
-    show_stats(&mut log_file, depot, global_branches, stats);
+    let mut last_checkpoint = time::Instant::now();
+    let mut last_cfg_dump = time::Instant::now();
+    let mut last_rerank = time::Instant::now();
+    let mut last_num_inputs = depot.num_inputs.load(Ordering::Relaxed);
+    let mut last_new_input = time::Instant::now();
+    show_stats(&mut log_file, depot, global_branches, stats, scheduler);
     while running.load(Ordering::SeqCst) {
         thread::sleep(time::Duration::from_secs(5));
         sync_counter -= 1;
-        if sync_afl && sync_counter <= 0 {
-            depot::sync_afl(executor, running.clone(), sync_dir, &mut synced_ids);
+        if sync_counter <= 0 {
+            if opts.sync_afl {
+                depot::sync_afl(executor, running.clone(), sync_dir, &mut synced_ids);
+            }
+            if let Some(p) = peer_sync.as_mut() {
+                p.sync(executor, depot, running.clone());
+            }
             sync_counter = 12;
         }
 
-        show_stats(&mut log_file, depot, global_branches, stats);
+        if last_checkpoint.elapsed().as_secs() >= config::CHECKPOINT_INTERVAL {
+            checkpoint::save_checkpoint(depot, global_branches);
+            last_checkpoint = time::Instant::now();
+        }
+
+        if last_cfg_dump.elapsed().as_secs() >= config::CFG_DUMP_INTERVAL {
+            depot.dump_cfg();
+            last_cfg_dump = time::Instant::now();
+        }
+
+        if last_rerank.elapsed().as_secs() >= config::RERANK_INTERVAL {
+            depot.rerank_queue();
+            last_rerank = time::Instant::now();
+        }
+
+        show_stats(&mut log_file, depot, global_branches, stats, scheduler);
+        let num_inputs = depot.num_inputs.load(Ordering::Relaxed);
+        if num_inputs != last_num_inputs {
+            last_num_inputs = num_inputs;
+            last_new_input = time::Instant::now();
+        }
+        let progress = get_progress(depot, stats, last_new_input.elapsed());
+        if let Some(reason) = opts.stop_conditions.check(&progress) {
+            return reason;
+        }
         if Arc::strong_count(&child_count) == 1 {
             let s = stats.read().unwrap();
             let cur_explore_num = s.get_explore_num();
             if cur_explore_num == 0 {
                 warn!("There is none constraint in the seeds, please ensure the inputs are vaild in the seed directory, or the program is ran correctly, or the read functions have been marked as source.");
-                break;
+                return StopReason::NoConstraints;
             } else {
                 if cur_explore_num == last_explore_num {
                     info!("Solve all constraints!!");
-                    break;
+                    return StopReason::Exhausted;
                 }
                 last_explore_num = cur_explore_num;
             }
         }
     }
+    StopReason::Interrupted
+}
+
+fn get_progress(
+    depot: &Arc<depot::Depot>,
+    stats: &Arc<RwLock<stats::ChartStats>>,
+    idle: time::Duration,
+) -> Progress {
+    let infos = depot.get_target_infos();
+    let cfg = depot.cfg.read().unwrap();
+    let targets = cfg.get_all_targets();
+    Progress {
+        elapsed: depot.start_time.elapsed(),
+        num_exec: stats.read().unwrap().get_num_exec(),
+        num_crashes: depot.num_crashes.load(Ordering::Relaxed),
+        num_target_crashes: infos.values().map(|i| i.num_crashes).sum(),
+        num_targets: targets.len(),
+        num_reached: targets
+            .iter()
+            .filter(|t| infos.get(t).is_some_and(|i| i.first_reached.is_some()))
+            .count(),
+        num_solved: targets.iter().filter(|t| cfg.is_solved(**t)).count(),
+        idle,
+    }
 }
@@ -0,0 +1,57 @@
+// Grammar mode (`-g`): inputs are generated as derivation trees of a
+// context-free grammar. The tree of every input in the queue is kept in the
+// `trees` directory, and the searches mutate its subtrees so the input stays
+// in the grammar. The tainted bytes of a cond are mapped to the nodes that
+// derive them: the byte-level searches are only used if they lie in free
+// nodes, otherwise the covering subtrees are regenerated.
+
+mod rules;
+mod tree;
+
+pub use self::{
+    rules::Grammar,
+    tree::{covering_nodes, is_free_taint, Tree},
+};
+
+use crate::{depot::Depot, executor::Executor, rng};
+use angora_common::config;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+// Run random derivations of the grammar as seeds
+pub fn seed_depot(executor: &mut Executor, depot: &Depot, grammar: &Grammar, running: Arc<AtomicBool>) {
+    executor.local_stats.clear();
+    let mut rng = rng::thread_rng();
+    for _ in 0..config::GRAMMAR_NUM_SEEDS {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        let tree = grammar.generate(&mut rng);
+        let buf = tree.to_bytes();
+        if buf.len() >= config::MAX_INPUT_LEN {
+            continue;
+        }
+        executor.run_sync(&buf);
+        if let Some(id) = executor.last_saved {
+            depot.save_tree(id, &tree);
+        }
+    }
+    info!("sync {} file from the grammar.", executor.local_stats.num_inputs);
+    executor.update_log();
+}
+
+// Keep the trees of the inputs with an id from `from` on that a byte-level
+// search derived from the tree by changing its free nodes only.
+pub fn patch_new_inputs(depot: &Depot, grammar: &Grammar, tree: &Tree, from: usize) {
+    let to = depot.num_inputs.load(Ordering::Relaxed);
+    for id in from..to {
+        if depot.has_tree(id) {
+            continue;
+        }
+        if let Some(patched) = tree.patch(grammar, &depot.get_input_buf(id)) {
+            depot.save_tree(id, &patched);
+        }
+    }
+}
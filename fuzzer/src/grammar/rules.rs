@@ -0,0 +1,188 @@
+use super::Tree;
+use angora_common::config;
+use rand::Rng;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+// {"start": "<json>",
+//  "rules": {"<json>": [["<value>"]], "<value>": [["true"], ["<number>"]], ...},
+//  "free": ["<number>"]}
+// Every alternative is a list of symbols. A symbol of the form "<name>" is a
+// nonterminal and needs a rule, anything else is a terminal. The bytes derived
+// from a free nonterminal may be changed by the byte-level searches.
+#[derive(Deserialize)]
+struct GrammarFile {
+    start: String,
+    rules: HashMap<String, Vec<Vec<String>>>,
+    #[serde(default)]
+    free: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Symbol {
+    Terminal(Vec<u8>),
+    NonTerminal(String),
+}
+
+pub struct Grammar {
+    start: String,
+    rules: HashMap<String, Vec<Vec<Symbol>>>,
+    free: HashSet<String>,
+    // Fewest nodes in a derivation of every alternative
+    costs: HashMap<String, Vec<usize>>,
+}
+
+fn is_nonterminal(s: &str) -> bool {
+    s.len() > 2 && s.starts_with('<') && s.ends_with('>')
+}
+
+impl Grammar {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let file: GrammarFile = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+        Self::new(file)
+    }
+
+    fn new(file: GrammarFile) -> Result<Self, String> {
+        if !file.rules.contains_key(&file.start) {
+            return Err(format!("No rule for the start symbol {}", file.start));
+        }
+        let mut rules = HashMap::new();
+        for (name, alternatives) in file.rules.iter() {
+            if alternatives.is_empty() {
+                return Err(format!("No alternatives for {}", name));
+            }
+            let mut parsed = vec![];
+            for alternative in alternatives.iter() {
+                let mut symbols = vec![];
+                for s in alternative.iter() {
+                    if !is_nonterminal(s) {
+                        symbols.push(Symbol::Terminal(s.as_bytes().to_vec()));
+                    } else if file.rules.contains_key(s) {
+                        symbols.push(Symbol::NonTerminal(s.clone()));
+                    } else {
+                        return Err(format!("No rule for {}, used by {}", s, name));
+                    }
+                }
+                parsed.push(symbols);
+            }
+            rules.insert(name.clone(), parsed);
+        }
+        for name in file.free.iter() {
+            if !rules.contains_key(name) {
+                return Err(format!("No rule for the free symbol {}", name));
+            }
+        }
+        let costs = min_costs(&rules);
+        for (name, c) in costs.iter() {
+            if c.iter().all(|c| *c == usize::MAX) {
+                return Err(format!("{} derives no finite input", name));
+            }
+        }
+        Ok(Self {
+            start: file.start,
+            rules,
+            free: file.free.into_iter().collect(),
+            costs,
+        })
+    }
+
+    pub fn is_free(&self, symbol: &str) -> bool {
+        self.free.contains(symbol)
+    }
+
+    pub fn generate<R: Rng>(&self, rng: &mut R) -> Tree {
+        self.generate_from(&self.start, 0, rng)
+    }
+
+    // A random derivation of the symbol. Below GRAMMAR_MAX_DEPTH only the
+    // cheapest alternatives are taken, so the tree stays finite.
+    pub fn generate_from<R: Rng>(&self, symbol: &str, depth: usize, rng: &mut R) -> Tree {
+        let alternatives = &self.rules[symbol];
+        let rule = if depth >= config::GRAMMAR_MAX_DEPTH {
+            let costs = &self.costs[symbol];
+            (0..alternatives.len()).min_by_key(|i| costs[*i]).unwrap()
+        } else {
+            rng.gen_range(0, alternatives.len())
+        };
+        let children = alternatives[rule]
+            .iter()
+            .map(|s| match s {
+                Symbol::Terminal(t) => Tree::Leaf(t.clone()),
+                Symbol::NonTerminal(n) => self.generate_from(n, depth + 1, rng),
+            })
+            .collect();
+        Tree::Node {
+            symbol: symbol.to_string(),
+            rule: Some(rule),
+            children,
+        }
+    }
+}
+
+fn min_costs(rules: &HashMap<String, Vec<Vec<Symbol>>>) -> HashMap<String, Vec<usize>> {
+    let mut costs: HashMap<String, Vec<usize>> = rules
+        .iter()
+        .map(|(n, a)| (n.clone(), vec![usize::MAX; a.len()]))
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (name, alternatives) in rules.iter() {
+            for (i, symbols) in alternatives.iter().enumerate() {
+                let cost = symbols.iter().fold(1usize, |acc, s| {
+                    let c = match s {
+                        Symbol::Terminal(_) => 1,
+                        Symbol::NonTerminal(n) => *costs[n].iter().min().unwrap(),
+                    };
+                    acc.saturating_add(c)
+                });
+                if cost < costs[name][i] {
+                    costs.get_mut(name).unwrap()[i] = cost;
+                    changed = true;
+                }
+            }
+        }
+    }
+    costs
+}
+
+#[cfg(test)]
+pub(super) fn test_grammar() -> Grammar {
+    let file = serde_json::from_str(
+        r#"{"start": "<list>",
+            "rules": {"<list>": [["[", "<items>", "]"]],
+                      "<items>": [["<num>"], ["<num>", ",", "<items>"]],
+                      "<num>": [["<digit>"], ["<digit>", "<num>"]],
+                      "<digit>": [["0"], ["1"], ["7"]]},
+            "free": ["<num>"]}"#,
+    )
+    .unwrap();
+    Grammar::new(file).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grammar_load() {
+        let g = test_grammar();
+        assert_eq!(g.costs["<digit>"], vec![2, 2, 2]);
+        assert_eq!(g.costs["<num>"], vec![3, 6]);
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let buf = g.generate(&mut rng).to_bytes();
+            assert_eq!(buf[0], b'[');
+            assert_eq!(*buf.last().unwrap(), b']');
+        }
+
+        let undefined = serde_json::from_str(r#"{"start": "<a>", "rules": {"<a>": [["<b>"]]}}"#).unwrap();
+        assert!(Grammar::new(undefined).is_err());
+        let infinite = serde_json::from_str(r#"{"start": "<a>", "rules": {"<a>": [["x", "<a>"]]}}"#).unwrap();
+        assert!(Grammar::new(infinite).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use super::Grammar;
+use angora_common::tag::TagSeg;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Tree {
+    Leaf(Vec<u8>),
+    // The rule is None if a byte-level search changed the bytes of a free
+    // node, they are kept as a single leaf then.
+    Node {
+        symbol: String,
+        rule: Option<usize>,
+        children: Vec<Tree>,
+    },
+}
+
+// A nonterminal node of the tree and the bytes [begin, end) it derives
+#[derive(Debug)]
+pub struct NodeSpan {
+    pub path: Vec<usize>,
+    pub symbol: String,
+    pub begin: usize,
+    pub end: usize,
+    // The node or one of its ancestors is free
+    pub free: bool,
+}
+
+impl Tree {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.write(&mut buf);
+        buf
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        match self {
+            Tree::Leaf(bytes) => buf.extend_from_slice(bytes),
+            Tree::Node { children, .. } => {
+                for child in children.iter() {
+                    child.write(buf);
+                }
+            },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Tree::Leaf(bytes) => bytes.len(),
+            Tree::Node { children, .. } => children.iter().map(|c| c.len()).sum(),
+        }
+    }
+
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Tree::Leaf(_) => None,
+            Tree::Node { symbol, .. } => Some(symbol),
+        }
+    }
+
+    // The nonterminal nodes in preorder
+    pub fn nodes(&self, grammar: &Grammar) -> Vec<NodeSpan> {
+        let mut nodes = vec![];
+        self.collect_nodes(grammar, &mut vec![], &mut 0, false, &mut nodes);
+        nodes
+    }
+
+    fn collect_nodes(
+        &self,
+        grammar: &Grammar,
+        path: &mut Vec<usize>,
+        pos: &mut usize,
+        in_free: bool,
+        nodes: &mut Vec<NodeSpan>,
+    ) {
+        match self {
+            Tree::Leaf(bytes) => *pos += bytes.len(),
+            Tree::Node { symbol, children, .. } => {
+                let free = in_free || grammar.is_free(symbol);
+                let idx = nodes.len();
+                nodes.push(NodeSpan {
+                    path: path.clone(),
+                    symbol: symbol.clone(),
+                    begin: *pos,
+                    end: *pos,
+                    free,
+                });
+                for (i, child) in children.iter().enumerate() {
+                    path.push(i);
+                    child.collect_nodes(grammar, path, pos, free, nodes);
+                    path.pop();
+                }
+                nodes[idx].end = *pos;
+            },
+        }
+    }
+
+    pub fn get(&self, path: &[usize]) -> Option<&Tree> {
+        match (path.split_first(), self) {
+            (None, _) => Some(self),
+            (Some((i, rest)), Tree::Node { children, .. }) => children.get(*i)?.get(rest),
+            _ => None,
+        }
+    }
+
+    // Returns false if there is no node at the path
+    pub fn replace(&mut self, path: &[usize], subtree: Tree) -> bool {
+        match (path.split_first(), self) {
+            (None, node) => {
+                *node = subtree;
+                true
+            },
+            (Some((i, rest)), Tree::Node { children, .. }) => match children.get_mut(*i) {
+                Some(child) => child.replace(rest, subtree),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    // The tree of an input that differs from this one only in the bytes of
+    // free nodes, e.g. after a byte-level search.
+    pub fn patch(&self, grammar: &Grammar, buf: &[u8]) -> Option<Tree> {
+        if buf.len() != self.len() {
+            return None;
+        }
+        self.patch_node(grammar, buf, &mut 0)
+    }
+
+    fn patch_node(&self, grammar: &Grammar, buf: &[u8], pos: &mut usize) -> Option<Tree> {
+        let begin = *pos;
+        *pos += self.len();
+        let new = &buf[begin..*pos];
+        match self {
+            Tree::Leaf(bytes) => {
+                if new == &bytes[..] {
+                    Some(self.clone())
+                } else {
+                    None
+                }
+            },
+            Tree::Node { symbol, rule, children } => {
+                if grammar.is_free(symbol) {
+                    if new == &self.to_bytes()[..] {
+                        return Some(self.clone());
+                    }
+                    return Some(Tree::Node {
+                        symbol: symbol.clone(),
+                        rule: None,
+                        children: vec![Tree::Leaf(new.to_vec())],
+                    });
+                }
+                let mut child_pos = begin;
+                let children = children
+                    .iter()
+                    .map(|c| c.patch_node(grammar, buf, &mut child_pos))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Tree::Node {
+                    symbol: symbol.clone(),
+                    rule: *rule,
+                    children,
+                })
+            },
+        }
+    }
+}
+
+// Whether all the tainted bytes are derived from free nodes, so a byte-level
+// search keeps the input in the grammar.
+pub fn is_free_taint(nodes: &[NodeSpan], offsets: &[TagSeg]) -> bool {
+    offsets.iter().all(|off| {
+        nodes
+            .iter()
+            .any(|n| n.free && n.begin <= off.begin as usize && off.end as usize <= n.end)
+    })
+}
+
+// The deepest nodes that derive all the bytes of a tainted segment, without
+// the ones inside another of them.
+pub fn covering_nodes<'a>(nodes: &'a [NodeSpan], offsets: &[TagSeg]) -> Vec<&'a NodeSpan> {
+    let mut covering: Vec<&NodeSpan> = vec![];
+    for off in offsets.iter() {
+        // Preorder, so the last one is the deepest
+        let node = nodes
+            .iter()
+            .rev()
+            .find(|n| n.begin <= off.begin as usize && off.end as usize <= n.end);
+        if let Some(node) = node {
+            if !covering.iter().any(|c| c.path == node.path) {
+                covering.push(node);
+            }
+        }
+    }
+    let paths: Vec<Vec<usize>> = covering.iter().map(|c| c.path.clone()).collect();
+    covering.retain(|c| {
+        !paths
+            .iter()
+            .any(|p| p.len() < c.path.len() && c.path.starts_with(p))
+    });
+    covering
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::rules::test_grammar, *};
+
+    fn seg(begin: u32, end: u32) -> TagSeg {
+        TagSeg {
+            sign: false,
+            begin,
+            end,
+        }
+    }
+
+    fn num(digits: &[u8]) -> Tree {
+        let digit = |d: u8| Tree::Node {
+            symbol: "<digit>".to_string(),
+            rule: Some(0),
+            children: vec![Tree::Leaf(vec![d])],
+        };
+        let (first, rest) = digits.split_first().unwrap();
+        let children = if rest.is_empty() {
+            vec![digit(*first)]
+        } else {
+            vec![digit(*first), num(rest)]
+        };
+        Tree::Node {
+            symbol: "<num>".to_string(),
+            rule: Some(children.len() - 1),
+            children,
+        }
+    }
+
+    // [10,7]
+    fn list() -> Tree {
+        let items = Tree::Node {
+            symbol: "<items>".to_string(),
+            rule: Some(1),
+            children: vec![
+                num(b"10"),
+                Tree::Leaf(b",".to_vec()),
+                Tree::Node {
+                    symbol: "<items>".to_string(),
+                    rule: Some(0),
+                    children: vec![num(b"7")],
+                },
+            ],
+        };
+        Tree::Node {
+            symbol: "<list>".to_string(),
+            rule: Some(0),
+            children: vec![Tree::Leaf(b"[".to_vec()), items, Tree::Leaf(b"]".to_vec())],
+        }
+    }
+
+    #[test]
+    fn tree_taint_mapping() {
+        let g = test_grammar();
+        let tree = list();
+        assert_eq!(tree.to_bytes(), b"[10,7]".to_vec());
+        let nodes = tree.nodes(&g);
+        assert!(is_free_taint(&nodes, &[seg(1, 3), seg(4, 5)]));
+        assert!(!is_free_taint(&nodes, &[seg(3, 4)]));
+
+        // The digits of the segments
+        let covering = covering_nodes(&nodes, &[seg(2, 3), seg(4, 5), seg(1, 2)]);
+        let paths: Vec<_> = covering.iter().map(|n| n.path.clone()).collect();
+        assert_eq!(paths, vec![vec![1, 0, 1, 0], vec![1, 2, 0, 0], vec![1, 0, 0]]);
+        // The items around the comma, the node of the digit is inside of it
+        let covering = covering_nodes(&nodes, &[seg(4, 5), seg(3, 5)]);
+        let paths: Vec<_> = covering.iter().map(|n| n.path.clone()).collect();
+        assert_eq!(paths, vec![vec![1]]);
+    }
+
+    #[test]
+    fn tree_replace_and_patch() {
+        let g = test_grammar();
+        let mut tree = list();
+        assert!(tree.replace(&[1, 2, 0], num(b"01")));
+        assert!(!tree.replace(&[1, 5], num(b"1")));
+        assert_eq!(tree.to_bytes(), b"[10,01]".to_vec());
+        assert_eq!(tree.get(&[1, 2, 0]).unwrap().symbol(), Some("<num>"));
+
+        let patched = tree.patch(&g, b"[99,01]").unwrap();
+        assert_eq!(patched.to_bytes(), b"[99,01]".to_vec());
+        assert_eq!(patched.get(&[1, 2, 0]), tree.get(&[1, 2, 0]));
+        // The comma is not free
+        assert!(tree.patch(&g, b"[10;01]").is_none());
+        assert!(tree.patch(&g, b"[10,011]").is_none());
+    }
+}
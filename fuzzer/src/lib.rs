@@ -1,4 +1,5 @@
 #![cfg_attr(feature = "unstable", feature(core_intrinsics))]
+#![cfg_attr(all(test, feature = "unstable"), feature(test))]
 
 #[macro_use]
 extern crate log;
@@ -13,6 +14,8 @@ extern crate csv;
 extern crate serde_derive;
 
 extern crate itertools;
+#[cfg(all(test, feature = "unstable"))]
+extern crate test;
 
 mod branches;
 mod cond_stmt;
@@ -26,13 +29,30 @@ pub mod track;
 mod fuzz_loop;
 mod fuzz_main;
 mod fuzz_type;
+mod scheduler;
 
+mod acquire;
 mod bind_cpu;
 mod check_dep;
+mod checkpoint;
+mod cmin;
 mod command;
+mod custom_mutator;
+mod options;
+mod rng;
+mod sanitizer;
+mod stop;
+mod tmin;
 mod tmpfs;
+mod triage;
 
 mod dyncfg;
+mod grammar;
 //mod directed;
 
 pub use crate::fuzz_main::fuzz_main;
+pub use crate::tmin::tmin_main;
+pub use crate::cmin::cmin_main;
+pub use crate::acquire::acquire_main;
+pub use crate::options::FuzzOptions;
+pub use crate::stop::{StopConditions, StopReason};
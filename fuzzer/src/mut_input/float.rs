@@ -0,0 +1,96 @@
+// 4 and 8 byte IEEE floats as unsigned integers in the same order as the
+// floats, so the distance between two of them is their distance in ULPs and
+// adding one moves to the next float. The NaNs are beyond the infinities.
+
+fn sign_bit(size: usize) -> u64 {
+    if size == 4 {
+        1 << 31
+    } else {
+        1 << 63
+    }
+}
+
+fn mask(size: usize) -> u64 {
+    if size == 4 {
+        u32::MAX as u64
+    } else {
+        u64::MAX
+    }
+}
+
+pub fn to_ordered(bits: u64, size: usize) -> u64 {
+    let bits = bits & mask(size);
+    if bits & sign_bit(size) != 0 {
+        !bits & mask(size)
+    } else {
+        bits | sign_bit(size)
+    }
+}
+
+pub fn from_ordered(ord: u64, size: usize) -> u64 {
+    let ord = ord & mask(size);
+    if ord & sign_bit(size) != 0 {
+        ord & !sign_bit(size)
+    } else {
+        !ord & mask(size)
+    }
+}
+
+pub fn to_f64(bits: u64, size: usize) -> f64 {
+    if size == 4 {
+        f32::from_bits(bits as u32) as f64
+    } else {
+        f64::from_bits(bits)
+    }
+}
+
+// Move the float delta ULPs up or down, the sign is taken care of
+pub fn step(bits: u64, size: usize, direction: bool, delta: u64) -> u64 {
+    let ord = to_ordered(bits, size);
+    let ord = if direction {
+        ord.saturating_add(delta).min(mask(size))
+    } else {
+        ord.saturating_sub(delta)
+    };
+    from_ordered(ord, size)
+}
+
+pub fn interesting_floats(size: usize) -> Vec<u64> {
+    if size == 4 {
+        [0.0, -0.0, 1.0, -1.0, 0.5, f32::EPSILON, f32::MIN_POSITIVE, f32::MAX, f32::MIN]
+            .iter()
+            .map(|f: &f32| f.to_bits() as u64)
+            .chain([f32::INFINITY, f32::NEG_INFINITY, f32::NAN].iter().map(|f| f.to_bits() as u64))
+            .collect()
+    } else {
+        [0.0, -0.0, 1.0, -1.0, 0.5, f64::EPSILON, f64::MIN_POSITIVE, f64::MAX, f64::MIN]
+            .iter()
+            .map(|f: &f64| f.to_bits())
+            .chain([f64::INFINITY, f64::NEG_INFINITY, f64::NAN].iter().map(|f| f.to_bits()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_order() {
+        let vals = [f64::NEG_INFINITY, -2.5, -1e-300, -0.0, 0.0, 1e-300, 1.0, 2.5, f64::INFINITY];
+        let ords: Vec<u64> = vals.iter().map(|v| to_ordered(v.to_bits(), 8)).collect();
+        assert!(ords.windows(2).all(|w| w[0] < w[1]));
+        for v in vals.iter() {
+            assert_eq!(from_ordered(to_ordered(v.to_bits(), 8), 8), v.to_bits());
+        }
+
+        let one = 1.0f32.to_bits() as u64;
+        let up = step(one, 4, true, 1);
+        assert_eq!(f32::from_bits(up as u32), 1.0 + f32::EPSILON);
+        assert_eq!(step(up, 4, false, 1), one);
+        // Across zero
+        let neg = step(0.0f32.to_bits() as u64, 4, false, 2);
+        assert!(f32::from_bits(neg as u32) < 0.0);
+        assert_eq!(to_ordered(neg, 4) + 2, to_ordered(0.0f32.to_bits() as u64, 4));
+    }
+}
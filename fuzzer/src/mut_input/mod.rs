@@ -1,3 +1,4 @@
+pub mod float;
 mod mut_input;
 pub mod offsets;
 mod rw;
@@ -1,8 +1,8 @@
 use super::*;
-use crate::search;
+use crate::{rng, search};
 use angora_common::config;
 
-use rand::{self, distributions::Uniform, Rng};
+use rand::{distributions::Uniform, Rng};
 
 use std::{fmt, u8};
 //use std::u16;
@@ -13,6 +13,8 @@ struct InputMeta {
     sign: bool,
     offset: usize,
     size: usize,
+    // An IEEE float, updated in ULPs
+    float: bool,
 }
 
 #[derive(Clone)]
@@ -71,17 +73,39 @@ impl MutInput {
     fn push(&mut self, mut ele: Vec<u8>, sign: bool) {
         if ele.len() != 1 && ele.len() != 2 && ele.len() != 4 && ele.len() != 8 {
             for _ in 0..ele.len() {
-                self.meta.push(InputMeta::new(sign, self.value.len(), 1));
+                self.meta.push(InputMeta::new(sign, self.value.len(), 1, false));
             }
         } else {
             self.meta
-                .push(InputMeta::new(sign, self.value.len(), ele.len()));
+                .push(InputMeta::new(sign, self.value.len(), ele.len(), false));
         }
         self.value.append(&mut ele);
     }
 
+    // The entries of the size of a float cmp are read as floats
+    pub fn mark_floats(&mut self, size: usize) {
+        if size != 4 && size != 8 {
+            return;
+        }
+        for info in self.meta.iter_mut() {
+            if info.size == size {
+                info.float = true;
+            }
+        }
+    }
+
+    pub fn is_float(&self, index: usize) -> bool {
+        self.meta[index].float
+    }
+
     pub fn update(&mut self, index: usize, direction: bool, delta: u64) {
         let info = &self.meta[index];
+        if info.float {
+            let bits = self.get_entry(index);
+            let size = info.size;
+            self.set(index, float::step(bits, size, direction, delta));
+            return;
+        }
         update_val_in_buf(
             &mut self.value,
             info.sign,
@@ -149,7 +173,7 @@ impl MutInput {
     }
 
     pub fn randomize_all(&mut self) {
-        let mut rng = rand::thread_rng();
+        let mut rng = rng::thread_rng();
         self.randomize_all_with_weight(&mut rng, 3);
     }
 
@@ -207,7 +231,11 @@ impl MutInput {
                     // set interesting value
                     let entry_idx: u32 = rng.gen_range(0, entry_len as u32);
                     let n = self.get_entry_len(entry_idx as usize);
-                    let vals = search::get_interesting_bytes(n);
+                    let vals = if self.is_float(entry_idx as usize) {
+                        float::interesting_floats(n)
+                    } else {
+                        search::get_interesting_bytes(n).to_vec()
+                    };
                     let wh = rng.gen_range(0, vals.len() as u32);
                     self.set(entry_idx as usize, vals[wh as usize]);
                 }
@@ -0,0 +1,80 @@
+// Options of a fuzzing campaign, from the command line of the fuzzer. The
+// command of the target (see `CommandOpt`) is built from them as well, the
+// tools that only run the target (cmin, tmin) start from the defaults.
+
+use crate::stop::StopConditions;
+use angora_common::config;
+
+#[derive(Clone, Debug)]
+pub struct FuzzOptions {
+    pub mode: String,
+    // "-" to continue in the output directory of a previous run
+    pub in_dir: String,
+    pub out_dir: String,
+    // "-" if there is none
+    pub track_target: String,
+    // The fast binary and its arguments
+    pub pargs: Vec<String>,
+    pub num_jobs: usize,
+    pub mem_limit: u64,
+    pub time_limit: u64,
+    pub search_method: String,
+    pub sync_afl: bool,
+    pub enable_afl: bool,
+    pub enable_exploitation: bool,
+    // The targets file
+    pub cfg_file: String,
+    // The sanitized builds, "[KIND=]PROM[:TARGETS]"
+    pub sanopt_targets: Vec<String>,
+    pub directed_only: bool,
+    pub resume: bool,
+    // Weighted if the targets file has weights, harmonic otherwise
+    pub distance_metric: Option<String>,
+    pub sync_dir: Option<String>,
+    pub sync_id: Option<String>,
+    pub schedule: String,
+    pub dict_file: Option<String>,
+    pub grammar_file: Option<String>,
+    // Shared library of the custom mutator
+    pub custom_mutator: Option<String>,
+    // GdSearch keeps the conds before the target that share its bytes
+    pub joint_conds: bool,
+    pub stop_conditions: StopConditions,
+    // Random if it is not given
+    pub seed: Option<u64>,
+    pub deterministic: bool,
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            mode: "llvm".to_string(),
+            in_dir: "-".to_string(),
+            out_dir: String::new(),
+            track_target: "-".to_string(),
+            pargs: vec![],
+            num_jobs: 1,
+            mem_limit: config::MEM_LIMIT,
+            time_limit: config::TIME_LIMIT,
+            search_method: "gd".to_string(),
+            sync_afl: false,
+            enable_afl: true,
+            enable_exploitation: true,
+            cfg_file: String::new(),
+            sanopt_targets: vec![],
+            directed_only: false,
+            resume: false,
+            distance_metric: None,
+            sync_dir: None,
+            sync_id: None,
+            schedule: "round_robin".to_string(),
+            dict_file: None,
+            grammar_file: None,
+            custom_mutator: None,
+            joint_conds: false,
+            stop_conditions: Default::default(),
+            seed: None,
+            deterministic: false,
+        }
+    }
+}
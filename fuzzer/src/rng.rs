@@ -0,0 +1,95 @@
+// Randomness of the fuzzer. Every thread has its own generator, seeded from
+// the seed of the campaign (`--seed`, random if it is not given) and an id:
+// 0 for the main thread, 1.. for the fuzzing threads. A run can be repeated
+// by giving it the seed of the first one, it is in chart_stat.json.
+// Use `rng::thread_rng()` and `rng::random()` instead of the ones of rand.
+
+use rand::{distributions::Standard, prelude::Distribution, rngs::StdRng, Error, Rng, RngCore, SeedableRng};
+use std::{
+    cell::RefCell,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+static SEED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // Threads that are not seeded, e.g. the triage, do not fuzz
+    static THREAD_RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+pub fn set_seed(seed: u64) {
+    SEED.store(seed, Ordering::SeqCst);
+}
+
+pub fn get_seed() -> u64 {
+    SEED.load(Ordering::SeqCst)
+}
+
+// SplitMix64, close seeds give unrelated streams
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub fn derive_seed(seed: u64, id: u64) -> u64 {
+    mix(seed ^ mix(id))
+}
+
+// Restart the generator of the current thread from the campaign seed
+pub fn seed_thread(id: u64) {
+    let seed = derive_seed(get_seed(), id);
+    THREAD_RNG.with(|r| *r.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// Handle to the generator of the current thread, like rand::ThreadRng
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FuzzRng;
+
+impl RngCore for FuzzRng {
+    fn next_u32(&mut self) -> u32 {
+        THREAD_RNG.with(|r| r.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        THREAD_RNG.with(|r| r.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        THREAD_RNG.with(|r| r.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        THREAD_RNG.with(|r| r.borrow_mut().try_fill_bytes(dest))
+    }
+}
+
+pub fn thread_rng() -> FuzzRng {
+    FuzzRng
+}
+
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    thread_rng().gen()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rng_seeded() {
+        set_seed(42);
+        seed_thread(1);
+        let a: Vec<u64> = (0..4).map(|_| random()).collect();
+        seed_thread(1);
+        let b: Vec<u64> = (0..4).map(|_| thread_rng().gen()).collect();
+        assert_eq!(a, b);
+        seed_thread(2);
+        assert_ne!(random::<u64>(), a[0]);
+        assert_ne!(derive_seed(42, 1), derive_seed(43, 1));
+    }
+}
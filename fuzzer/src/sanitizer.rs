@@ -0,0 +1,144 @@
+// Sanitized builds of the target for SanOpt. Every build is run with the
+// options of its runtime, and reports a bug with its own exit code (or by
+// aborting). A build can be given the targets it covers, e.g. the cfg file
+// of the target acquisition of that sanitizer, and the target conds are
+// fuzzed on the first build that covers them.
+
+use crate::{check_dep, dyncfg::cfg::CmpId, dyncfg::fparse};
+use angora_common::defs;
+use std::{collections::HashSet, fmt, path::Path};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SanitizerKind {
+    Asan,
+    Ubsan,
+    Msan,
+}
+
+static SANITIZER_KINDS: [SanitizerKind; 3] =
+    [SanitizerKind::Asan, SanitizerKind::Ubsan, SanitizerKind::Msan];
+
+impl SanitizerKind {
+    pub fn all() -> &'static [SanitizerKind] {
+        &SANITIZER_KINDS
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        SANITIZER_KINDS.iter().find(|k| k.name() == name).copied()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SanitizerKind::Asan => "asan",
+            SanitizerKind::Ubsan => "ubsan",
+            SanitizerKind::Msan => "msan",
+        }
+    }
+
+    // The runtime linked into the binary, ASan if none is found
+    pub fn detect(bin: &str) -> Self {
+        if check_dep::contains_symbol(bin, "__msan_init") {
+            SanitizerKind::Msan
+        } else if check_dep::contains_symbol(bin, "__ubsan_handle")
+            && !check_dep::contains_symbol(bin, "__asan_init")
+        {
+            SanitizerKind::Ubsan
+        } else {
+            SanitizerKind::Asan
+        }
+    }
+
+    // The options of the runtime while fuzzing
+    pub fn options(&self) -> (&'static str, &'static str) {
+        match self {
+            SanitizerKind::Asan => (defs::ASAN_OPTIONS_VAR, defs::ASAN_OPTIONS_CONTENT),
+            SanitizerKind::Ubsan => (defs::UBSAN_OPTIONS_VAR, defs::UBSAN_OPTIONS_CONTENT),
+            SanitizerKind::Msan => (defs::MSAN_OPTIONS_VAR, defs::MSAN_OPTIONS_CONTENT),
+        }
+    }
+
+    // The exit code of a report, ASan aborts
+    pub fn error_code(&self) -> Option<i32> {
+        match self {
+            SanitizerKind::Asan => None,
+            SanitizerKind::Ubsan => Some(defs::UBSAN_ERROR_CODE),
+            SanitizerKind::Msan => Some(defs::MSAN_ERROR_CODE),
+        }
+    }
+
+    // Whether a sanitized binary exited with a report of any of the runtimes
+    pub fn is_error_code(code: i32) -> bool {
+        SANITIZER_KINDS.iter().any(|k| k.error_code() == Some(code))
+    }
+}
+
+impl fmt::Display for SanitizerKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sanitizer {
+    pub kind: SanitizerKind,
+    pub bin: String,
+    // All the targets if None
+    pub targets: Option<HashSet<CmpId>>,
+}
+
+impl Sanitizer {
+    // `[KIND=]BIN[:TARGETS]`, the kind is detected from the binary if it is
+    // not given, and TARGETS is a cfg file with the targets the build covers.
+    pub fn parse(spec: &str) -> Self {
+        let (kind, rest) = match spec.find('=') {
+            Some(pos) => {
+                let name = &spec[..pos];
+                let kind = SanitizerKind::from_name(name)
+                    .unwrap_or_else(|| panic!("Unknown sanitizer {:?}, use asan, ubsan or msan", name));
+                (Some(kind), &spec[pos + 1..])
+            },
+            None => (None, spec),
+        };
+        let (bin, targets) = match rest.find(':') {
+            Some(pos) => (&rest[..pos], Some(&rest[pos + 1..])),
+            None => (rest, None),
+        };
+        let targets = targets.map(|file| {
+            fparse::parse_targets_file(Path::new(file))
+                .unwrap_or_else(|e| panic!("Could not read targets of {}: {:?}", bin, e))
+                .targets
+        });
+        Self {
+            kind: kind.unwrap_or_else(|| SanitizerKind::detect(bin)),
+            bin: bin.to_string(),
+            targets,
+        }
+    }
+
+    pub fn covers(&self, cmp: CmpId) -> bool {
+        self.targets.as_ref().is_none_or(|t| t.contains(&cmp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizer_kinds() {
+        for kind in SANITIZER_KINDS.iter() {
+            assert_eq!(SanitizerKind::from_name(kind.name()), Some(*kind));
+        }
+        assert!(SanitizerKind::is_error_code(defs::MSAN_ERROR_CODE));
+        assert!(SanitizerKind::is_error_code(defs::UBSAN_ERROR_CODE));
+        assert!(!SanitizerKind::is_error_code(1));
+
+        let san = Sanitizer {
+            kind: SanitizerKind::Ubsan,
+            bin: String::new(),
+            targets: Some([3, 5].iter().copied().collect()),
+        };
+        assert!(san.covers(5) && !san.covers(4));
+    }
+}
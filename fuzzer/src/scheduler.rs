@@ -0,0 +1,160 @@
+// Hands out distinct conds to the fuzzing threads. A thread leases a batch of
+// the best conds from the depot and works through it. When the depot has no
+// conds left to lease, it steals from the batches of the other threads, so no
+// two threads fuzz the same cond and the queue lock is only taken per batch.
+
+use crate::{cond_stmt::CondStmt, depot::{Depot, QPriority}, stats::ThreadEntry};
+use angora_common::config;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread, time,
+};
+
+pub struct Scheduler {
+    depot: Arc<Depot>,
+    batches: Vec<Mutex<VecDeque<(CondStmt, QPriority)>>>,
+    stats: Vec<Mutex<ThreadEntry>>,
+}
+
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    match m.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            warn!("Mutex poisoned! Results may be incorrect. Continuing...");
+            poisoned.into_inner()
+        },
+    }
+}
+
+impl Scheduler {
+    pub fn new(depot: Arc<Depot>, num_threads: usize) -> Self {
+        Self {
+            depot,
+            batches: (0..num_threads).map(|_| Mutex::new(VecDeque::new())).collect(),
+            stats: (0..num_threads).map(|_| Mutex::new(ThreadEntry::default())).collect(),
+        }
+    }
+
+    // The next cond for the thread to fuzz. None once every cond is done, or
+    // when fuzzing stops.
+    pub fn next(&self, thread_id: usize, running: &AtomicBool) -> Option<(CondStmt, QPriority)> {
+        while running.load(Ordering::Relaxed) {
+            if let Some(entry) = lock(&self.batches[thread_id]).pop_front() {
+                return Some(entry);
+            }
+
+            let batch = self.depot.lease_entries(config::SCHEDULE_BATCH_SIZE);
+            if !batch.is_empty() {
+                lock(&self.stats[thread_id]).num_leased += batch.len().into();
+                lock(&self.batches[thread_id]).extend(batch);
+                continue;
+            }
+
+            if let Some(entry) = self.steal(thread_id) {
+                lock(&self.stats[thread_id]).num_stolen.count();
+                return Some(entry);
+            }
+
+            // The conds that are being fuzzed may come back not done
+            if self.depot.num_leased() == 0 {
+                return None;
+            }
+            let t = time::Instant::now();
+            thread::sleep(time::Duration::from_millis(config::SCHEDULE_IDLE_MS));
+            lock(&self.stats[thread_id]).idle_time += t.elapsed().into();
+        }
+        None
+    }
+
+    // Take the last cond of the batch of another thread
+    fn steal(&self, thread_id: usize) -> Option<(CondStmt, QPriority)> {
+        let n = self.batches.len();
+        (1..n)
+            .map(|i| (thread_id + i) % n)
+            .find_map(|victim| lock(&self.batches[victim]).pop_back())
+    }
+
+    // Give the fuzzed cond back to the depot
+    pub fn finish(
+        &self,
+        thread_id: usize,
+        cond: CondStmt,
+        priority: QPriority,
+        num_exec: usize,
+        num_new: usize,
+    ) {
+        self.depot.return_entry(cond, priority, num_new);
+        let mut stats = lock(&self.stats[thread_id]);
+        stats.num_conds.count();
+        stats.num_exec += num_exec.into();
+    }
+
+    // Give back the conds of the thread that it has not fuzzed yet
+    pub fn release(&self, thread_id: usize) {
+        let batch: Vec<_> = lock(&self.batches[thread_id]).drain(..).collect();
+        for (cond, priority) in batch {
+            self.depot.release_entry(cond, priority);
+        }
+    }
+
+    pub fn thread_stats(&self) -> Vec<ThreadEntry> {
+        self.stats.iter().map(|s| *lock(s)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{depot::parse_schedule, dyncfg::cfg::ControlFlowGraph};
+    use std::{env, fs, process, sync::RwLock};
+
+    #[test]
+    fn scheduler_distinct_conds() {
+        let dir = env::temp_dir().join(format!("parmesan-scheduler-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let depot = Arc::new(Depot::new(
+            dir.join("seeds"),
+            &dir,
+            RwLock::new(ControlFlowGraph::empty_new()),
+            parse_schedule("round_robin"),
+            Default::default(),
+        ));
+        let conds: Vec<CondStmt> = (0..6)
+            .map(|i| {
+                let mut cond = CondStmt::new();
+                cond.base.cmpid = i;
+                cond.is_desirable = true;
+                cond
+            })
+            .collect();
+        depot.add_entries(conds);
+
+        let running = AtomicBool::new(true);
+        let scheduler = Scheduler::new(depot.clone(), 2);
+        let mut cmpids = vec![];
+        // Thread 0 leases a batch, thread 1 gets the rest and steals from 0
+        for thread_id in &[0, 1, 1] {
+            let (cond, _) = scheduler.next(*thread_id, &running).unwrap();
+            cmpids.push(cond.base.cmpid);
+        }
+        let (stolen, priority) = scheduler.next(1, &running).unwrap();
+        cmpids.push(stolen.base.cmpid);
+        cmpids.sort();
+        cmpids.dedup();
+        assert_eq!(cmpids.len(), 4);
+        assert_eq!(depot.num_leased(), 6);
+        assert_eq!(scheduler.thread_stats()[1].num_stolen.0, 1);
+
+        scheduler.finish(1, stolen, priority, 10, 0);
+        scheduler.release(0);
+        // The three conds that are still being fuzzed
+        assert_eq!(depot.num_leased(), 3);
+        assert_eq!(depot.queue.lock().unwrap().len(), 3);
+        drop((scheduler, depot));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
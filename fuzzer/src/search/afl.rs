@@ -4,13 +4,15 @@
 // And GE algorithm.
 
 use super::*;
-use rand::{self, distributions::Uniform, Rng};
+use crate::stats::NUM_HAVOC_OPS;
+use rand::{distributions::WeightedIndex, Rng};
 
 static IDX_TO_SIZE: [usize; 4] = [1, 2, 4, 8];
 
 pub struct AFLFuzz<'a> {
     handler: SearchHandler<'a>,
     run_ratio: usize,
+    tokens: Vec<Vec<u8>>,
 }
 
 impl<'a> AFLFuzz<'a> {
@@ -26,7 +28,12 @@ impl<'a> AFLFuzz<'a> {
             5
         };
 
-        Self { handler, run_ratio }
+        let tokens = handler.executor.dict_tokens();
+        Self {
+            handler,
+            run_ratio,
+            tokens,
+        }
     }
 
     pub fn run(&mut self) {
@@ -49,13 +56,23 @@ impl<'a> AFLFuzz<'a> {
         } else {
             256
         };
-        let max_choice = if config::ENABLE_MICRO_RANDOM_LEN {
-            8
-        } else {
-            6
-        };
-
-        let choice_range = Uniform::new(0, max_choice);
+        // The operators that change the length are the last three
+        let mut enabled = [true; NUM_HAVOC_OPS];
+        if !config::ENABLE_MICRO_RANDOM_LEN {
+            enabled[5..].copy_from_slice(&[false; 3]);
+        }
+        if self.tokens.is_empty() {
+            enabled[4] = false;
+            enabled[7] = false;
+        }
+        let dist = self
+            .handler
+            .executor
+            .global_stats
+            .read()
+            .unwrap()
+            .havoc_distribution(&enabled);
+        let choice_dist = WeightedIndex::new(&dist).unwrap();
 
         self.handler.max_times += (config::MAX_HAVOC_FLIP_TIMES * self.run_ratio).into();
         self.handler.skip = false;
@@ -65,8 +82,10 @@ impl<'a> AFLFuzz<'a> {
                 break;
             }
             let mut buf = self.handler.buf.clone();
-            self.havoc_flip(&mut buf, max_stacking, choice_range);
+            let used = self.havoc_flip(&mut buf, max_stacking, &choice_dist);
             self.handler.execute(&buf);
+            let found_new = self.handler.executor.has_new_path;
+            self.handler.executor.local_stats.havoc.record(&used, found_new);
         }
     }
 
@@ -101,7 +120,7 @@ impl<'a> AFLFuzz<'a> {
             return None;
         }
 
-        let split_at = f_loc + rand::random::<usize>() % (l_loc - f_loc);
+        let split_at = f_loc + rng::random::<usize>() % (l_loc - f_loc);
         Some([&buf1[..split_at], &buf2[split_at..]].concat())
     }
 
@@ -118,20 +137,29 @@ impl<'a> AFLFuzz<'a> {
     }
 
     // TODO both endian?
-    fn havoc_flip(&self, buf: &mut Vec<u8>, max_stacking: usize, choice_range: Uniform<u32>) {
-        let mut rng = rand::thread_rng();
+    // Returns which of the operators have been used
+    fn havoc_flip(
+        &self,
+        buf: &mut Vec<u8>,
+        max_stacking: usize,
+        choice_dist: &WeightedIndex<f64>,
+    ) -> [bool; NUM_HAVOC_OPS] {
+        let mut rng = rng::thread_rng();
         let mut byte_len = buf.len() as u32;
         let use_stacking = 1 + rng.gen_range(0, max_stacking);
+        let mut used = [false; NUM_HAVOC_OPS];
 
         for _ in 0..use_stacking {
-            match rng.sample(choice_range) {
-                0 | 1 => {
+            let op = rng.sample(choice_dist);
+            used[op] = true;
+            match op {
+                0 => {
                     // flip bit
                     let byte_idx: u32 = rng.gen_range(0, byte_len);
                     let bit_idx: u32 = rng.gen_range(0, 8);
                     buf[byte_idx as usize] ^= 128 >> bit_idx;
                 },
-                2 | 3 => {
+                1 => {
                     //add or sub
                     let n: u32 = rng.gen_range(0, 3);
                     let size = IDX_TO_SIZE[n as usize];
@@ -149,7 +177,7 @@ impl<'a> AFLFuzz<'a> {
                         );
                     }
                 },
-                4 => {
+                2 => {
                     // set interesting value
                     let n: u32 = rng.gen_range(0, 3);
                     let size = IDX_TO_SIZE[n as usize];
@@ -160,13 +188,21 @@ impl<'a> AFLFuzz<'a> {
                         mut_input::set_val_in_buf(buf, byte_idx as usize, size, vals[wh as usize]);
                     }
                 },
-                5 => {
+                3 => {
                     // random byte
                     let byte_idx: u32 = rng.gen_range(0, byte_len);
                     let val: u8 = rng.gen();
                     buf[byte_idx as usize] = val;
                 },
-                6 => {
+                4 => {
+                    // overwrite with a dictionary token
+                    let token = &self.tokens[rng.gen_range(0, self.tokens.len())];
+                    if byte_len as usize >= token.len() {
+                        let byte_idx = rng.gen_range(0, byte_len as usize - token.len() + 1);
+                        buf[byte_idx..byte_idx + token.len()].copy_from_slice(token);
+                    }
+                },
+                5 => {
                     // delete bytes
                     let remove_len: u32 = rng.gen_range(1, 5);
                     if byte_len > remove_len {
@@ -178,7 +214,7 @@ impl<'a> AFLFuzz<'a> {
                         }
                     }
                 },
-                7 => {
+                6 => {
                     // insert bytes
                     let add_len = rng.gen_range(1, 5);
                     let new_len = byte_len + add_len;
@@ -190,9 +226,20 @@ impl<'a> AFLFuzz<'a> {
                         }
                     }
                 },
+                7 => {
+                    // insert a dictionary token
+                    let token = &self.tokens[rng.gen_range(0, self.tokens.len())];
+                    let new_len = byte_len + token.len() as u32;
+                    if new_len < config::MAX_INPUT_LEN as u32 {
+                        let byte_idx = rng.gen_range(0, byte_len as usize + 1);
+                        byte_len = new_len;
+                        buf.splice(byte_idx..byte_idx, token.iter().cloned());
+                    }
+                },
                 _ => {},
             }
         }
+        used
     }
 
     fn random_len(&mut self) {
@@ -203,7 +250,7 @@ impl<'a> AFLFuzz<'a> {
 
         // let step = std::cmp::max( len / config::INFLATE_MAX_ITER_NUM + 1, 5);
         let orig_len = self.handler.buf.len();
-        let mut rng = rand::thread_rng();
+        let mut rng = rng::thread_rng();
 
         let mut buf = self.handler.buf.clone();
         for _ in 0..config::RANDOM_LEN_NUM {
@@ -225,7 +272,7 @@ impl<'a> AFLFuzz<'a> {
             return;
         }
 
-        let mut rng = rand::thread_rng();
+        let mut rng = rng::thread_rng();
         let mut buf = self.handler.buf.clone();
         let mut step = 1;
         for _ in 0..4 {
@@ -0,0 +1,71 @@
+// Fuzz the whole input with the custom mutator, after trimming it with the
+// mutator if it can. A trimmed candidate is kept if it takes the same path.
+
+use super::*;
+
+pub struct CustomFuzz<'a> {
+    handler: SearchHandler<'a>,
+}
+
+impl<'a> CustomFuzz<'a> {
+    pub fn new(handler: SearchHandler<'a>) -> Self {
+        Self { handler }
+    }
+
+    fn trim(&mut self) -> Vec<u8> {
+        let buf = self.handler.buf.clone();
+        let executor = &mut self.handler.executor;
+        let steps = match executor.custom_mutator().and_then(|m| m.init_trim(&buf)) {
+            Some(steps) => steps,
+            None => return buf,
+        };
+        if executor.run_status(&buf) != StatusType::Normal {
+            return buf;
+        }
+        let path = executor.branches.get_path();
+
+        let mut cur = buf;
+        let mut step = 0;
+        let mut num_steps = 0;
+        while step < steps && num_steps < config::MAX_CUSTOM_TRIM_STEPS {
+            let candidate = executor.custom_mutator().unwrap().trim();
+            let success = !candidate.is_empty()
+                && candidate.len() <= cur.len()
+                && executor.run_status(&candidate) == StatusType::Normal
+                && executor.branches.get_path() == path;
+            if success {
+                cur = candidate;
+            }
+            match executor.custom_mutator().unwrap().post_trim(success) {
+                Some(next) => step = next,
+                None => break,
+            }
+            num_steps += 1;
+        }
+        cur
+    }
+
+    pub fn run(&mut self) {
+        let buf = self.trim();
+        self.handler.max_times = config::MAX_CUSTOM_FUZZ_TIMES.into();
+        // Stop if the mutator keeps returning nothing
+        let mut num_empty = 0;
+        loop {
+            if self.handler.is_stopped_or_skip() || num_empty >= config::MAX_CUSTOM_FUZZ_TIMES {
+                break;
+            }
+            let add_buf = self.handler.executor.random_input_buf();
+            let new_buf = self
+                .handler
+                .executor
+                .custom_mutator()
+                .unwrap()
+                .fuzz(&buf, &add_buf, config::MAX_INPUT_LEN);
+            if new_buf.is_empty() {
+                num_empty += 1;
+                continue;
+            }
+            self.handler.execute(&new_buf);
+        }
+    }
+}
@@ -28,6 +28,19 @@ impl<'a> ExploitFuzz<'a> {
                 }
                 input.set(i, orig_v);
             }
+
+            // and the tokens that fit into the bytes of the cond
+            let mut tokens = self.handler.executor.dict_tokens();
+            tokens.retain(|t| t.len() <= input.val_len());
+            tokens.truncate(config::MAX_DICT_TOKENS_TRY);
+            for token in tokens.iter() {
+                if self.handler.is_stopped_or_skip() {
+                    break;
+                }
+                input.assign(token);
+                self.handler.execute_input(&input);
+            }
+            input.assign(&orig_input_val);
         }
 
         self.handler.max_times = config::MAX_EXPLOIT_EXEC_NUM.into();
@@ -8,6 +8,9 @@ use std;
 pub struct GdSearch<'a> {
     handler: SearchHandler<'a>,
     sample_index: (usize, usize),
+    prefix: PrefixConds,
+    // The lowest joint output so far
+    fmin: u64,
 }
 
 impl<'a> GdSearch<'a> {
@@ -15,6 +18,8 @@ impl<'a> GdSearch<'a> {
         Self {
             handler,
             sample_index: (0, 0),
+            prefix: Default::default(),
+            fmin: std::u64::MAX,
         }
     }
 
@@ -24,6 +29,15 @@ impl<'a> GdSearch<'a> {
         }
         debug!("input : {:?}", input);
         let f = self.handler.execute_cond(input);
+        if self.prefix.is_empty() || f == std::u64::MAX || self.handler.is_stopped_or_skip() {
+            return f;
+        }
+        let g = self.prefix.output(&self.handler);
+        if g > 0 && f < self.fmin {
+            self.handler.executor.local_stats.joint.num_prevented.count();
+        }
+        let f = f.saturating_add(g);
+        self.fmin = self.fmin.min(f);
         f
     }
 
@@ -57,6 +71,10 @@ impl<'a> GdSearch<'a> {
             self.handler.cond
         );
 
+        if self.handler.executor.cmd.joint_conds {
+            self.prefix = PrefixConds::collect(&mut self.handler);
+        }
+
         let mut f0 = if !self.handler.cond.is_first_time() {
             self.reload_input(&mut input)
         } else {
@@ -0,0 +1,113 @@
+// Mutate the derivation tree of the input instead of its bytes (grammar mode).
+// A mutation replaces a subtree with a new derivation of its nonterminal, or
+// with a subtree of the same nonterminal from the tree of another input.
+
+use super::*;
+use crate::{
+    depot::Depot,
+    grammar::{covering_nodes, Grammar, Tree},
+};
+
+// Trees of other inputs to splice subtrees from
+const NUM_DONORS: usize = 8;
+
+pub struct GrammarFuzz<'a> {
+    handler: SearchHandler<'a>,
+    grammar: &'a Grammar,
+    depot: &'a Depot,
+    tree: Tree,
+    donors: Vec<Tree>,
+}
+
+impl<'a> GrammarFuzz<'a> {
+    pub fn new(handler: SearchHandler<'a>, grammar: &'a Grammar, depot: &'a Depot, tree: Tree) -> Self {
+        let donors = (0..NUM_DONORS)
+            .filter_map(|_| depot.get_tree(depot.next_random()))
+            .collect();
+        Self {
+            handler,
+            grammar,
+            depot,
+            tree,
+            donors,
+        }
+    }
+
+    fn execute(&mut self, tree: &Tree) {
+        let buf = tree.to_bytes();
+        if buf.len() >= config::MAX_INPUT_LEN {
+            self.handler.skip_candidate();
+            return;
+        }
+        if self.handler.cond.base.is_explore() {
+            self.handler.execute_cond_buf(&buf);
+        } else {
+            self.handler.execute(&buf);
+        }
+        if let Some(id) = self.handler.executor.last_saved {
+            self.depot.save_tree(id, tree);
+        }
+    }
+
+    fn mutate_node<R: Rng>(&self, tree: &mut Tree, path: &[usize], symbol: &str, rng: &mut R) {
+        let depth = path.len();
+        let subtree = if rng.gen() {
+            self.donor_subtree(symbol, rng)
+        } else {
+            None
+        };
+        let subtree = subtree.unwrap_or_else(|| self.grammar.generate_from(symbol, depth, rng));
+        tree.replace(path, subtree);
+    }
+
+    fn donor_subtree<R: Rng>(&self, symbol: &str, rng: &mut R) -> Option<Tree> {
+        let donor = self.donors.choose(rng)?;
+        let nodes = donor.nodes(self.grammar);
+        let same: Vec<_> = nodes.iter().filter(|n| n.symbol == symbol).collect();
+        let node = same.choose(rng)?;
+        donor.get(&node.path).cloned()
+    }
+
+    // Random mutations of random subtrees, for the AFL conds
+    pub fn run_havoc(&mut self) {
+        let mut rng = rng::thread_rng();
+        self.handler.max_times = config::MAX_HAVOC_FLIP_TIMES.into();
+        loop {
+            if self.handler.is_stopped_or_skip() {
+                break;
+            }
+            let mut tree = self.tree.clone();
+            for _ in 0..rng.gen_range(1, config::GRAMMAR_MAX_STACKING + 1) {
+                let nodes = tree.nodes(self.grammar);
+                if let Some(node) = nodes.choose(&mut rng) {
+                    self.mutate_node(&mut tree, &node.path, &node.symbol, &mut rng);
+                }
+            }
+            self.execute(&tree);
+        }
+    }
+
+    // Regenerate the subtrees that derive the tainted bytes of the cond, for
+    // conds that depend on the structure of the input.
+    pub fn run_cond(&mut self) {
+        let mut rng = rng::thread_rng();
+        let nodes = self.tree.nodes(self.grammar);
+        let targets: Vec<(Vec<usize>, String)> = covering_nodes(&nodes, &self.handler.cond.offsets)
+            .into_iter()
+            .map(|n| (n.path.clone(), n.symbol.clone()))
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+        loop {
+            if self.handler.is_stopped_or_skip() || self.handler.cond.is_done() {
+                break;
+            }
+            let mut tree = self.tree.clone();
+            for (path, symbol) in targets.iter() {
+                self.mutate_node(&mut tree, path, symbol, &mut rng);
+            }
+            self.execute(&tree);
+        }
+    }
+}
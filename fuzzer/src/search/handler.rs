@@ -33,6 +33,15 @@ impl<'a> SearchHandler<'a> {
         !self.running.load(Ordering::Relaxed) || self.skip
     }
 
+    // A candidate that was not run, e.g. because it is too long, still uses up
+    // the budget, or a search that only makes such candidates never stops.
+    pub fn skip_candidate(&mut self) {
+        self.max_times.0 = self.max_times.0.saturating_sub(1);
+        if self.executor.local_stats.num_exec > self.max_times {
+            self.skip = true;
+        }
+    }
+
     fn process_status(&mut self, status: StatusType) {
         match status {
             StatusType::Skip => {
@@ -79,6 +88,12 @@ impl<'a> SearchHandler<'a> {
         f_output
     }
 
+    pub fn execute_cond_buf(&mut self, buf: &Vec<u8>) -> u64 {
+        let (status, f_output) = self.executor.run_with_cond(buf, self.cond);
+        self.process_status(status);
+        f_output
+    }
+
     pub fn execute_input_direct(&mut self) {
         let status = self.executor.run(&self.buf, self.cond);
         self.process_status(status);
@@ -86,7 +101,11 @@ impl<'a> SearchHandler<'a> {
 
     pub fn get_f_input(&self) -> MutInput {
         debug!("input offset: {:?}", self.cond.offsets);
-        MutInput::from(&self.cond.offsets, &self.buf)
+        let mut input = MutInput::from(&self.cond.offsets, &self.buf);
+        if self.cond.base.is_float() {
+            input.mark_floats(self.cond.base.size as usize);
+        }
+        input
     }
 }
 
@@ -0,0 +1,223 @@
+// Input-to-state correspondence (REDQUEEN): operands of a cmp are often
+// copied from the input, so the bytes of one operand are searched in the
+// input, in several encodings, and replaced with the other operand. It uses
+// the operands of the run, not the taint of the cond, so without the track
+// binary the conds are the cmps logged by the fast binary.
+
+use super::*;
+use angora_common::cond_stmt_base::CondStmtBase;
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Encoding {
+    Le(usize),
+    Be(usize),
+    Dec,
+    Hex,
+}
+
+fn mask(size: usize) -> u64 {
+    if size >= 8 {
+        u64::MAX
+    } else {
+        (1 << (size * 8)) - 1
+    }
+}
+
+fn sign_extend(v: u64, size: usize) -> i64 {
+    if size >= 8 {
+        v as i64
+    } else {
+        let shift = 64 - size * 8;
+        ((v << shift) as i64) >> shift
+    }
+}
+
+// The value may have been read from fewer bytes than the cmp compares
+fn encodings(size: usize) -> Vec<Encoding> {
+    let mut encs = vec![];
+    for &n in [8, 4, 2, 1].iter().filter(|&&n| n <= size) {
+        encs.push(Encoding::Le(n));
+        if n > 1 {
+            encs.push(Encoding::Be(n));
+        }
+    }
+    encs.push(Encoding::Dec);
+    encs.push(Encoding::Hex);
+    encs
+}
+
+fn encode(v: u64, enc: Encoding, size: usize, signed: bool) -> Option<Vec<u8>> {
+    match enc {
+        Encoding::Le(n) | Encoding::Be(n) => {
+            // Does not fit in n bytes, unless it is sign extended
+            let s = sign_extend(v, size);
+            if n < size && v & !mask(n) != 0 && !(signed && sign_extend(v & mask(n), n) == s) {
+                return None;
+            }
+            let mut bytes: Vec<u8> = (0..n).map(|i| (v >> (i * 8)) as u8).collect();
+            if let Encoding::Be(_) = enc {
+                bytes.reverse();
+            }
+            Some(bytes)
+        },
+        Encoding::Dec => {
+            if signed {
+                Some(sign_extend(v, size).to_string().into_bytes())
+            } else {
+                Some(v.to_string().into_bytes())
+            }
+        },
+        Encoding::Hex => Some(format!("{:x}", v).into_bytes()),
+    }
+}
+
+fn find_all(buf: &[u8], pattern: &[u8], max: usize) -> Vec<usize> {
+    if pattern.is_empty() || pattern.len() > buf.len() {
+        return vec![];
+    }
+    buf.windows(pattern.len())
+        .enumerate()
+        .filter(|(_, w)| *w == pattern)
+        .map(|(i, _)| i)
+        .take(max)
+        .collect()
+}
+
+// The inputs with the bytes of `from` replaced by `to` and its neighbors
+fn candidates(buf: &[u8], from: u64, to: u64, size: usize, signed: bool) -> Vec<Vec<u8>> {
+    let mut bufs = vec![];
+    for enc in encodings(size) {
+        let pattern = match encode(from, enc, size, signed) {
+            Some(p) => p,
+            None => continue,
+        };
+        // A single digit matches everywhere
+        if pattern.len() == 1 && matches!(enc, Encoding::Dec | Encoding::Hex) {
+            continue;
+        }
+        let positions = find_all(buf, &pattern, config::I2S_MAX_MATCHES);
+        if positions.is_empty() {
+            continue;
+        }
+        for delta in [0u64, 1, u64::MAX].iter() {
+            let v = to.wrapping_add(*delta) & mask(size);
+            let replacement = match encode(v, enc, size, signed) {
+                Some(r) => r,
+                None => continue,
+            };
+            for &pos in positions.iter() {
+                let mut new_buf = buf[..pos].to_vec();
+                new_buf.extend_from_slice(&replacement);
+                new_buf.extend_from_slice(&buf[pos + pattern.len()..]);
+                bufs.push(new_buf);
+            }
+        }
+    }
+    bufs
+}
+
+fn operand_size(base: &CondStmtBase) -> usize {
+    if base.size == 0 || base.size > 8 {
+        8
+    } else {
+        base.size as usize
+    }
+}
+
+// The inputs that may give one operand the value of the other, from the
+// operands of a run on buf
+fn solutions(buf: &[u8], base: &CondStmtBase, (arg1, arg2): (u64, u64)) -> Vec<Vec<u8>> {
+    let size = operand_size(base);
+    let signed = base.is_signed();
+    let (arg1, arg2) = (arg1 & mask(size), arg2 & mask(size));
+    let mut bufs = candidates(buf, arg1, arg2, size, signed);
+    bufs.extend(candidates(buf, arg2, arg1, size, signed));
+    bufs
+}
+
+// One of the operands of the cmp, as it was logged, is found in the input
+pub fn is_input_to_state(buf: &[u8], base: &CondStmtBase) -> bool {
+    let size = operand_size(base);
+    let signed = base.is_signed();
+    [base.arg1, base.arg2].iter().any(|&v| {
+        let v = v & mask(size);
+        encodings(size).into_iter().any(|enc| match encode(v, enc, size, signed) {
+            Some(p) if p.len() > 1 || matches!(enc, Encoding::Le(_)) => {
+                !find_all(buf, &p, 1).is_empty()
+            },
+            _ => false,
+        })
+    })
+}
+
+pub struct I2sSearch<'a> {
+    pub handler: SearchHandler<'a>,
+}
+
+impl<'a> I2sSearch<'a> {
+    pub fn new(handler: SearchHandler<'a>) -> Self {
+        Self { handler }
+    }
+
+    pub fn run(&mut self) {
+        self.handler.execute_cond_direct();
+        let (arg1, arg2) = match self.handler.executor.t_conds.get_args() {
+            Some(args) => args,
+            None => return,
+        };
+        let buf = self.handler.buf.clone();
+        let mut tried = HashSet::new();
+        for new_buf in solutions(&buf, &self.handler.cond.base, (arg1, arg2)) {
+            if self.handler.is_stopped_or_skip() || self.handler.cond.is_done() {
+                break;
+            }
+            if new_buf.len() >= config::MAX_INPUT_LEN || !tried.insert(new_buf.clone()) {
+                continue;
+            }
+            self.handler.execute_cond_buf(&new_buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angora_common::defs;
+
+    #[test]
+    fn i2s_candidates() {
+        // A big endian u32 and a decimal number in the input
+        let buf = b"ab\x00\x00\x01\x02cd258;".to_vec();
+        let bufs = candidates(&buf, 0x102, 0x304, 4, false);
+        assert!(bufs.contains(&b"ab\x00\x00\x03\x04cd258;".to_vec()));
+        assert!(bufs.contains(&b"ab\x00\x00\x03\x05cd258;".to_vec()));
+        assert!(bufs.contains(&b"ab\x00\x00\x01\x02cd772;".to_vec()));
+        assert!(bufs.contains(&b"ab\x00\x00\x01\x02cd771;".to_vec()));
+
+        // -1 read from a single byte and compared as an i32
+        let bufs = candidates(b"x\xffy", 0xffff_ffff, 7, 4, true);
+        assert!(bufs.contains(&b"x\x07y".to_vec()));
+        assert!(candidates(b"x\xffy", 0xffff_ffff, 7, 4, false).is_empty());
+    }
+
+    #[test]
+    fn i2s_no_offsets() {
+        // A cmp logged by the fast binary, the input has no taint
+        let mut base = CondStmtBase::default();
+        base.op = defs::COND_ICMP_EQ_OP;
+        base.size = 4;
+        base.arg1 = 0x6463_6261;
+        base.arg2 = 0x4443_4241;
+        let cond = CondStmt::from(base);
+        assert!(!cond.is_tainted());
+
+        let buf = b"xxabcdyy".to_vec();
+        assert!(is_input_to_state(&buf, &cond.base));
+        let bufs = solutions(&buf, &cond.base, (cond.base.arg1, cond.base.arg2));
+        assert!(bufs.contains(&b"xxABCDyy".to_vec()));
+
+        assert!(!is_input_to_state(b"xxyy", &cond.base));
+        assert!(solutions(b"xxyy", &cond.base, (cond.base.arg1, cond.base.arg2)).is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+// Conds before the target on the path of the input that read some of its
+// bytes (like Angora's nested conds). Moving the bytes of the target may
+// flip them and leave the path, so with `--joint` GdSearch minimizes the
+// output of the target plus the distance of each of them from its outcome
+// on the input. The fast binary reports their operands in the runs of the
+// target, so they cost no runs of their own.
+
+use super::*;
+use angora_common::{cond_stmt_base::CondStmtBase, tag::TagSeg};
+
+fn overlaps(a: &[TagSeg], b: &[TagSeg]) -> bool {
+    a.iter()
+        .any(|x| b.iter().any(|y| x.begin < y.end && y.begin < x.end))
+}
+
+#[derive(Default)]
+pub struct PrefixConds {
+    // With the condition flipped, so their output is 0 while they keep it
+    conds: Vec<CondStmtBase>,
+}
+
+impl PrefixConds {
+    pub fn collect(handler: &mut SearchHandler) -> Self {
+        let buf = handler.buf.clone();
+        let conds = match handler.executor.track_conds(&buf) {
+            Some(c) => c,
+            None => return Default::default(),
+        };
+        let idx = match conds.iter().position(|c| c.base == handler.cond.base) {
+            Some(i) => i,
+            None => return Default::default(),
+        };
+        let offsets = &handler.cond.offsets;
+        let mut prefix: Vec<CondStmtBase> = vec![];
+        // The closest ones first
+        for c in conds[..idx].iter().rev() {
+            if prefix.len() >= config::JOINT_MAX_PREFIX_CONDS {
+                break;
+            }
+            if !c.base.is_explore()
+                || c.base.is_switch()
+                || c.base == handler.cond.base
+                || prefix.contains(&c.base)
+                || !overlaps(&c.offsets, offsets)
+            {
+                continue;
+            }
+            let mut base = c.base;
+            base.flip_condition();
+            prefix.push(base);
+        }
+
+        if !prefix.is_empty() {
+            handler.executor.watch_conds(&prefix);
+        }
+        let stats = &mut handler.executor.local_stats.joint;
+        stats.num_searches.count();
+        stats.num_prefix_conds += prefix.len().into();
+        Self { conds: prefix }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conds.is_empty()
+    }
+
+    // The sum of the distances of the prefix conds from their outcome, in
+    // the last run of the target
+    pub fn output(&self, handler: &SearchHandler) -> u64 {
+        handler
+            .executor
+            .watched_outputs()
+            .iter()
+            .fold(0, |sum, &f| sum.saturating_add(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(begin: u32, end: u32) -> TagSeg {
+        TagSeg {
+            sign: false,
+            begin,
+            end,
+        }
+    }
+
+    #[test]
+    fn joint_overlaps() {
+        assert!(overlaps(&[seg(0, 4)], &[seg(8, 9), seg(3, 5)]));
+        assert!(!overlaps(&[seg(0, 4)], &[seg(4, 8)]));
+        assert!(!overlaps(&[], &[seg(0, 8)]));
+    }
+}
@@ -39,7 +39,7 @@ impl<'a> LenFuzz<'a> {
                 if buf_len + extended_len < config::MAX_INPUT_LEN {
                     // len > X
                     let mut v = vec![0u8; extended_len + 1];
-                    rand::thread_rng().fill_bytes(&mut v);
+                    rng::thread_rng().fill_bytes(&mut v);
                     buf.append(&mut v);
                     self.handler.execute(&buf);
                     // some special chars: NULL, LF, CR, SPACE
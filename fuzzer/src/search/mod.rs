@@ -2,6 +2,7 @@ use crate::{
     cond_stmt::CondStmt,
     executor::{Executor, StatusType},
     mut_input::{self, MutInput},
+    rng,
 };
 use angora_common::config;
 use rand::prelude::*;
@@ -21,6 +22,8 @@ pub mod interesting_val;
 pub use self::interesting_val::*;
 mod handler;
 pub use self::handler::SearchHandler;
+mod joint;
+use self::joint::PrefixConds;
 
 pub mod gd;
 pub use self::gd::GdSearch;
@@ -31,6 +34,10 @@ pub mod cbh;
 pub use self::cbh::CbhSearch;
 pub mod mb;
 pub use self::mb::MbSearch;
+pub mod i2s;
+pub use self::i2s::I2sSearch;
+pub mod smt;
+pub use self::smt::SmtSearch;
 
 //Other cases of special offsets
 pub mod cmpfn;
@@ -39,9 +46,13 @@ pub mod len;
 pub use self::len::LenFuzz;
 pub mod afl;
 pub use self::afl::AFLFuzz;
+pub mod custom;
+pub use self::custom::CustomFuzz;
 pub mod exploit;
 pub use self::exploit::ExploitFuzz;
 pub mod det;
 pub use self::det::DetFuzz;
 pub mod one_byte;
 pub use self::one_byte::OneByteFuzz;
+pub mod grammar;
+pub use self::grammar::GrammarFuzz;
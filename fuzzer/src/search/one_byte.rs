@@ -25,6 +25,31 @@ impl<'a> OneByteFuzz<'a> {
             self.handler.execute_input_direct();
         }
     }
+    // The byte may start a keyword, e.g. the '<' of "<?xml", so the tokens
+    // are written over and inserted at its offset.
+    fn fuzz_tokens(&mut self) {
+        let mut tokens = self.handler.executor.dict_tokens();
+        tokens.truncate(config::MAX_DICT_TOKENS_TRY);
+        self.handler.max_times += (2 * tokens.len()).into();
+        let off = self.handler.cond.offsets[0].begin as usize;
+        let orig_buf = self.handler.buf.clone();
+        for token in tokens.iter() {
+            if self.handler.is_stopped_or_skip() {
+                break;
+            }
+            if off + token.len() <= orig_buf.len() {
+                let mut buf = orig_buf.clone();
+                buf[off..off + token.len()].copy_from_slice(token);
+                self.handler.execute(&buf);
+            }
+            if off <= orig_buf.len() && orig_buf.len() + token.len() < config::MAX_INPUT_LEN {
+                let mut buf = orig_buf.clone();
+                buf.splice(off..off, token.iter().cloned());
+                self.handler.execute(&buf);
+            }
+        }
+    }
+
     pub fn run(&mut self) {
         if !self.handler.cond.is_first_time() {
             warn!("fuzz one byte more than one time");
@@ -44,5 +69,8 @@ impl<'a> OneByteFuzz<'a> {
             input.set(0, i);
             self.execute(&input);
         }
+        if !self.handler.cond.is_done() {
+            self.fuzz_tokens();
+        }
     }
 }
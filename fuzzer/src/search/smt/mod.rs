@@ -0,0 +1,127 @@
+// Solver stage for the conds that the gradient search gave up on (with the
+// `smt` feature). The tainted operand of the cond is fitted to a function
+// of its bytes from a few runs, the cmps before it on the path that copy
+// their bytes keep their outcome, and z3 solves for the bytes.
+
+mod query;
+#[cfg(feature = "smt")]
+mod solver;
+
+use self::query::*;
+use super::*;
+use angora_common::defs;
+
+#[cfg(feature = "smt")]
+use self::solver::solve;
+
+#[cfg(not(feature = "smt"))]
+fn solve(_query: &Query) -> Vec<Vec<u8>> {
+    vec![]
+}
+
+pub struct SmtSearch<'a> {
+    handler: SearchHandler<'a>,
+}
+
+impl<'a> SmtSearch<'a> {
+    pub fn new(handler: SearchHandler<'a>) -> Self {
+        Self { handler }
+    }
+
+    // The cond is abandoned by the other searches, it gets one try
+    pub fn should_run(cond: &CondStmt) -> bool {
+        cfg!(feature = "smt")
+            && !cond.solver_tried
+            && (cond.state.is_unsolvable()
+                || cond.num_minimal_optima >= config::MAX_NUM_MINIMAL_OPTIMA_ALL)
+    }
+
+    // The operands of the cond with the bytes set to x
+    fn sample(&mut self, positions: &[usize], x: u64) -> Option<(u64, u64)> {
+        let mut buf = self.handler.buf.clone();
+        for (i, &p) in positions.iter().enumerate() {
+            buf[p] = (x >> (i * 8)) as u8;
+        }
+        self.handler.execute_cond_buf(&buf);
+        self.handler.executor.t_conds.get_args()
+    }
+
+    fn build_query(&mut self) -> Option<Query> {
+        let buf = self.handler.buf.clone();
+        let conds = self.handler.executor.track_conds(&buf)?;
+        let idx = conds.iter().position(|c| c.base == self.handler.cond.base)?;
+        let target = &conds[idx];
+        let op = basic_op(target)?;
+        let pos = positions(&target.offsets);
+        if pos.is_empty() || pos.len() > 8 || pos.iter().any(|&p| p >= buf.len()) {
+            return None;
+        }
+
+        let mut rng = rng::thread_rng();
+        let m = mask(target.base.size);
+        let mut samples = vec![(read_le(&buf, &pos), target.base.arg1 & m, target.base.arg2 & m)];
+        for _ in 0..config::SMT_NUM_SAMPLES {
+            if self.handler.is_stopped_or_skip() || self.handler.cond.is_done() {
+                return None;
+            }
+            let x = rng.gen::<u64>() & mask(pos.len() as u32);
+            if let Some((arg1, arg2)) = self.sample(&pos, x) {
+                samples.push((x, arg1 & m, arg2 & m));
+            }
+        }
+        // The operand that changes with the bytes is the tainted one
+        let varies = |f: fn(&(u64, u64, u64)) -> u64| samples.iter().any(|s| f(s) != f(&samples[0]));
+        let (tainted_is_arg1, other) = match (varies(|s| s.1), varies(|s| s.2)) {
+            (true, false) => (true, samples[0].2),
+            (false, true) => (false, samples[0].1),
+            _ => return None,
+        };
+        let fit_samples = samples
+            .iter()
+            .map(|&(x, a1, a2)| (x, if tainted_is_arg1 { a1 } else { a2 }))
+            .collect();
+
+        let prefix = conds[..idx]
+            .iter()
+            .rev()
+            .filter_map(|c| copy_constraint(c, &buf))
+            .filter(|c| c.positions.iter().all(|p| pos.contains(p)))
+            .take(config::SMT_MAX_PREFIX)
+            .collect();
+
+        Some(Query {
+            positions: pos.clone(),
+            target: Constraint {
+                positions: pos,
+                size: target.base.size,
+                op,
+                signed: target.base.is_signed(),
+                tainted_is_arg1,
+                tainted: Operand::Fit(fit_samples),
+                other,
+                goal: target.base.condition != defs::COND_TRUE_ST,
+            },
+            prefix,
+        })
+    }
+
+    pub fn run(&mut self) {
+        self.handler.cond.solver_tried = true;
+        let query = match self.build_query() {
+            Some(q) => q,
+            None => return,
+        };
+        debug!("smt query: {:?}", query);
+        for values in solve(&query) {
+            if self.handler.is_stopped_or_skip() || self.handler.cond.is_done() {
+                break;
+            }
+            let mut buf = self.handler.buf.clone();
+            for (&p, &v) in query.positions.iter().zip(values.iter()) {
+                buf[p] = v;
+            }
+            // Saved to the depot if it is a new path
+            self.handler.execute_cond_buf(&buf);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+// What the solver knows about the cmps on the path: only the operands of
+// some runs and the bytes they are tainted by. The tainted operand of a cmp
+// is modeled as a function of those bytes, read as a little endian integer.
+
+use crate::cond_stmt::CondStmt;
+use angora_common::{defs, tag::TagSeg};
+
+#[cfg_attr(not(feature = "smt"), allow(dead_code))]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    // A copy of the bytes, in either byte order
+    Copy { big_endian: bool },
+    // Some function of the bytes, fitted to the (bytes, operand) samples
+    Fit(Vec<(u64, u64)>),
+}
+
+#[cfg_attr(not(feature = "smt"), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub positions: Vec<usize>,
+    pub size: u32,
+    // The basic op, a switch is an equality
+    pub op: u32,
+    pub signed: bool,
+    pub tainted_is_arg1: bool,
+    pub tainted: Operand,
+    pub other: u64,
+    // Whether the cmp has to hold
+    pub goal: bool,
+}
+
+#[cfg_attr(not(feature = "smt"), allow(dead_code))]
+#[derive(Debug)]
+pub struct Query {
+    // The input bytes the solution may change
+    pub positions: Vec<usize>,
+    pub target: Constraint,
+    // The cmps before the target on the path, which keep their outcome
+    pub prefix: Vec<Constraint>,
+}
+
+pub fn positions(offsets: &[TagSeg]) -> Vec<usize> {
+    let mut pos: Vec<usize> = offsets
+        .iter()
+        .flat_map(|o| o.begin as usize..o.end as usize)
+        .collect();
+    pos.sort_unstable();
+    pos.dedup();
+    pos
+}
+
+pub fn read_le(buf: &[u8], positions: &[usize]) -> u64 {
+    positions
+        .iter()
+        .rev()
+        .fold(0, |v, &p| (v << 8) | buf[p] as u64)
+}
+
+pub fn mask(size: u32) -> u64 {
+    if size >= 8 {
+        u64::MAX
+    } else {
+        (1 << (size * 8)) - 1
+    }
+}
+
+// The integer cmps the solver can model
+pub fn basic_op(cond: &CondStmt) -> Option<u32> {
+    let base = &cond.base;
+    if !base.is_explore() || base.is_float() || base.size == 0 || base.size > 8 {
+        return None;
+    }
+    let op = base.op & defs::COND_BASIC_MASK;
+    if op == defs::COND_SW_OP {
+        Some(defs::COND_ICMP_EQ_OP)
+    } else if (defs::COND_ICMP_EQ_OP..=defs::COND_ICMP_SLE_OP).contains(&op) {
+        Some(op)
+    } else {
+        None
+    }
+}
+
+// A cmp on the path whose tainted operand is a copy of its bytes, so it can
+// be kept with its outcome on the input.
+pub fn copy_constraint(cond: &CondStmt, buf: &[u8]) -> Option<Constraint> {
+    let op = basic_op(cond)?;
+    let pos = positions(&cond.offsets);
+    if pos.is_empty() || pos.len() > 8 || pos.iter().any(|&p| p >= buf.len()) {
+        return None;
+    }
+    let m = mask(cond.base.size);
+    let le = read_le(buf, &pos) & m;
+    let be = pos.iter().fold(0u64, |v, &p| (v << 8) | buf[p] as u64) & m;
+    let (arg1, arg2) = (cond.base.arg1 & m, cond.base.arg2 & m);
+    let (big_endian, tainted_is_arg1) = if le == arg1 {
+        (false, true)
+    } else if le == arg2 {
+        (false, false)
+    } else if pos.len() > 1 && be == arg1 {
+        (true, true)
+    } else if pos.len() > 1 && be == arg2 {
+        (true, false)
+    } else {
+        return None;
+    };
+    Some(Constraint {
+        positions: pos,
+        size: cond.base.size,
+        op,
+        signed: cond.base.is_signed(),
+        tainted_is_arg1,
+        tainted: Operand::Copy { big_endian },
+        other: if tainted_is_arg1 { arg2 } else { arg1 },
+        goal: cond.base.condition == defs::COND_TRUE_ST,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smt_copy_constraint() {
+        let buf = b"\x00\x12\x34zz".to_vec();
+        let mut cond = CondStmt::new();
+        cond.base.op = defs::COND_ICMP_ULT_OP;
+        cond.base.size = 4;
+        cond.base.arg1 = 7;
+        cond.base.arg2 = 0x1234;
+        cond.base.condition = defs::COND_FALSE_ST;
+        cond.offsets = vec![TagSeg {
+            sign: false,
+            begin: 1,
+            end: 3,
+        }];
+        let c = copy_constraint(&cond, &buf).unwrap();
+        assert_eq!(c.positions, vec![1, 2]);
+        assert_eq!(c.tainted, Operand::Copy { big_endian: true });
+        assert!(!c.tainted_is_arg1 && !c.goal);
+        assert_eq!(c.other, 7);
+
+        cond.base.arg2 = 0x1235;
+        assert!(copy_constraint(&cond, &buf).is_none());
+    }
+}
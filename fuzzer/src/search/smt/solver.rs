@@ -0,0 +1,207 @@
+// Bit-vector queries for z3, only built with the `smt` feature
+
+use super::query::{mask, Constraint, Operand, Query};
+use angora_common::{config, defs};
+use std::collections::HashMap;
+use z3::{
+    ast::{Ast, Bool, BV},
+    Config, Context, SatResult, Solver,
+};
+
+fn bytes_value<'ctx>(
+    bytes: &HashMap<usize, BV<'ctx>>,
+    c: &Constraint,
+    big_endian: bool,
+) -> BV<'ctx> {
+    // The first byte is the least significant one, unless big endian
+    let mut order: Vec<&BV<'ctx>> = c.positions.iter().map(|p| &bytes[p]).collect();
+    if !big_endian {
+        order.reverse();
+    }
+    let mut v = order[0].clone();
+    for b in order.iter().skip(1) {
+        v = v.concat(b);
+    }
+    let bits = c.size * 8;
+    let len = v.get_size();
+    if len < bits {
+        v.zero_ext(bits - len)
+    } else if len > bits {
+        v.extract(bits - 1, 0)
+    } else {
+        v
+    }
+}
+
+// The operand as a function of the bytes that matches all the samples:
+// a * x + b or x ^ b, with x in either byte order.
+fn fit<'ctx>(
+    ctx: &'ctx Context,
+    bytes: &HashMap<usize, BV<'ctx>>,
+    c: &Constraint,
+    samples: &[(u64, u64)],
+) -> Option<BV<'ctx>> {
+    let bits = c.size * 8;
+    let n = c.positions.len();
+    let swap = |x: u64| {
+        let mut v = 0;
+        for i in 0..n {
+            v = (v << 8) | ((x >> (i * 8)) & 0xff);
+        }
+        v
+    };
+    let m = mask(c.size);
+    let value = |x: u64| BV::from_u64(ctx, x & m, bits);
+    for &big_endian in [false, true].iter() {
+        let xs: Vec<u64> = samples
+            .iter()
+            .map(|(x, _)| if big_endian { swap(*x) } else { *x })
+            .collect();
+        let solver = Solver::new(ctx);
+        let a = BV::new_const(ctx, "a", bits);
+        let b = BV::new_const(ctx, "b", bits);
+        for (x, (_, y)) in xs.iter().zip(samples.iter()) {
+            solver.assert(&value(*x).bvmul(&a).bvadd(&b)._eq(&value(*y)));
+        }
+        if solver.check() == SatResult::Sat {
+            let model = solver.get_model()?;
+            let (a, b) = (model.eval(&a)?, model.eval(&b)?);
+            return Some(bytes_value(bytes, c, big_endian).bvmul(&a).bvadd(&b));
+        }
+        let key = (xs[0] ^ samples[0].1) & m;
+        if xs.iter().zip(samples.iter()).all(|(x, (_, y))| (x ^ y) & m == key) {
+            return Some(bytes_value(bytes, c, big_endian).bvxor(&value(key)));
+        }
+    }
+    None
+}
+
+fn holds<'ctx>(
+    ctx: &'ctx Context,
+    bytes: &HashMap<usize, BV<'ctx>>,
+    c: &Constraint,
+) -> Option<Bool<'ctx>> {
+    let tainted = match &c.tainted {
+        Operand::Copy { big_endian } => bytes_value(bytes, c, *big_endian),
+        Operand::Fit(samples) => fit(ctx, bytes, c, samples)?,
+    };
+    let other = BV::from_u64(ctx, c.other, c.size * 8);
+    let (l, r) = if c.tainted_is_arg1 {
+        (&tainted, &other)
+    } else {
+        (&other, &tainted)
+    };
+    let pred = match c.op {
+        defs::COND_ICMP_EQ_OP => l._eq(r),
+        defs::COND_ICMP_NE_OP => l._eq(r).not(),
+        defs::COND_ICMP_UGT_OP if c.signed => l.bvsgt(r),
+        defs::COND_ICMP_UGE_OP if c.signed => l.bvsge(r),
+        defs::COND_ICMP_ULT_OP if c.signed => l.bvslt(r),
+        defs::COND_ICMP_ULE_OP if c.signed => l.bvsle(r),
+        defs::COND_ICMP_UGT_OP => l.bvugt(r),
+        defs::COND_ICMP_UGE_OP => l.bvuge(r),
+        defs::COND_ICMP_ULT_OP => l.bvult(r),
+        defs::COND_ICMP_ULE_OP => l.bvule(r),
+        defs::COND_ICMP_SGT_OP => l.bvsgt(r),
+        defs::COND_ICMP_SGE_OP => l.bvsge(r),
+        defs::COND_ICMP_SLT_OP => l.bvslt(r),
+        defs::COND_ICMP_SLE_OP => l.bvsle(r),
+        _ => return None,
+    };
+    Some(if c.goal { pred } else { pred.not() })
+}
+
+// Values of the bytes in query.positions, different ones for every solution
+pub fn solve(query: &Query) -> Vec<Vec<u8>> {
+    let mut cfg = Config::new();
+    cfg.set_timeout_msec(config::SMT_TIMEOUT_MS);
+    let ctx = Context::new(&cfg);
+    let bytes: HashMap<usize, BV> = query
+        .positions
+        .iter()
+        .map(|&p| (p, BV::new_const(&ctx, format!("b{}", p), 8)))
+        .collect();
+
+    let solver = Solver::new(&ctx);
+    match holds(&ctx, &bytes, &query.target) {
+        Some(goal) => solver.assert(&goal),
+        None => return vec![],
+    }
+    for c in query.prefix.iter() {
+        if let Some(keep) = holds(&ctx, &bytes, c) {
+            solver.assert(&keep);
+        }
+    }
+
+    let mut solutions = vec![];
+    while solutions.len() < config::SMT_MAX_SOLUTIONS && solver.check() == SatResult::Sat {
+        let model = match solver.get_model() {
+            Some(m) => m,
+            None => break,
+        };
+        let values: Option<Vec<u8>> = query
+            .positions
+            .iter()
+            .map(|p| model.eval(&bytes[p]).and_then(|v| v.as_u64()).map(|v| v as u8))
+            .collect();
+        let values = match values {
+            Some(v) => v,
+            None => break,
+        };
+        // Block this solution
+        let same: Vec<Bool> = query
+            .positions
+            .iter()
+            .zip(values.iter())
+            .map(|(p, v)| bytes[p]._eq(&BV::from_u64(&ctx, *v as u64, 8)))
+            .collect();
+        let same: Vec<&Bool> = same.iter().collect();
+        solver.assert(&Bool::and(&ctx, &same).not());
+        solutions.push(values);
+    }
+    solutions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smt_solve_transformed_eq() {
+        // (x * 3 + 5) as u16 == 1000, x read from bytes 2 and 3 (little endian)
+        let samples = vec![(0u64, 5u64), (1, 8), (100, 305), (0x1234, 0x36a1)];
+        let target = Constraint {
+            positions: vec![2, 3],
+            size: 2,
+            op: defs::COND_ICMP_EQ_OP,
+            signed: false,
+            tainted_is_arg1: true,
+            tainted: Operand::Fit(samples),
+            other: 1000,
+            goal: true,
+        };
+        // The byte 3 has to stay below 0x60
+        let prefix = Constraint {
+            positions: vec![3],
+            size: 1,
+            op: defs::COND_ICMP_ULT_OP,
+            signed: false,
+            tainted_is_arg1: true,
+            tainted: Operand::Copy { big_endian: false },
+            other: 0x60,
+            goal: true,
+        };
+        let query = Query {
+            positions: vec![2, 3],
+            target,
+            prefix: vec![prefix],
+        };
+        let solutions = solve(&query);
+        assert!(!solutions.is_empty());
+        for s in solutions {
+            let x = s[0] as u64 | (s[1] as u64) << 8;
+            assert_eq!((x * 3 + 5) & 0xffff, 1000);
+            assert!(s[1] < 0x60);
+        }
+    }
+}
@@ -1,8 +1,13 @@
 use super::*;
-use crate::{branches::GlobalBranches, depot::Depot};
+use crate::{
+    branches::GlobalBranches,
+    depot::{Depot, INIT_DISTANCE},
+    dyncfg::cfg::CmpId,
+    scheduler::Scheduler,
+};
 use colored::*;
 use serde_derive::Serialize;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 #[derive(Default, Serialize)]
 pub struct ChartStats {
@@ -22,10 +27,20 @@ pub struct ChartStats {
     num_hangs: Counter,
     num_crashes: Counter,
     num_targets: Counter,
+    // Of the RNG, to repeat the run with --seed
+    seed: u64,
+    distance_metric: &'static str,
+    schedule: String,
+    num_dict_tokens: usize,
+    num_auto_tokens: usize,
 
     fuzz: FuzzStats,
     search: SearchStats,
     state: StateStats,
+    targets: TargetStats,
+    threads: ThreadStats,
+    havoc: HavocStats,
+    joint: JointStats,
 }
 
 impl ChartStats {
@@ -53,18 +68,31 @@ impl ChartStats {
         self.num_hangs += local.num_hangs;
         st.num_crashes += local.num_crashes;
         self.num_crashes += local.num_crashes;
-        //self.num_targets += local.num_targets;
+        self.havoc.merge(&local.havoc);
+        self.joint.merge(&local.joint);
 
         //local.clear();
     }
 
     pub fn sync_from_global(&mut self, depot: &Arc<Depot>, gb: &Arc<GlobalBranches>) {
         self.get_speed();
-        self.iter_pq(depot);
+        let pending = self.iter_pq(depot);
+        self.targets.sync(depot, &pending);
+        self.num_targets = self.targets.num_reached().into();
         self.sync_from_branches(gb);
+        self.distance_metric = depot.cfg.read().unwrap().metric_name();
+        self.schedule = depot.schedule.describe();
+        self.num_dict_tokens = depot.dict.num_user();
+        self.num_auto_tokens = depot.dict.num_auto();
     }
 
-    fn iter_pq(&mut self, depot: &Arc<Depot>) {
+    pub fn sync_from_scheduler(&mut self, scheduler: &Scheduler) {
+        self.threads = ThreadStats(scheduler.thread_stats());
+    }
+
+    // Returns the cmps of the conds that are not done yet, with the lowest
+    // distance of their conds in the queue
+    fn iter_pq(&mut self, depot: &Arc<Depot>) -> HashMap<CmpId, u32> {
         let q = match depot.queue.lock() {
             Ok(guard) => guard,
             Err(poisoned) => {
@@ -76,7 +104,20 @@ impl ChartStats {
         self.state = Default::default();
         self.fuzz.clear();
         let mut max_round = 0;
-        for (item, _) in q.iter() {
+        let mut pending = HashMap::new();
+        // The leased conds are being fuzzed and are not in the queue
+        let leased = match depot.leased.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                warn!("Lock poisoned. Results can be incorrect! Continuing...");
+                poisoned.into_inner()
+            }
+        };
+        for (item, p) in q.iter().chain(leased.iter()) {
+            if !item.is_done() && !item.base.is_afl() && p.distance != INIT_DISTANCE {
+                let d = pending.entry(item.base.cmpid).or_insert(p.distance);
+                *d = (*d).min(p.distance);
+            }
             if item.fuzz_times > max_round {
                 max_round = item.fuzz_times;
             }
@@ -87,6 +128,7 @@ impl ChartStats {
             }
         }
         self.max_rounds = max_round.into();
+        pending
     }
 
     fn sync_from_branches(&mut self, gb: &Arc<GlobalBranches>) {
@@ -121,6 +163,19 @@ impl ChartStats {
         )
     }
 
+    // Learned probabilities of the enabled havoc operators
+    pub fn havoc_distribution(&self, enabled: &[bool; NUM_HAVOC_OPS]) -> Vec<f64> {
+        self.havoc.distribution(enabled)
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn get_num_exec(&self) -> usize {
+        self.num_exec.0
+    }
+
     pub fn get_explore_num(&self) -> usize {
         self.fuzz
             .get(fuzz_type::FuzzType::ExploreFuzz.index())
@@ -154,6 +209,13 @@ impl fmt::Display for ChartStats {
     EXECS  |   TOTAL: {},     ROUND: {},     MAX_R: {}
     SPEED  |  PERIOD: {:6}r/s    TIME: {}us, 
     FOUND  |    PATH: {},     HANGS: {},   CRASHES: {} 
+  SCHEDULE | {}
+    DICT   |    USER: {},      AUTO: {}
+    JOINT  |{}
+{}
+{}
+{}
+{}
 {}
 {}
 {}
@@ -176,12 +238,20 @@ impl fmt::Display for ChartStats {
             self.num_inputs,
             self.num_hangs,
             self.num_crashes,
+            self.schedule,
+            self.num_dict_tokens,
+            self.num_auto_tokens,
+            self.joint,
             " -- FUZZ -- ".blue().bold(),
             self.fuzz,
             " -- SEARCH -- ".blue().bold(),
             self.search,
             " -- STATE -- ".blue().bold(),
             self.state,
+            " -- TARGETS -- ".blue().bold(),
+            self.targets,
+            " -- THREADS -- ".blue().bold(),
+            self.threads,
         )
     }
 }
@@ -0,0 +1,134 @@
+use angora_common::config;
+use serde_derive::Serialize;
+
+pub const NUM_HAVOC_OPS: usize = 8;
+static HAVOC_OP_NAMES: [&str; NUM_HAVOC_OPS] = [
+    "flip_bit",
+    "arith",
+    "interesting",
+    "random_byte",
+    "overwrite_token",
+    "delete_bytes",
+    "insert_bytes",
+    "insert_token",
+];
+// Before the operators were learned, flip_bit and arith had two of the eight
+// choices each, the others one. The learning starts from these weights.
+static HAVOC_OP_PRIORS: [f64; NUM_HAVOC_OPS] = [2.0, 2.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+
+#[derive(Clone, Copy, Serialize)]
+pub struct HavocOpStats {
+    name: &'static str,
+    // Havoc executions that used the operator, and the ones with new paths
+    execs: usize,
+    finds: usize,
+    probability: f64,
+}
+
+// Success of the havoc mutation operators of AFLFuzz, from which the
+// probability of picking each of them is learned.
+#[derive(Clone, Serialize)]
+pub struct HavocStats(Vec<HavocOpStats>);
+
+impl Default for HavocStats {
+    fn default() -> Self {
+        let sum: f64 = HAVOC_OP_PRIORS.iter().sum();
+        HavocStats(
+            HAVOC_OP_NAMES
+                .iter()
+                .zip(HAVOC_OP_PRIORS.iter())
+                .map(|(&name, w)| HavocOpStats {
+                    name,
+                    execs: 0,
+                    finds: 0,
+                    probability: w / sum,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl HavocStats {
+    pub fn record(&mut self, used: &[bool; NUM_HAVOC_OPS], found_new: bool) {
+        for (op, _) in self.0.iter_mut().zip(used.iter()).filter(|(_, u)| **u) {
+            op.execs += 1;
+            if found_new {
+                op.finds += 1;
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &HavocStats) {
+        for (op, o) in self.0.iter_mut().zip(other.0.iter()) {
+            op.execs += o.execs;
+            op.finds += o.finds;
+        }
+        let dist = self.distribution(&[true; NUM_HAVOC_OPS]);
+        for (op, p) in self.0.iter_mut().zip(dist) {
+            op.probability = p;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+
+    // Probability of the enabled operators: proportional to their smoothed
+    // rate of new paths per execution, mixed with a share of the prior so no
+    // operator is given up on. Without executions, it is the prior. The
+    // others have none.
+    pub fn distribution(&self, enabled: &[bool; NUM_HAVOC_OPS]) -> Vec<f64> {
+        let priors: Vec<f64> = HAVOC_OP_PRIORS
+            .iter()
+            .zip(enabled.iter())
+            .map(|(w, e)| if *e { *w } else { 0.0 })
+            .collect();
+        let rates: Vec<f64> = self
+            .0
+            .iter()
+            .zip(priors.iter())
+            .map(|(op, w)| (op.finds as f64 + w) / (op.execs as f64 + config::HAVOC_OP_PRIOR_EXECS))
+            .collect();
+        let sum: f64 = rates.iter().sum();
+        let prior_sum: f64 = priors.iter().sum();
+        let eps = config::HAVOC_OP_EXPLORATION;
+        rates
+            .iter()
+            .zip(priors.iter())
+            .map(|(r, w)| if *w > 0.0 { (1.0 - eps) * r / sum + eps * w / prior_sum } else { 0.0 })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn havoc_distribution() {
+        let mut local = HavocStats::default();
+        let prior = local.distribution(&[true; NUM_HAVOC_OPS]);
+        assert!((prior[0] - 0.2).abs() < 1e-9 && (prior[2] - 0.1).abs() < 1e-9);
+        for i in 0..1000 {
+            // Only arith finds new paths
+            local.record(&[true, true, false, false, false, false, false, false], i % 10 == 0);
+            local.record(&[true, false, false, true, false, false, false, false], false);
+        }
+        let mut global = HavocStats::default();
+        global.merge(&local);
+        global.merge(&local);
+        assert_eq!(global.0[1].execs, 2000);
+        assert_eq!(global.0[1].finds, 200);
+
+        let mut enabled = [false; NUM_HAVOC_OPS];
+        enabled[..4].copy_from_slice(&[true; 4]);
+        let dist = global.distribution(&enabled);
+        assert_eq!(dist.len(), NUM_HAVOC_OPS);
+        assert!(dist[4..].iter().all(|p| *p == 0.0));
+        assert!((dist.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(dist[1] > dist[0] && dist[0] > dist[3]);
+        // Never tried, so it keeps the prior and beats the one that failed
+        assert!(dist[2] > dist[3]);
+        assert!(dist[..4].iter().all(|p| *p >= config::HAVOC_OP_EXPLORATION / 6.0));
+    }
+}
@@ -0,0 +1,34 @@
+use super::*;
+use serde_derive::Serialize;
+
+// GdSearch with the conds before the target that read its bytes (`--joint`)
+#[derive(Clone, Default, Serialize)]
+pub struct JointStats {
+    pub num_searches: Counter,
+    pub num_prefix_conds: Counter,
+    // Inputs that brought the target closer but flipped a prefix cond, the
+    // search did not move to them
+    pub num_prevented: Counter,
+}
+
+impl JointStats {
+    pub fn merge(&mut self, other: &JointStats) {
+        self.num_searches += other.num_searches;
+        self.num_prefix_conds += other.num_prefix_conds;
+        self.num_prevented += other.num_prevented;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Default::default();
+    }
+}
+
+impl fmt::Display for JointStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "  SEARCH: {},  PREFIX: {},  PREVENTED: {}",
+            self.num_searches, self.num_prefix_conds, self.num_prevented
+        )
+    }
+}
@@ -15,6 +15,9 @@ pub struct LocalStats {
 
     pub avg_exec_time: SyncAverage,
     pub avg_edge_num: SyncAverage,
+
+    pub havoc: HavocStats,
+    pub joint: JointStats,
 }
 
 impl LocalStats {
@@ -31,6 +34,8 @@ impl LocalStats {
 
         self.start_time = Default::default();
         self.track_time = Default::default();
+        self.havoc.clear();
+        self.joint.clear();
     }
 
     pub fn find_new(&mut self, status: &StatusType) {
@@ -6,12 +6,18 @@ mod chart;
 mod entry;
 mod format;
 mod fuzz;
+mod havoc;
+mod joint;
 mod local;
 mod search;
 mod show;
 mod state;
+mod target;
+mod thread;
 
-pub use self::{bunny::*, chart::*, entry::*, local::*};
-use self::{fuzz::*, search::*, state::*};
+pub use self::{
+    bunny::*, chart::*, entry::*, havoc::{HavocStats, NUM_HAVOC_OPS}, joint::JointStats, local::*, thread::ThreadEntry,
+};
+use self::{fuzz::*, search::*, state::*, target::*, thread::ThreadStats};
 
 pub use self::{format::*, show::*};
@@ -1,5 +1,5 @@
 use super::ChartStats;
-use crate::{branches::GlobalBranches, depot::Depot};
+use crate::{branches::GlobalBranches, depot::Depot, scheduler::Scheduler};
 use angora_common::defs;
 use std::{
     fs,
@@ -12,11 +12,13 @@ pub fn show_stats(
     depot: &Arc<Depot>,
     gb: &Arc<GlobalBranches>,
     stats: &Arc<RwLock<ChartStats>>,
+    scheduler: &Scheduler,
 ) {
-    stats
-        .write()
-        .expect("Could not write stats.")
-        .sync_from_global(depot, gb);
+    {
+        let mut s = stats.write().expect("Could not write stats.");
+        s.sync_from_global(depot, gb);
+        s.sync_from_scheduler(scheduler);
+    }
 
     let dir = depot
         .dirs
@@ -0,0 +1,138 @@
+use super::*;
+use crate::{depot::Depot, dyncfg::cfg::CmpId};
+use colored::*;
+use serde_derive::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+// Targets shown in the terminal, all of them are in chart_stat.json
+const MAX_SHOWN_TARGETS: usize = 10;
+
+#[derive(Clone, Serialize)]
+pub struct TargetEntry {
+    cmpid: CmpId,
+    reached: bool,
+    // Seconds after the fuzzer was started
+    first_reached: Option<u64>,
+    num_inputs: usize,
+    first_input: Option<usize>,
+    // Lowest queue distance of the pending conds of the target
+    best_distance: Option<u32>,
+    solved: bool,
+    num_crashes: usize,
+    weight: f64,
+    groups: Vec<String>,
+}
+
+impl fmt::Display for TargetEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reached = match self.first_reached {
+            Some(t) => format_time(time::Duration::from_secs(t)).cyan(),
+            None => "--".normal(),
+        };
+        let distance = match self.best_distance {
+            Some(d) => format!("{:>7}", d).yellow(),
+            None => format!("{:>7}", "--").normal(),
+        };
+        let crashed = if self.num_crashes > 0 { "yes".red().bold() } else { "no".normal() };
+        write!(
+            f,
+            " {:>9} |  REACHED: {},  INPUTS: {},  DIST: {},  SOLVED: {},  CRASH: {}",
+            self.cmpid,
+            reached,
+            Counter(self.num_inputs),
+            distance,
+            if self.solved { "yes".green().bold() } else { "no".normal() },
+            crashed
+        )
+    }
+}
+
+#[derive(Default, Serialize)]
+pub struct TargetStats(Vec<TargetEntry>);
+
+impl TargetStats {
+    // pending has the lowest distance of the conds in the queue that are not
+    // done yet, by cmp
+    pub fn sync(&mut self, depot: &Arc<Depot>, pending: &HashMap<CmpId, u32>) {
+        let infos = depot.get_target_infos();
+        let cfg = depot.cfg.read().unwrap();
+
+        let mut entries = vec![];
+        for cmpid in cfg.get_all_targets() {
+            let info = infos.get(&cmpid).cloned().unwrap_or_default();
+            entries.push(TargetEntry {
+                cmpid,
+                reached: info.first_reached.is_some(),
+                first_reached: info.first_reached,
+                num_inputs: info.num_inputs,
+                first_input: info.first_input,
+                best_distance: pending.get(&cmpid).cloned(),
+                solved: cfg.is_solved(cmpid),
+                num_crashes: info.num_crashes,
+                weight: cfg.get_target_weight(cmpid),
+                groups: cfg.get_target_groups(cmpid),
+            });
+        }
+        entries.sort_by_key(|e| e.cmpid);
+        self.0 = entries;
+    }
+
+    pub fn num_reached(&self) -> usize {
+        self.0.iter().filter(|e| e.reached).count()
+    }
+
+    // (name, reached, solved, crashed, total) of every target group
+    fn groups(&self) -> Vec<(&str, usize, usize, usize, usize)> {
+        let mut groups: BTreeMap<&str, (usize, usize, usize, usize)> = BTreeMap::new();
+        for e in self.0.iter() {
+            for g in e.groups.iter() {
+                let c = groups.entry(g).or_default();
+                c.0 += e.reached as usize;
+                c.1 += e.solved as usize;
+                c.2 += (e.num_crashes > 0) as usize;
+                c.3 += 1;
+            }
+        }
+        groups.into_iter().map(|(g, c)| (g, c.0, c.1, c.2, c.3)).collect()
+    }
+}
+
+impl fmt::Display for TargetStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "           |  REACHED: {},  SOLVED: {},  TOTAL: {}",
+            Counter(self.num_reached()),
+            Counter(self.0.iter().filter(|e| e.solved).count()),
+            Counter(self.0.len())
+        )?;
+        for (name, reached, solved, crashed, total) in self.groups() {
+            write!(
+                f,
+                "\n {:>9} |  REACHED: {},  SOLVED: {},  CRASHED: {},  TOTAL: {}",
+                name.magenta(),
+                Counter(reached),
+                Counter(solved),
+                Counter(crashed),
+                Counter(total)
+            )?;
+        }
+        // Reached targets first, then the heaviest and the ones we are closest to
+        let mut shown: Vec<&TargetEntry> = self.0.iter().collect();
+        shown.sort_by(|a, b| {
+            (!a.reached, b.weight, a.best_distance.unwrap_or(u32::MAX))
+                .partial_cmp(&(!b.reached, a.weight, b.best_distance.unwrap_or(u32::MAX)))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        for e in shown.iter().take(MAX_SHOWN_TARGETS) {
+            write!(f, "\n{}", e)?;
+        }
+        if self.0.len() > MAX_SHOWN_TARGETS {
+            write!(f, "\n           |  ... {} more in chart_stat.json", self.0.len() - MAX_SHOWN_TARGETS)?;
+        }
+        Ok(())
+    }
+}
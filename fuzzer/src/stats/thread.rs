@@ -0,0 +1,52 @@
+use super::*;
+use serde_derive::Serialize;
+
+// Threads shown in the terminal, all of them are in chart_stat.json
+const MAX_SHOWN_THREADS: usize = 8;
+
+#[derive(Clone, Copy, Default, Serialize)]
+pub struct ThreadEntry {
+    pub num_conds: Counter,
+    pub num_exec: Counter,
+    // Conds leased from the depot and stolen from other threads
+    pub num_leased: Counter,
+    pub num_stolen: Counter,
+    // Waiting for conds, while other threads still have some
+    pub idle_time: TimeDuration,
+}
+
+impl fmt::Display for ThreadEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CONDS: {},  EXECS: {},  LEASED: {},  STOLEN: {},  IDLE: {}",
+            self.num_conds, self.num_exec, self.num_leased, self.num_stolen, self.idle_time
+        )
+    }
+}
+
+#[derive(Default, Serialize)]
+pub struct ThreadStats(pub Vec<ThreadEntry>);
+
+impl fmt::Display for ThreadStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut total = ThreadEntry::default();
+        for e in self.0.iter() {
+            total.num_conds += e.num_conds;
+            total.num_exec += e.num_exec;
+            total.num_leased += e.num_leased;
+            total.num_stolen += e.num_stolen;
+            total.idle_time += e.idle_time;
+        }
+        write!(f, "       ALL |  {}", total)?;
+        if self.0.len() > 1 {
+            for (i, e) in self.0.iter().enumerate().take(MAX_SHOWN_THREADS) {
+                write!(f, "\n {:>9} |  {}", i, e)?;
+            }
+            if self.0.len() > MAX_SHOWN_THREADS {
+                write!(f, "\n           |  ... {} more in chart_stat.json", self.0.len() - MAX_SHOWN_THREADS)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+// When a campaign ends on its own, e.g. in CI or for benchmarks. The main
+// thread checks the conditions after every sync, and the fuzzer exits with
+// the code of the reason it stopped for, so scripts can tell them apart.
+
+use std::{fmt, time::Duration};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    // SIGINT
+    Interrupted,
+    // All the constraints of the queue have been explored
+    Exhausted,
+    // The seeds have no constraint
+    NoConstraints,
+    TimeBudget,
+    ExecBudget,
+    Crash,
+    TargetCrash,
+    TargetsReached,
+    TargetsSolved,
+    NoCoverage,
+}
+
+impl StopReason {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            // Ends of a normal run, as before the stop conditions
+            StopReason::Interrupted | StopReason::Exhausted | StopReason::NoConstraints => 0,
+            StopReason::TimeBudget => 10,
+            StopReason::ExecBudget => 11,
+            StopReason::Crash => 12,
+            StopReason::TargetCrash => 13,
+            StopReason::TargetsReached => 14,
+            StopReason::TargetsSolved => 15,
+            StopReason::NoCoverage => 16,
+        }
+    }
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            StopReason::Interrupted => "interrupted",
+            StopReason::Exhausted => "all constraints explored",
+            StopReason::NoConstraints => "no constraints in the seeds",
+            StopReason::TimeBudget => "time budget used up",
+            StopReason::ExecBudget => "exec budget used up",
+            StopReason::Crash => "found a crash",
+            StopReason::TargetCrash => "found a crash at a target",
+            StopReason::TargetsReached => "targets reached",
+            StopReason::TargetsSolved => "targets solved",
+            StopReason::NoCoverage => "no new coverage",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CrashStop {
+    Any,
+    Target,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TargetStop {
+    AnyReached,
+    AllReached,
+    AnySolved,
+    AllSolved,
+}
+
+// Where the campaign is at, as seen by the main thread
+#[derive(Clone, Debug, Default)]
+pub struct Progress {
+    pub elapsed: Duration,
+    pub num_exec: usize,
+    pub num_crashes: usize,
+    pub num_target_crashes: usize,
+    pub num_targets: usize,
+    pub num_reached: usize,
+    pub num_solved: usize,
+    // Since the last new input
+    pub idle: Duration,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StopConditions {
+    pub time_budget: Option<Duration>,
+    pub exec_budget: Option<usize>,
+    pub crash: Option<CrashStop>,
+    pub targets: Option<TargetStop>,
+    pub max_idle: Option<Duration>,
+}
+
+impl StopConditions {
+    pub fn new(
+        time_budget: Option<u64>,
+        exec_budget: Option<usize>,
+        crash: Option<&str>,
+        targets: Option<&str>,
+        max_idle: Option<u64>,
+    ) -> Self {
+        Self {
+            time_budget: time_budget.map(Duration::from_secs),
+            exec_budget,
+            crash: crash.map(|c| match c {
+                "any" => CrashStop::Any,
+                "target" => CrashStop::Target,
+                _ => panic!("Unknown crash stop condition {:?}, use any or target", c),
+            }),
+            targets: targets.map(|t| match t {
+                "any_reached" => TargetStop::AnyReached,
+                "all_reached" => TargetStop::AllReached,
+                "any_solved" => TargetStop::AnySolved,
+                "all_solved" => TargetStop::AllSolved,
+                _ => panic!("Unknown target stop condition {:?}", t),
+            }),
+            max_idle: max_idle.map(|m| Duration::from_secs(m * 60)),
+        }
+    }
+
+    pub fn check(&self, p: &Progress) -> Option<StopReason> {
+        if let Some(c) = self.crash {
+            if p.num_target_crashes > 0 || (c == CrashStop::Any && p.num_crashes > 0) {
+                return Some(if p.num_target_crashes > 0 {
+                    StopReason::TargetCrash
+                } else {
+                    StopReason::Crash
+                });
+            }
+        }
+        if let Some(t) = self.targets {
+            let done = p.num_targets > 0
+                && match t {
+                    TargetStop::AnyReached => p.num_reached > 0,
+                    TargetStop::AllReached => p.num_reached >= p.num_targets,
+                    TargetStop::AnySolved => p.num_solved > 0,
+                    TargetStop::AllSolved => p.num_solved >= p.num_targets,
+                };
+            if done {
+                return Some(match t {
+                    TargetStop::AnyReached | TargetStop::AllReached => StopReason::TargetsReached,
+                    TargetStop::AnySolved | TargetStop::AllSolved => StopReason::TargetsSolved,
+                });
+            }
+        }
+        if self.exec_budget.is_some_and(|b| p.num_exec >= b) {
+            return Some(StopReason::ExecBudget);
+        }
+        if self.time_budget.is_some_and(|b| p.elapsed >= b) {
+            return Some(StopReason::TimeBudget);
+        }
+        if self.max_idle.is_some_and(|m| p.idle >= m) {
+            return Some(StopReason::NoCoverage);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stop_conditions() {
+        let mut p = Progress {
+            num_targets: 2,
+            ..Default::default()
+        };
+        assert_eq!(StopConditions::default().check(&p), None);
+
+        let stop = StopConditions::new(Some(60), Some(1000), Some("target"), Some("all_reached"), Some(5));
+        assert_eq!(stop.check(&p), None);
+        p.num_crashes = 1;
+        p.num_reached = 1;
+        assert_eq!(stop.check(&p), None);
+        p.num_reached = 2;
+        assert_eq!(stop.check(&p), Some(StopReason::TargetsReached));
+        p.num_target_crashes = 1;
+        assert_eq!(stop.check(&p), Some(StopReason::TargetCrash));
+
+        let stop = StopConditions::new(Some(60), Some(1000), None, None, Some(5));
+        p.idle = Duration::from_secs(300);
+        assert_eq!(stop.check(&p), Some(StopReason::NoCoverage));
+        p.elapsed = Duration::from_secs(60);
+        assert_eq!(stop.check(&p), Some(StopReason::TimeBudget));
+        p.num_exec = 1000;
+        assert_eq!(stop.check(&p), Some(StopReason::ExecBudget));
+        assert_ne!(StopReason::ExecBudget.exit_code(), StopReason::TimeBudget.exit_code());
+        assert_eq!(StopReason::Exhausted.exit_code(), 0);
+    }
+}
@@ -0,0 +1,299 @@
+// Shrink a crashing or target-reaching input while keeping the same outcome:
+// the same crash bucket (see triage), or reaching the same target cmp.
+// The bytes that no condition on the path depends on are deleted first, since
+// they are unlikely to matter. AFL-style block deletion runs on what is left.
+
+use crate::{
+    command::CommandOpt, depot, dyncfg::cfg::CmpId,
+    executor::{Executor, StatusType}, options::FuzzOptions, triage::Triage,
+};
+use angora_common::defs;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+static CANDIDATE_FILE: &str = "candidate";
+// Smallest block of the block deletion is 1 / TMIN_BLOCK_DIV of the input
+const TMIN_BLOCK_DIV: usize = 16;
+
+enum Outcome {
+    Crash(String),
+    Target(CmpId),
+}
+
+struct Minimizer {
+    executor: Executor,
+    // Runs the sanitized binary of SanOpt if there is one
+    crash_executor: Option<Executor>,
+    triage: Triage,
+    candidate_path: PathBuf,
+    outcome: Outcome,
+    // The fast binary crashes as well, so the (slow) triage run can be skipped
+    // for candidates that do not crash at all.
+    fast_crash_check: bool,
+    num_exec: usize,
+}
+
+impl Minimizer {
+    fn bucket_of(&mut self, buf: &Vec<u8>) -> Option<String> {
+        fs::write(&self.candidate_path, buf).expect("Could not write candidate");
+        self.triage.run_crash(&self.candidate_path, None).map(|(r, _)| r.bucket_id())
+    }
+
+    fn reaches_target(&mut self, buf: &Vec<u8>, target: CmpId) -> bool {
+        match self.executor.track_conds(buf) {
+            Some(conds) => conds.iter().any(|c| c.base.cmpid == target),
+            None => false,
+        }
+    }
+
+    fn preserves(&mut self, buf: &Vec<u8>) -> bool {
+        self.num_exec += 1;
+        match &self.outcome {
+            Outcome::Crash(bucket) => {
+                let bucket = bucket.clone();
+                if self.fast_crash_check {
+                    let ex = self.crash_executor.as_mut().unwrap_or(&mut self.executor);
+                    if ex.run_status(buf) != StatusType::Crash {
+                        return false;
+                    }
+                }
+                self.bucket_of(buf) == Some(bucket)
+            },
+            Outcome::Target(target) => {
+                let target = *target;
+                self.reaches_target(buf, target)
+            },
+        }
+    }
+
+    // Which bytes the conditions on the path depend on. For a target, only
+    // the conditions before it matter.
+    fn relevant_bytes(&mut self, buf: &Vec<u8>) -> Option<Vec<bool>> {
+        let conds = self.executor.track_conds(buf)?;
+        let target = match self.outcome {
+            Outcome::Target(t) => Some(t),
+            Outcome::Crash(_) => None,
+        };
+        let mut relevant = vec![false; buf.len()];
+        for cond in conds.iter() {
+            for off in cond.offsets.iter().chain(cond.offsets_opt.iter()) {
+                let end = (off.end as usize).min(buf.len());
+                for r in relevant.iter_mut().take(end).skip(off.begin as usize) {
+                    *r = true;
+                }
+            }
+            if Some(cond.base.cmpid) == target {
+                break;
+            }
+        }
+        Some(relevant)
+    }
+
+    // Try to delete the ranges, the last one first so the others stay valid.
+    fn delete_ranges(&mut self, buf: &mut Vec<u8>, ranges: &[(usize, usize)]) {
+        for &(begin, end) in ranges.iter().rev() {
+            let mut candidate = buf.clone();
+            candidate.drain(begin..end);
+            if !candidate.is_empty() && self.preserves(&candidate) {
+                *buf = candidate;
+            }
+        }
+    }
+
+    fn delete_irrelevant(&mut self, buf: &mut Vec<u8>) {
+        let relevant = match self.relevant_bytes(buf) {
+            Some(r) => r,
+            None => {
+                warn!("Could not track the input, falling back to block deletion");
+                return;
+            },
+        };
+        let ranges = irrelevant_ranges(&relevant);
+        if ranges.is_empty() {
+            return;
+        }
+        // Most of the time all of them can go at once
+        let candidate: Vec<u8> = buf
+            .iter()
+            .zip(relevant.iter())
+            .filter(|(_, r)| **r)
+            .map(|(b, _)| *b)
+            .collect();
+        if !candidate.is_empty() && self.preserves(&candidate) {
+            *buf = candidate;
+            return;
+        }
+        self.delete_ranges(buf, &ranges);
+    }
+
+    fn delete_blocks(&mut self, buf: &mut Vec<u8>) {
+        let mut block = (buf.len() / TMIN_BLOCK_DIV).max(1);
+        loop {
+            let mut pos = buf.len();
+            while pos > 0 {
+                let begin = pos.saturating_sub(block);
+                let mut candidate = buf.clone();
+                candidate.drain(begin..pos);
+                if !candidate.is_empty() && self.preserves(&candidate) {
+                    *buf = candidate;
+                }
+                pos = begin;
+            }
+            if block == 1 {
+                break;
+            }
+            block /= 2;
+        }
+    }
+
+    // Replace the irrelevant bytes that are left with '0', as afl-tmin does
+    fn normalize(&mut self, buf: &mut Vec<u8>) {
+        let relevant = match self.relevant_bytes(buf) {
+            Some(r) => r,
+            None => return,
+        };
+        for (begin, end) in irrelevant_ranges(&relevant) {
+            if buf[begin..end].iter().all(|b| *b == b'0') {
+                continue;
+            }
+            let mut candidate = buf.clone();
+            for b in &mut candidate[begin..end] {
+                *b = b'0';
+            }
+            if self.preserves(&candidate) {
+                *buf = candidate;
+            }
+        }
+    }
+}
+
+// Maximal ranges of bytes that are not relevant
+fn irrelevant_ranges(relevant: &[bool]) -> Vec<(usize, usize)> {
+    let mut ranges = vec![];
+    let mut begin = None;
+    for (i, r) in relevant.iter().enumerate() {
+        match (begin, r) {
+            (None, false) => begin = Some(i),
+            (Some(b), true) => {
+                ranges.push((b, i));
+                begin = None;
+            },
+            _ => {},
+        }
+    }
+    if let Some(b) = begin {
+        ranges.push((b, relevant.len()));
+    }
+    ranges
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn tmin_main(
+    input: &str,
+    output: &str,
+    track_target: &str,
+    pargs: Vec<String>,
+    sanopt_target: Option<&str>,
+    target_cmp: Option<CmpId>,
+    mem_limit: u64,
+    time_limit: u64,
+) {
+    pretty_env_logger::init();
+    if !Path::new(input).is_file() {
+        error!("Input {} does not exist", input);
+        process::exit(1);
+    }
+
+    let work_dir = env::temp_dir().join(format!("parmesan-tmin-{}", process::id()));
+    fs::create_dir_all(&work_dir).expect("Could not create work directory");
+    let opts = FuzzOptions {
+        track_target: track_target.to_string(),
+        pargs,
+        mem_limit,
+        time_limit,
+        enable_afl: false,
+        enable_exploitation: false,
+        sanopt_targets: sanopt_target.into_iter().map(String::from).collect(),
+        ..Default::default()
+    };
+    let cmd = CommandOpt::new(&opts, &work_dir);
+    let result = minimize(Path::new(input), &work_dir, &cmd, target_cmp);
+    // Clears the tmpfs directory
+    drop(cmd);
+    if let Err(e) = fs::remove_dir_all(&work_dir) {
+        warn!("Could not remove work directory {:?}: {:?}", work_dir, e);
+    }
+
+    let (orig_len, buf, num_exec) = result.unwrap_or_else(|e| {
+        error!("{}", e);
+        process::exit(1);
+    });
+    fs::write(output, &buf).expect("Could not write minimized input");
+    println!(
+        "Minimized {} bytes to {} bytes with {} executions, written to {}",
+        orig_len,
+        buf.len(),
+        num_exec,
+        output
+    );
+}
+
+fn minimize(
+    input: &Path,
+    work_dir: &Path,
+    cmd: &CommandOpt,
+    target_cmp: Option<CmpId>,
+) -> Result<(usize, Vec<u8>, usize), String> {
+    let executor = Executor::standalone(cmd.specify(1), work_dir);
+    let crash_executor = cmd
+        .sanitizers
+        .first()
+        .map(|_| Executor::standalone(cmd.specify(2).sanopt(0), work_dir));
+    let mut m = Minimizer {
+        executor,
+        crash_executor,
+        triage: Triage::new(cmd, &work_dir.join(defs::CRASHES_DIR)),
+        candidate_path: work_dir.join(CANDIDATE_FILE),
+        outcome: Outcome::Target(0),
+        fast_crash_check: false,
+        num_exec: 0,
+    };
+
+    let mut buf = depot::read_from_file(input);
+    let orig_len = buf.len();
+    m.outcome = match target_cmp {
+        Some(target) => {
+            if !m.reaches_target(&buf, target) {
+                return Err(format!("The input does not reach target {}", target));
+            }
+            Outcome::Target(target)
+        },
+        None => {
+            let bucket = m.bucket_of(&buf).ok_or("The input does not crash")?;
+            info!("Crash bucket: {}", bucket);
+            let ex = m.crash_executor.as_mut().unwrap_or(&mut m.executor);
+            m.fast_crash_check = ex.run_status(&buf) == StatusType::Crash;
+            Outcome::Crash(bucket)
+        },
+    };
+
+    m.delete_irrelevant(&mut buf);
+    m.delete_blocks(&mut buf);
+    m.normalize(&mut buf);
+    Ok((orig_len, buf, m.num_exec))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tmin_irrelevant_ranges() {
+        let relevant = vec![false, false, true, true, false, true, false];
+        assert_eq!(irrelevant_ranges(&relevant), vec![(0, 2), (4, 5), (6, 7)]);
+        assert_eq!(irrelevant_ranges(&[true, true]), vec![]);
+    }
+}
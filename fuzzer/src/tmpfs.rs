@@ -16,7 +16,8 @@ pub fn create_tmpfs_dir(target: &Path) {
         let dir_name = format!("angora_tmp_{}", pid);
         let tmp_dir = shm_dir.join(dir_name);
         fs::create_dir(&tmp_dir).unwrap();
-        if target.exists() {
+        // The link may be dangling if /dev/shm was cleared (e.g., by a reboot)
+        if target.symlink_metadata().is_ok() {
             fs::remove_file(target).unwrap();
         }
         symlink(&tmp_dir, target).unwrap();
@@ -0,0 +1,234 @@
+// Checksum checks: an equality between a value computed from many bytes of
+// the input (a CRC, a hash) and a few bytes copied directly from the input.
+// The gradient of such a cond is meaningless, so it is not searched. The
+// bytes of the field are repaired with the value the check expects instead.
+
+use crate::cond_stmt::CondStmt;
+use angora_common::{cond_stmt_base::CondStmtBase, config, defs, tag::TagSeg};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checksum {
+    // The cmp of the check, to read its operands on other inputs
+    pub cond: CondStmtBase,
+    // The bytes [begin, end) the computed value is compared with
+    pub begin: usize,
+    pub end: usize,
+    pub big_endian: bool,
+    // Which operand is the computed value
+    pub computed_is_arg1: bool,
+    // The bytes the value is computed from, and the value on the tracked input
+    pub span: Vec<TagSeg>,
+    pub value: u64,
+}
+
+fn span_len(offsets: &[TagSeg]) -> usize {
+    offsets.iter().map(|o| (o.end - o.begin) as usize).sum()
+}
+
+// The segments as one range, None if there is a gap between them
+fn contiguous(offsets: &[TagSeg]) -> Option<(usize, usize)> {
+    let mut segs: Vec<_> = offsets
+        .iter()
+        .map(|o| (o.begin as usize, o.end as usize))
+        .collect();
+    segs.sort();
+    let (begin, mut end) = *segs.first()?;
+    for (b, e) in segs.into_iter().skip(1) {
+        if b > end {
+            return None;
+        }
+        end = end.max(e);
+    }
+    Some((begin, end))
+}
+
+fn mask(size: usize) -> u64 {
+    if size >= 8 {
+        u64::MAX
+    } else {
+        (1 << (size * 8)) - 1
+    }
+}
+
+fn read_value(buf: &[u8], big_endian: bool) -> u64 {
+    let fold = |v: u64, b: &u8| (v << 8) | *b as u64;
+    if big_endian {
+        buf.iter().fold(0, fold)
+    } else {
+        buf.iter().rev().fold(0, fold)
+    }
+}
+
+impl Checksum {
+    pub fn detect(cond: &CondStmt, buf: &[u8]) -> Option<Self> {
+        let op = cond.base.op & defs::COND_BASIC_MASK;
+        let size = cond.base.size as usize;
+        if (op != defs::COND_ICMP_EQ_OP && op != defs::COND_ICMP_NE_OP) || !(2..=8).contains(&size) {
+            return None;
+        }
+        // The field is the operand with fewer tainted bytes. The tracker puts
+        // the operand with fewer segments in offsets, which may be the other.
+        let (field, span) = if span_len(&cond.offsets) <= span_len(&cond.offsets_opt) {
+            (&cond.offsets, &cond.offsets_opt)
+        } else {
+            (&cond.offsets_opt, &cond.offsets)
+        };
+        let (begin, end) = contiguous(field)?;
+        if end - begin != size || end > buf.len() || span_len(span) < config::MIN_CHECKSUM_SPAN {
+            return None;
+        }
+        // Repairing the field would change the value
+        if span
+            .iter()
+            .any(|o| (o.begin as usize) < end && begin < o.end as usize)
+        {
+            return None;
+        }
+
+        let m = mask(size);
+        let (arg1, arg2) = (cond.base.arg1 & m, cond.base.arg2 & m);
+        // The field is a direct copy of the input bytes
+        for &big_endian in [false, true].iter() {
+            let field = read_value(&buf[begin..end], big_endian);
+            let computed_is_arg1 = if field == arg2 {
+                true
+            } else if field == arg1 {
+                false
+            } else {
+                continue;
+            };
+            return Some(Self {
+                cond: cond.base,
+                begin,
+                end,
+                big_endian,
+                computed_is_arg1,
+                span: span.clone(),
+                value: if computed_is_arg1 { arg1 } else { arg2 },
+            });
+        }
+        None
+    }
+
+    pub fn computed(&self, arg1: u64, arg2: u64) -> u64 {
+        let v = if self.computed_is_arg1 { arg1 } else { arg2 };
+        v & mask(self.end - self.begin)
+    }
+
+    // Whether the bytes the value is computed from are the same as in base
+    pub fn is_span_unchanged(&self, base: &[u8], buf: &[u8]) -> bool {
+        self.span.iter().all(|o| {
+            let (b, e) = (o.begin as usize, o.end as usize);
+            e <= base.len() && e <= buf.len() && base[b..e] == buf[b..e]
+        })
+    }
+
+    // Write the value to the field, returns false if it is already there
+    pub fn fix(&self, buf: &mut [u8], value: u64) -> bool {
+        if self.end > buf.len() {
+            return false;
+        }
+        let size = self.end - self.begin;
+        let mut bytes: Vec<u8> = (0..size).map(|i| (value >> (i * 8)) as u8).collect();
+        if self.big_endian {
+            bytes.reverse();
+        }
+        let field = &mut buf[self.begin..self.end];
+        if field == &bytes[..] {
+            return false;
+        }
+        field.copy_from_slice(&bytes);
+        true
+    }
+}
+
+// Mark the checksum checks on the path of the input, so they are not
+// searched, and return them in the order they were reached.
+pub fn find_checksums(cond_list: &mut [CondStmt], buf: &[u8]) -> Vec<Checksum> {
+    let mut checksums: Vec<Checksum> = vec![];
+    for cond in cond_list.iter_mut() {
+        if !cond.base.is_explore() || checksums.len() >= config::MAX_CHECKSUMS_PER_INPUT {
+            continue;
+        }
+        if let Some(ck) = Checksum::detect(cond, buf) {
+            cond.is_desirable = false;
+            // The same check in a loop, e.g. over the chunks of the file
+            if !checksums.iter().any(|c| c.begin == ck.begin) {
+                checksums.push(ck);
+            }
+        }
+    }
+    checksums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(begin: u32, end: u32) -> TagSeg {
+        TagSeg {
+            sign: false,
+            begin,
+            end,
+        }
+    }
+
+    #[test]
+    fn checksum_detect_and_fix() {
+        // 4 bytes of big endian crc after 20 bytes of data
+        let mut buf = vec![7u8; 20];
+        buf.extend_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+        let mut cond = CondStmt::new();
+        cond.base.op = defs::COND_ICMP_EQ_OP;
+        cond.base.size = 4;
+        cond.base.arg1 = 0xdeadbeef;
+        cond.base.arg2 = 0x12345678;
+        cond.offsets = vec![seg(20, 22), seg(22, 24)];
+        cond.offsets_opt = vec![seg(0, 20)];
+
+        let ck = Checksum::detect(&cond, &buf).unwrap();
+        assert_eq!((ck.begin, ck.end, ck.big_endian), (20, 24, true));
+        assert_eq!(ck.value, 0xdeadbeef);
+        assert!(ck.fix(&mut buf, ck.value));
+        assert_eq!(&buf[20..], &[0xde, 0xad, 0xbe, 0xef]);
+        assert!(!ck.fix(&mut buf, ck.value));
+
+        let mut other = buf.clone();
+        other[23] = 0;
+        assert!(ck.is_span_unchanged(&buf, &other));
+        other[3] = 0;
+        assert!(!ck.is_span_unchanged(&buf, &other));
+
+        // The field is not a copy of the input
+        cond.base.arg1 = 1;
+        cond.base.arg2 = 0x87654321;
+        assert!(Checksum::detect(&cond, &buf).is_none());
+        // The computed value depends on too few bytes
+        cond.base.arg2 = 0xdeadbeef;
+        assert!(Checksum::detect(&cond, &buf).is_some());
+        cond.offsets_opt = vec![seg(0, 4)];
+        assert!(Checksum::detect(&cond, &buf).is_none());
+    }
+
+    #[test]
+    fn checksum_detect_swapped() {
+        // The computed value is tainted by fewer segments than the field
+        let mut buf = vec![7u8; 20];
+        buf.extend_from_slice(&[0x78, 0x56, 0x34, 0x12]);
+        let mut cond = CondStmt::new();
+        cond.base.op = defs::COND_ICMP_NE_OP;
+        cond.base.size = 4;
+        cond.base.arg1 = 0x12345678;
+        cond.base.arg2 = 0xdeadbeef;
+        cond.offsets = vec![seg(0, 20)];
+        cond.offsets_opt = vec![seg(20, 21), seg(21, 22), seg(22, 24)];
+
+        let ck = Checksum::detect(&cond, &buf).unwrap();
+        assert_eq!((ck.begin, ck.end, ck.big_endian), (20, 24, false));
+        assert!(!ck.computed_is_arg1);
+        assert_eq!(ck.value, 0xdeadbeef);
+        assert_eq!(ck.span.len(), 1);
+        assert!(ck.fix(&mut buf, ck.value));
+        assert_eq!(&buf[20..], &[0xef, 0xbe, 0xad, 0xde]);
+    }
+}
@@ -2,7 +2,7 @@
   filter undesirable conds
 */
 
-use crate::cond_stmt::CondStmt;
+use crate::{cond_stmt::CondStmt, search::i2s};
 use angora_common::{cond_stmt_base::CondStmtBase, config, defs};
 use std::{
     collections::HashSet,
@@ -117,3 +117,20 @@ pub fn filter_cond_list(cond_list: &mut Vec<CondStmt>) {
         dedup_exploit, dedup_explore
     );
 }
+
+// The cmps logged by the fast binary have no taint, they are kept if one of
+// their operands is found in the input, for I2S.
+pub fn filter_logged_cmps(cond_list: &mut Vec<CondStmt>, buf: &[u8]) {
+    let mut unique_conds = HashSet::new();
+    for cond in cond_list {
+        if !cond.base.is_explore()
+            || cond.base.op & defs::COND_BOOL_MASK > 0
+            || exceed_max_order(cond)
+            || size_not_match(cond)
+            || !unique_conds.insert(CondArgs::from(&cond.base))
+            || !i2s::is_input_to_state(buf, &cond.base)
+        {
+            cond.is_desirable = false;
+        }
+    }
+}
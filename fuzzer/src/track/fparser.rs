@@ -4,7 +4,7 @@ use crate::{
     cond_stmt::{CondState, CondStmt},
     mut_input,
 };
-use angora_common::{defs, tag::TagSeg};
+use angora_common::{cond_stmt_base::CondStmtBase, defs, tag::TagSeg};
 use runtime::get_log_data;
 use std::{collections::HashMap, io, path::Path};
 
@@ -124,3 +124,26 @@ pub fn load_track_data(
 
     (cond_list, indirect_edges_list)
 }
+
+// The conds without the track binary: the cmps the fast binary logged on the
+// input, with no taint
+pub fn load_logged_cmps(
+    logged: &[CondStmtBase],
+    id: u32,
+    speed: u32,
+    buf: &[u8],
+) -> Vec<CondStmt> {
+    let mut cond_list: Vec<CondStmt> = logged
+        .iter()
+        .map(|cond_base| {
+            let mut cond = CondStmt::from(*cond_base);
+            // It is the outcome in the log, not a taint label
+            cond.base.lb1 = 0;
+            cond.base.belong = id;
+            cond.speed = speed;
+            cond
+        })
+        .collect();
+    filter::filter_logged_cmps(&mut cond_list, buf);
+    cond_list
+}
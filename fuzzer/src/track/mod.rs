@@ -1,5 +1,9 @@
+mod checksum;
 mod filter;
 mod fparser;
 mod load_pin_data;
 
-pub use self::fparser::*;
+pub use self::{
+    checksum::{find_checksums, Checksum},
+    fparser::*,
+};
@@ -0,0 +1,98 @@
+use super::CrashReport;
+use crate::sanitizer::SanitizerKind;
+use angora_common::defs;
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+// All crashes with the same bug type and stack hash
+#[derive(Serialize, Deserialize)]
+pub struct Bucket {
+    pub report: CrashReport,
+    // File names in the crashes directory
+    pub crashes: Vec<String>,
+    // The smallest crash of the bucket, copied to the bucket directory
+    pub reproducer: String,
+    pub reproducer_len: usize,
+    // The SanOpt builds that caught crashes of the bucket while fuzzing
+    #[serde(default)]
+    pub sanitizers: Vec<SanitizerKind>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct TriageIndex {
+    // Crashes with a lower id have been triaged already
+    pub num_triaged: usize,
+    pub buckets: BTreeMap<String, Bucket>,
+}
+
+impl TriageIndex {
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(defs::TRIAGE_INDEX_FILE);
+        match fs::File::open(&path) {
+            Ok(f) => serde_json::from_reader(BufReader::new(f)).unwrap_or_else(|e| {
+                warn!("Could not parse triage index {:?}: {:?}", path, e);
+                Default::default()
+            }),
+            Err(_) => Default::default(),
+        }
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let tmp_path = dir.join(format!("{}.tmp", defs::TRIAGE_INDEX_FILE));
+        {
+            let writer = BufWriter::new(fs::File::create(&tmp_path)?);
+            serde_json::to_writer_pretty(writer, self)?;
+        }
+        fs::rename(tmp_path, dir.join(defs::TRIAGE_INDEX_FILE))
+    }
+
+    // Add a crash to its bucket and keep the smallest reproducer.
+    // Returns true if the crash opened a new bucket.
+    pub fn add(
+        &mut self,
+        dir: &Path,
+        report: CrashReport,
+        crash: &Path,
+        stderr: &str,
+        kind: Option<SanitizerKind>,
+    ) -> io::Result<bool> {
+        let name = crash.file_name().unwrap().to_string_lossy().to_string();
+        let len = fs::metadata(crash)?.len() as usize;
+        let id = report.bucket_id();
+        let bucket_dir = dir.join(&id);
+        let repro_path: PathBuf = bucket_dir.join(defs::TRIAGE_REPRO_FILE);
+
+        let is_new = !self.buckets.contains_key(&id);
+        if is_new {
+            fs::create_dir_all(&bucket_dir)?;
+            fs::write(bucket_dir.join(defs::TRIAGE_REPORT_FILE), stderr)?;
+            self.buckets.insert(
+                id.clone(),
+                Bucket {
+                    report,
+                    crashes: vec![],
+                    reproducer: repro_path.to_string_lossy().to_string(),
+                    reproducer_len: usize::MAX,
+                    sanitizers: vec![],
+                },
+            );
+        }
+
+        let bucket = self.buckets.get_mut(&id).unwrap();
+        bucket.crashes.push(name);
+        if let Some(k) = kind {
+            if !bucket.sanitizers.contains(&k) {
+                bucket.sanitizers.push(k);
+            }
+        }
+        if len < bucket.reproducer_len {
+            fs::copy(crash, &repro_path)?;
+            bucket.reproducer_len = len;
+        }
+        Ok(is_new)
+    }
+}
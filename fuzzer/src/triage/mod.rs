@@ -0,0 +1,159 @@
+// Re-run the crashes on the sanitized binary with symbolization enabled and
+// group them into buckets by bug type and stack hash. The buckets are in
+// `triage/index.json` in the output directory, every bucket has a directory
+// with the sanitizer report and its smallest crash as reproducer.
+
+mod index;
+mod report;
+
+pub use self::{index::TriageIndex, report::CrashReport};
+
+use crate::{
+    command::CommandOpt,
+    depot::{self, Depot},
+    sanitizer::{Sanitizer, SanitizerKind},
+};
+use angora_common::{config, defs};
+use std::{
+    fs,
+    os::unix::process::ExitStatusExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread, time,
+};
+use wait_timeout::ChildExt;
+
+static TRIAGE_STDERR_FILE: &str = "triage_stderr";
+
+pub struct Triage {
+    bin: String,
+    sanitizers: Vec<Sanitizer>,
+    args: Vec<String>,
+    is_stdin: bool,
+    ld_library: String,
+    stderr_path: PathBuf,
+    dir: PathBuf,
+    crashes_dir: PathBuf,
+    index: TriageIndex,
+}
+
+impl Triage {
+    pub fn new(cmd: &CommandOpt, crashes_dir: &Path) -> Self {
+        let dir = crashes_dir.parent().unwrap().join(defs::TRIAGE_DIR);
+        fs::create_dir_all(&dir).expect("Could not create triage directory");
+        // Continue where the last run stopped when resuming
+        let index = TriageIndex::load(&dir);
+        // Prefer the sanitized binary of SanOpt, it has the useful report
+        let bin = cmd
+            .sanitizers
+            .first()
+            .map_or_else(|| cmd.main.0.clone(), |s| s.bin.clone());
+        Self {
+            bin,
+            sanitizers: cmd.sanitizers.clone(),
+            args: cmd.main.1.clone(),
+            is_stdin: cmd.is_stdin,
+            ld_library: cmd.ld_library.clone(),
+            stderr_path: cmd.tmp_dir.join(TRIAGE_STDERR_FILE),
+            dir,
+            crashes_dir: crashes_dir.to_path_buf(),
+            index,
+        }
+    }
+
+    // Run the input and parse the report, on the build of the sanitizer that
+    // caught it if it is known. Returns None if it does not crash.
+    pub fn run_crash(&self, input: &Path, kind: Option<SanitizerKind>) -> Option<(CrashReport, String)> {
+        let bin = kind
+            .and_then(|k| self.sanitizers.iter().find(|s| s.kind == k))
+            .map_or(&self.bin, |s| &s.bin);
+        let input_str = input.to_str().unwrap().to_string();
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|a| if a == "@@" { input_str.clone() } else { a.clone() })
+            .collect();
+        let stdin = if self.is_stdin {
+            Stdio::from(fs::File::open(input).ok()?)
+        } else {
+            Stdio::null()
+        };
+        let stderr = fs::File::create(&self.stderr_path).expect("Could not create triage stderr file");
+
+        let mut child = Command::new(bin)
+            .args(&args)
+            .stdin(stdin)
+            .stdout(Stdio::null())
+            .stderr(Stdio::from(stderr))
+            .env(defs::ASAN_OPTIONS_VAR, defs::ASAN_TRIAGE_OPTIONS_CONTENT)
+            .env(defs::MSAN_OPTIONS_VAR, defs::MSAN_TRIAGE_OPTIONS_CONTENT)
+            .env(defs::UBSAN_OPTIONS_VAR, defs::UBSAN_TRIAGE_OPTIONS_CONTENT)
+            .env(defs::LD_LIBRARY_PATH_VAR, &self.ld_library)
+            .spawn()
+            .expect("Could not run target for triage");
+
+        let timeout = time::Duration::from_secs(config::TIME_LIMIT_TRIAGE);
+        let status = match child.wait_timeout(timeout).unwrap() {
+            Some(status) => status,
+            None => {
+                child.kill().expect("Could not send kill signal to child.");
+                child.wait().expect("Error during waiting for child.");
+                return Some((CrashReport::without_report("timeout"), String::new()));
+            }
+        };
+
+        let output = String::from_utf8_lossy(&fs::read(&self.stderr_path).unwrap_or_default()).to_string();
+        if let Some(report) = CrashReport::parse(&output) {
+            return Some((report, output));
+        }
+        status.signal().map(|sig| (CrashReport::from_signal(sig), output))
+    }
+
+    // Triage the crashes with an id in [num_triaged, num_crashes)
+    pub fn triage_crashes(&mut self, num_crashes: usize, depot: &Depot) {
+        if self.index.num_triaged >= num_crashes {
+            return;
+        }
+        for id in self.index.num_triaged..num_crashes {
+            let crash = depot::get_file_name(&self.crashes_dir, id);
+            let kind = depot.get_crash_tag(id);
+            let (report, stderr) = self
+                .run_crash(&crash, kind)
+                .unwrap_or_else(|| (CrashReport::without_report("unreproducible"), String::new()));
+            match self.index.add(&self.dir, report, &crash, &stderr, kind) {
+                Ok(true) => info!("New crash bucket for {:?}", crash),
+                Ok(false) => {},
+                Err(e) => warn!("Could not triage crash {:?}: {:?}", crash, e),
+            }
+        }
+        self.index.num_triaged = num_crashes;
+        if let Err(e) = self.index.save(&self.dir) {
+            warn!("Could not save triage index: {:?}", e);
+        }
+    }
+
+    pub fn num_buckets(&self) -> usize {
+        self.index.buckets.len()
+    }
+}
+
+// Triage the new crashes in the background while fuzzing. The thread returns
+// the triage state when fuzzing stops, the crashes found after that are
+// triaged by the caller once the fuzzing threads have finished.
+pub fn triage_loop(mut triage: Triage, depot: Arc<Depot>, running: Arc<AtomicBool>) -> thread::JoinHandle<Triage> {
+    thread::spawn(move || {
+        // A crash is only triaged in the round after it was counted,
+        // so the file has been written completely.
+        let mut num_crashes = triage.index.num_triaged;
+        while running.load(Ordering::SeqCst) {
+            triage.triage_crashes(num_crashes, &depot);
+            num_crashes = depot.num_crashes.load(Ordering::Relaxed);
+            thread::sleep(time::Duration::from_secs(config::TRIAGE_INTERVAL));
+        }
+        triage
+    })
+}
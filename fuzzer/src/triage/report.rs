@@ -0,0 +1,236 @@
+// Parse the report a sanitizer prints to stderr, e.g.
+//   ==42==ERROR: AddressSanitizer: heap-buffer-overflow on address ...
+//       #0 0x4f2a1b in parse_header /src/parser.c:120:9
+//       #1 0x4f3c10 in main /src/main.c:33:5
+//   SUMMARY: AddressSanitizer: heap-buffer-overflow /src/parser.c:120:9 in parse_header
+
+use angora_common::config;
+
+// Frames of the sanitizer runtime are not part of the bug
+static RUNTIME_FRAME_PREFIXES: [&str; 6] = [
+    "__asan",
+    "__msan",
+    "__ubsan",
+    "__sanitizer",
+    "__interceptor",
+    "__interception",
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub sanitizer: String,
+    pub bug_type: String,
+    // First frame outside of the sanitizer runtime
+    pub frame: Option<String>,
+    pub stack: Vec<String>,
+    pub stack_hash: u64,
+}
+
+impl CrashReport {
+    pub fn parse(stderr: &str) -> Option<Self> {
+        let mut sanitizer = None;
+        let mut bug_type = None;
+        let mut runtime_error = None;
+        let mut stack = vec![];
+        let mut in_stack = false;
+
+        for line in stderr.lines() {
+            let line = line.trim();
+            if let Some(pos) = line.find("SUMMARY: ") {
+                // The summary has the cleanest bug type, prefer it over the header
+                if let Some((san, ty)) = split_sanitizer(&line[pos + 9..]) {
+                    sanitizer = Some(san);
+                    bug_type = Some(ty);
+                }
+            } else if let Some(pos) = line.find("ERROR: ").or_else(|| line.find("WARNING: ")) {
+                if bug_type.is_none() {
+                    let rest = &line[pos..];
+                    let rest = &rest[rest.find(' ').unwrap() + 1..];
+                    if let Some((san, ty)) = split_sanitizer(rest) {
+                        sanitizer = Some(san);
+                        bug_type = Some(ty);
+                    }
+                }
+            } else if let Some(pos) = line.find(": runtime error: ") {
+                // UBSan: file:line:col: runtime error: signed integer overflow: ...
+                if runtime_error.is_none() {
+                    let msg = &line[pos + 17..];
+                    let msg = msg.split(':').next().unwrap_or(msg);
+                    let loc = strip_column(&line[..pos]);
+                    runtime_error = Some((normalize_message(msg), loc));
+                }
+            } else if let Some(frame) = parse_frame(line) {
+                // Only the first stack trace, the others are about the allocation
+                if stack.is_empty() || in_stack {
+                    in_stack = true;
+                    stack.push(frame);
+                }
+            } else if in_stack {
+                in_stack = false;
+            }
+        }
+
+        let sanitizer = sanitizer?;
+        let mut bug_type = bug_type.unwrap_or_else(|| "unknown".to_string());
+        let stack: Vec<String> = stack
+            .into_iter()
+            .filter(|f| !is_runtime_frame(f))
+            .collect();
+        let mut frame = stack.first().cloned();
+        if let Some((msg, loc)) = runtime_error {
+            bug_type = msg;
+            if frame.is_none() {
+                frame = Some(loc);
+            }
+        }
+
+        let mut report = CrashReport {
+            sanitizer,
+            bug_type,
+            frame,
+            stack,
+            stack_hash: 0,
+        };
+        report.stack_hash = report.compute_hash();
+        Some(report)
+    }
+
+    // The program crashed without a sanitizer report
+    pub fn from_signal(signal: i32) -> Self {
+        Self::without_report(&format!("signal-{}", signal))
+    }
+
+    pub fn without_report(bug_type: &str) -> Self {
+        let mut report = CrashReport {
+            sanitizer: "none".to_string(),
+            bug_type: bug_type.to_string(),
+            frame: None,
+            stack: vec![],
+            stack_hash: 0,
+        };
+        report.stack_hash = report.compute_hash();
+        report
+    }
+
+    pub fn bucket_id(&self) -> String {
+        let ty: String = self
+            .bug_type
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        format!("{}-{:016x}", ty, self.stack_hash)
+    }
+
+    // FNV-1a, the hash has to be the same across builds to resume the triage
+    fn compute_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let frames = self.stack.iter().take(config::TRIAGE_STACK_DEPTH);
+        for s in std::iter::once(&self.bug_type).chain(self.frame.iter()).chain(frames) {
+            for b in s.bytes().chain(std::iter::once(0)) {
+                hash ^= b as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+// "AddressSanitizer: heap-buffer-overflow on ..." -> (AddressSanitizer, heap-buffer-overflow)
+fn split_sanitizer(s: &str) -> Option<(String, String)> {
+    let pos = s.find(": ")?;
+    let sanitizer = &s[..pos];
+    if !sanitizer.ends_with("Sanitizer") {
+        return None;
+    }
+    let bug_type = s[pos + 2..].split_whitespace().next()?;
+    Some((sanitizer.to_string(), bug_type.to_string()))
+}
+
+// "#3 0x4f2a1b in parse_header /src/parser.c:120:9" -> "parse_header /src/parser.c:120"
+fn parse_frame(line: &str) -> Option<String> {
+    let mut it = line.split_whitespace();
+    let num = it.next()?;
+    if !num.starts_with('#') || num[1..].parse::<u32>().is_err() {
+        return None;
+    }
+    it.next()?;
+    let mut rest: Vec<&str> = it.collect();
+    if rest.first() == Some(&"in") {
+        rest.remove(0);
+    }
+    let func = rest.first()?;
+    match rest.get(1) {
+        Some(loc) if !loc.starts_with('(') => Some(format!("{} {}", func, strip_column(loc))),
+        // Not symbolized: "(/path/to/bin+0x1234)", keep the module offset
+        _ => Some(rest.join(" ")),
+    }
+}
+
+fn strip_column(loc: &str) -> String {
+    let parts: Vec<&str> = loc.rsplitn(3, ':').collect();
+    if parts.len() == 3 && parts[0].parse::<u32>().is_ok() && parts[1].parse::<u32>().is_ok() {
+        format!("{}:{}", parts[2], parts[1])
+    } else {
+        loc.to_string()
+    }
+}
+
+fn is_runtime_frame(frame: &str) -> bool {
+    RUNTIME_FRAME_PREFIXES.iter().any(|p| frame.starts_with(p))
+}
+
+// Drop the values from a message, they differ between crashes of the same bug
+fn normalize_message(msg: &str) -> String {
+    msg.split_whitespace()
+        .filter(|w| !w.chars().any(|c| c.is_ascii_digit()))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_asan_report() {
+        let stderr = r#"
+=================================================================
+==4242==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000011 at pc 0x4f2a1b bp 0x7ffd sp 0x7ffd
+READ of size 1 at 0x602000000011 thread T0
+    #0 0x4f2a1b in __asan_memcpy (/out/base64.asan+0x4f2a1b)
+    #1 0x4f2a1b in decode /src/base64.c:120:9
+    #2 0x4f3c10 in main /src/base64.c:33:5
+
+0x602000000011 is located 0 bytes to the right of 1-byte region
+allocated by thread T0 here:
+    #0 0x4b5d90 in malloc (/out/base64.asan+0x4b5d90)
+    #1 0x4f3b00 in main /src/base64.c:30:12
+
+SUMMARY: AddressSanitizer: heap-buffer-overflow /src/base64.c:120:9 in decode
+==4242==ABORTING
+"#;
+        let report = CrashReport::parse(stderr).unwrap();
+        assert_eq!(report.sanitizer, "AddressSanitizer");
+        assert_eq!(report.bug_type, "heap-buffer-overflow");
+        assert_eq!(report.frame, Some("decode /src/base64.c:120".to_string()));
+        assert_eq!(report.stack.len(), 2);
+
+        // Another path into decode is another bucket, another address is not
+        let other = stderr.replace("base64.c:33:5", "base64.c:40:5");
+        assert_ne!(CrashReport::parse(&other).unwrap().bucket_id(), report.bucket_id());
+        let same = stderr.replace("0x602000000011", "0x602000000051");
+        assert_eq!(CrashReport::parse(&same).unwrap().bucket_id(), report.bucket_id());
+    }
+
+    #[test]
+    fn parse_ubsan_report() {
+        let stderr = r#"/src/num.c:7:12: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'
+SUMMARY: UndefinedBehaviorSanitizer: undefined-behavior /src/num.c:7:12 in
+"#;
+        let report = CrashReport::parse(stderr).unwrap();
+        assert_eq!(report.sanitizer, "UndefinedBehaviorSanitizer");
+        assert_eq!(report.bug_type, "signed integer overflow");
+        assert_eq!(report.frame, Some("/src/num.c:7".to_string()));
+        assert_eq!(CrashReport::parse("Segmentation fault"), None);
+    }
+}
@@ -39,6 +39,8 @@
 #define COND_EXPLOIT_MASK 0x4000
 #define COND_FN_TYPE 0x8002
 #define COND_LEN_TYPE 0x8003
+// __angora_cond_cmpid while the fuzzer logs all cmps (defs::CMP_LOG_CMPID)
+#define CMP_LOG_CMPID 0xFFFFFFFF
 
 #ifdef DEBUG_INFO
 // #define DEBUG_PRINTF printf
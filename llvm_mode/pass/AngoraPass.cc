@@ -74,6 +74,9 @@ public:
 
   std::set<std::tuple<BasicBlock *, BasicBlock *>> CfgEdges;
   std::map<BasicBlock *, u32 > EdgesId;
+  // Source location of every cmp id, written to cmp_locs.json for the
+  // target acquisition of parmesan-targets
+  std::vector<std::string> CmpLocs;
   // Const Variables
   DenseSet<u32> UniqCidSet;
 
@@ -141,6 +144,7 @@ public:
   void setValueNonSan(Value *v);
   void setInsNonSan(Instruction *v);
   Value *castArgType(IRBuilder<> &IRB, Value *V);
+  Value *isTracedCond(IRBuilder<> &IRB, Constant *Cid);
   void initVariables(Module &M);
   void countEdge(Module &M, BasicBlock &BB);
   void visitIndirectCallDominator(CallInst *CI, u32 CallSiteId);
@@ -160,6 +164,8 @@ public:
   void resetIndirectCallContext(IRBuilder<> *IRB);
   void assignBasicBlockId(BasicBlock &BB, bool isFirst);
   void printCFG(raw_ostream &);
+  void recordCmpLoc(Instruction *Inst, u32 Cid);
+  void printCmpLocs(raw_ostream &);
 };
 
 } // namespace
@@ -228,6 +234,8 @@ u32 AngoraLLVMPass::getInstructionId(Instruction *Inst) {
 
   // Store mappings
   IdMap[Inst] = h;
+  if (FastMode)
+    recordCmpLoc(Inst, h);
 
   return h;
 }
@@ -311,7 +319,8 @@ void AngoraLLVMPass::initVariables(Module &M) {
                            ConstantInt::get(Int32Ty, 0), "__angora_prev_loc", 0,
                            GlobalVariable::GeneralDynamicTLSModel, 0, false);
 
-    Type *TraceCmpArgs[5] = {Int32Ty, Int32Ty, Int32Ty, Int64Ty, Int64Ty};
+    Type *TraceCmpArgs[7] = {Int32Ty, Int32Ty, Int32Ty, Int32Ty,
+                             Int32Ty, Int64Ty, Int64Ty};
     TraceCmpTy = FunctionType::get(Int32Ty, TraceCmpArgs, false);
     TraceCmp = M.getOrInsertFunction("__angora_trace_cmp", TraceCmpTy);
     if (Function *F = dyn_cast<Function>(TraceCmp)) {
@@ -711,13 +720,14 @@ void AngoraLLVMPass::visitCompareFunc(Instruction *Inst) {
 Value *AngoraLLVMPass::castArgType(IRBuilder<> &IRB, Value *V) {
   Type *OpType = V->getType();
   Value *NV = V;
+  // Floats are passed as their raw bits, the fuzzer compares them in ULPs
   if (OpType->isFloatTy()) {
-    NV = IRB.CreateFPToUI(V, Int32Ty);
+    NV = IRB.CreateBitCast(V, Int32Ty);
     setValueNonSan(NV);
-    NV = IRB.CreateIntCast(NV, Int64Ty, false);
+    NV = IRB.CreateZExt(NV, Int64Ty);
     setValueNonSan(NV);
   } else if (OpType->isDoubleTy()) {
-    NV = IRB.CreateFPToUI(V, Int64Ty);
+    NV = IRB.CreateBitCast(V, Int64Ty);
     setValueNonSan(NV);
   } else if (OpType->isPointerTy()) {
     NV = IRB.CreatePtrToInt(V, Int64Ty);
@@ -729,6 +739,21 @@ Value *AngoraLLVMPass::castArgType(IRBuilder<> &IRB, Value *V) {
   return NV;
 }
 
+// The fast binary calls the runtime for the cond the fuzzer is running, or
+// for every cmp while the fuzzer logs them.
+Value *AngoraLLVMPass::isTracedCond(IRBuilder<> &IRB, Constant *Cid) {
+  LoadInst *CurCid = IRB.CreateLoad(AngoraCondId, "angora_target_cond_id");
+  setInsNonSan(CurCid);
+  Value *CmpEq = IRB.CreateICmpEQ(Cid, CurCid, "angora_is_target_cond");
+  setValueNonSan(CmpEq);
+  Value *IsLog = IRB.CreateICmpEQ(
+      CurCid, ConstantInt::get(Int32Ty, CMP_LOG_CMPID), "angora_is_cmp_log");
+  setValueNonSan(IsLog);
+  Value *IsTraced = IRB.CreateOr(CmpEq, IsLog);
+  setValueNonSan(IsTraced);
+  return IsTraced;
+}
+
 void AngoraLLVMPass::processCmp(Instruction *Cond, Constant *Cid,
                                 Instruction *InsertPoint) {
   CmpInst *Cmp = dyn_cast<CmpInst>(Cond);
@@ -752,6 +777,15 @@ void AngoraLLVMPass::processCmp(Instruction *Cond, Constant *Cid,
 
   IRBuilder<> IRB(InsertPoint);
 
+  Value *SizeArg = ConstantInt::get(Int32Ty, num_bytes);
+  u32 predicate = Cmp->getPredicate();
+  if (ConstantInt *CInt = dyn_cast<ConstantInt>(OpArg[1])) {
+    if (CInt->isNegative()) {
+      predicate |= COND_SIGN_MASK;
+    }
+  }
+  Value *TypeArg = ConstantInt::get(Int32Ty, predicate);
+
   if (FastMode) {
     /*
     OpArg[0] = castArgType(IRB, OpArg[0]);
@@ -764,10 +798,7 @@ void AngoraLLVMPass::processCmp(Instruction *Cond, Constant *Cid,
         IRB.CreateCall(TraceCmp, {CondExt, Cid, CurCtx, OpArg[0], OpArg[1]});
     setInsNonSan(ProxyCall);
     */
-    LoadInst *CurCid = IRB.CreateLoad(AngoraCondId, "angora_target_cond_id");
-    setInsNonSan(CurCid);
-    Value *CmpEq = IRB.CreateICmpEQ(Cid, CurCid, "angora_is_target_cond");
-    setValueNonSan(CmpEq);
+    Value *CmpEq = isTracedCond(IRB, Cid);
 
     BranchInst *BI = cast<BranchInst>(
         SplitBlockAndInsertIfThen(CmpEq, InsertPoint, false, ColdCallWeights));
@@ -780,18 +811,10 @@ void AngoraLLVMPass::processCmp(Instruction *Cond, Constant *Cid,
     setValueNonSan(CondExt);
     LoadInst *CurCtx = ThenB.CreateLoad(AngoraContext, "angora_context");
     setInsNonSan(CurCtx);
-    CallInst *ProxyCall =
-        ThenB.CreateCall(TraceCmp, {CondExt, Cid, CurCtx, OpArg[0], OpArg[1]});
+    CallInst *ProxyCall = ThenB.CreateCall(
+        TraceCmp, {CondExt, Cid, CurCtx, SizeArg, TypeArg, OpArg[0], OpArg[1]});
     setInsNonSan(ProxyCall);
   } else if (TrackMode) {
-    Value *SizeArg = ConstantInt::get(Int32Ty, num_bytes);
-    u32 predicate = Cmp->getPredicate();
-    if (ConstantInt *CInt = dyn_cast<ConstantInt>(OpArg[1])) {
-      if (CInt->isNegative()) {
-        predicate |= COND_SIGN_MASK;
-      }
-    }
-    Value *TypeArg = ConstantInt::get(Int32Ty, predicate);
     Value *CondExt = IRB.CreateZExt(Cond, Int32Ty);
     setValueNonSan(CondExt);
     OpArg[0] = castArgType(IRB, OpArg[0]);
@@ -818,11 +841,10 @@ void AngoraLLVMPass::processBoolCmp(Value *Cond, Constant *Cid,
   Value *OpArg[2];
   OpArg[1] = ConstantInt::get(Int64Ty, 1);
   IRBuilder<> IRB(InsertPoint);
+  Value *SizeArg = ConstantInt::get(Int32Ty, 1);
+  Value *TypeArg = ConstantInt::get(Int32Ty, COND_EQ_OP | COND_BOOL_MASK);
   if (FastMode) {
-    LoadInst *CurCid = IRB.CreateLoad(AngoraCondId);
-    setInsNonSan(CurCid);
-    Value *CmpEq = IRB.CreateICmpEQ(Cid, CurCid);
-    setValueNonSan(CmpEq);
+    Value *CmpEq = isTracedCond(IRB, Cid);
     BranchInst *BI = cast<BranchInst>(
         SplitBlockAndInsertIfThen(CmpEq, InsertPoint, false, ColdCallWeights));
     setInsNonSan(BI);
@@ -833,12 +855,10 @@ void AngoraLLVMPass::processBoolCmp(Value *Cond, Constant *Cid,
     setValueNonSan(OpArg[0]);
     LoadInst *CurCtx = ThenB.CreateLoad(AngoraContext);
     setInsNonSan(CurCtx);
-    CallInst *ProxyCall =
-        ThenB.CreateCall(TraceCmp, {CondExt, Cid, CurCtx, OpArg[0], OpArg[1]});
+    CallInst *ProxyCall = ThenB.CreateCall(
+        TraceCmp, {CondExt, Cid, CurCtx, SizeArg, TypeArg, OpArg[0], OpArg[1]});
     setInsNonSan(ProxyCall);
   } else if (TrackMode) {
-    Value *SizeArg = ConstantInt::get(Int32Ty, 1);
-    Value *TypeArg = ConstantInt::get(Int32Ty, COND_EQ_OP | COND_BOOL_MASK);
     Value *CondExt = IRB.CreateZExt(Cond, Int32Ty);
     setValueNonSan(CondExt);
     OpArg[0] = IRB.CreateZExt(CondExt, Int64Ty);
@@ -896,10 +916,7 @@ void AngoraLLVMPass::visitSwitchInst(Module &M, Instruction *Inst) {
   IRBuilder<> IRB(Sw);
 
   if (FastMode) {
-    LoadInst *CurCid = IRB.CreateLoad(AngoraCondId);
-    setInsNonSan(CurCid);
-    Value *CmpEq = IRB.CreateICmpEQ(Cid, CurCid);
-    setValueNonSan(CmpEq);
+    Value *CmpEq = isTracedCond(IRB, Cid);
     BranchInst *BI = cast<BranchInst>(
         SplitBlockAndInsertIfThen(CmpEq, Sw, false, ColdCallWeights));
     setInsNonSan(BI);
@@ -1013,6 +1030,49 @@ void AngoraLLVMPass::printCFG(raw_ostream &out) {
   out << "]\n";
 }
 
+static std::string jsonEscape(StringRef S) {
+  std::string Out;
+  for (char C : S) {
+    if (C == '"' || C == '\\') {
+      Out += '\\';
+      Out += C;
+    } else if ((unsigned char)C < 0x20) {
+      char Buf[8];
+      snprintf(Buf, sizeof(Buf), "\\u%04x", C);
+      Out += Buf;
+    } else {
+      Out += C;
+    }
+  }
+  return Out;
+}
+
+void AngoraLLVMPass::recordCmpLoc(Instruction *Inst, u32 Cid) {
+  DILocation *Loc = Inst->getDebugLoc();
+  if (!Loc)
+    return;
+  std::string File = Loc->getFilename().str();
+  if (File.size() > 0 && File[0] != '/' && Loc->getDirectory().size() > 0)
+    File = Loc->getDirectory().str() + "/" + File;
+  std::string Entry;
+  raw_string_ostream OS(Entry);
+  OS << "{\"cmp\": " << Cid << ", \"bb\": " << EdgesId[Inst->getParent()]
+     << ", \"func\": \"" << jsonEscape(Inst->getFunction()->getName())
+     << "\", \"file\": \"" << jsonEscape(File) << "\", \"line\": "
+     << Loc->getLine() << ", \"col\": " << Loc->getColumn() << "}";
+  CmpLocs.push_back(OS.str());
+}
+
+void AngoraLLVMPass::printCmpLocs(raw_ostream &out) {
+  out << "[";
+  for (size_t i = 0; i < CmpLocs.size(); i++) {
+    if (i > 0)
+      out << ",\n";
+    out << CmpLocs[i];
+  }
+  out << "]\n";
+}
+
 bool AngoraLLVMPass::runOnModule(Module &M) {
 
   SAYF(cCYA "angora-llvm-pass\n");
@@ -1096,6 +1156,8 @@ bool AngoraLLVMPass::runOnModule(Module &M) {
     std::error_code EC;
     raw_fd_ostream InfoFile("edges.json", EC);
     printCFG(InfoFile);
+    raw_fd_ostream LocsFile("cmp_locs.json", EC);
+    printCmpLocs(LocsFile);
   }
   if (is_bc)
     OKF("Max constraint id is %d", CidCounter);
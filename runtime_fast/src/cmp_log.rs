@@ -0,0 +1,120 @@
+// corresponding to fuzzer/src/cond_stmt/cmp_log.rs
+
+use angora_common::{cmp_log::*, config, cond_stmt_base::CondStmtBase, defs, shm};
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    env,
+    ops::DerefMut,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+// The mode of the log is read once per run, in reset
+static IS_ON: AtomicBool = AtomicBool::new(false);
+
+#[inline(always)]
+pub fn is_on() -> bool {
+    IS_ON.load(Ordering::Relaxed)
+}
+
+pub struct ShmCmpLog {
+    log: shm::SHM<CmpLog>,
+    // Like the order of the track binary, see runtime/src/logger.rs
+    orders: HashMap<(u32, u32), u32>,
+}
+
+// shm contains pointer..
+unsafe impl Send for ShmCmpLog {}
+
+impl ShmCmpLog {
+    pub fn get_from_env_id() -> Option<Self> {
+        let id_val = env::var(defs::CMP_LOG_ENV_VAR);
+        match id_val {
+            Ok(val) => {
+                let shm_id = val.parse::<i32>().expect("Could not parse i32 value.");
+                let log = shm::SHM::<CmpLog>::from_id(shm_id);
+                if log.is_fail() {
+                    process::exit(1);
+                }
+                Some(Self {
+                    log,
+                    orders: HashMap::new(),
+                })
+            }
+            Err(_) => None,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn log_cmp(
+        &mut self,
+        condition: u32,
+        cmpid: u32,
+        context: u32,
+        size: u32,
+        op: u32,
+        arg1: u64,
+        arg2: u64,
+    ) {
+        let order = self.orders.entry((cmpid, context)).or_insert(0);
+        *order += 1;
+        let order = *order;
+        let len = self.log.len as usize;
+        if self.log.mode == CMP_LOG_ALL
+            && order <= config::MAX_COND_ORDER
+            && len < config::CMP_LOG_MAX_ENTRIES
+        {
+            self.log.entries[len] = CondStmtBase {
+                cmpid,
+                context,
+                order,
+                condition,
+                op,
+                size,
+                lb1: condition,
+                arg1,
+                arg2,
+                ..Default::default()
+            };
+            self.log.len += 1;
+        } else if self.log.mode == CMP_LOG_WATCH {
+            for cond in self.log.entries[..len].iter_mut() {
+                if cond.cmpid == cmpid && cond.context == context && cond.order & 0xFFFF == order {
+                    cond.arg1 = arg1;
+                    cond.arg2 = arg2;
+                    cond.lb1 = condition;
+                }
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.orders.clear();
+        if self.log.mode == CMP_LOG_ALL {
+            self.log.len = 0;
+        } else if self.log.mode == CMP_LOG_WATCH {
+            let len = self.log.len as usize;
+            for cond in self.log.entries[..len].iter_mut() {
+                cond.lb1 = u32::MAX;
+            }
+        }
+        IS_ON.store(self.log.mode != CMP_LOG_OFF, Ordering::Relaxed);
+    }
+}
+
+lazy_static! {
+    pub static ref SHM_CMP_LOG: Mutex<Option<ShmCmpLog>> =
+        Mutex::new(ShmCmpLog::get_from_env_id());
+}
+
+#[inline(always)]
+pub fn reset_cmp_log() {
+    let mut log = SHM_CMP_LOG.lock().expect("SHM mutex poisoned.");
+    if let Some(ref mut l) = log.deref_mut() {
+        l.reset();
+    }
+}
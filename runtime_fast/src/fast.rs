@@ -1,4 +1,4 @@
-use super::{shm_conds, forkcli, shm_branches};
+use super::{cmp_log, shm_conds, forkcli, shm_branches};
 use std::ops::DerefMut;
 
 use std::sync::Once;
@@ -18,9 +18,17 @@ pub extern "C" fn __angora_trace_cmp(
     condition: u32,
     cmpid: u32,
     context: u32,
+    size: u32,
+    op: u32,
     arg1: u64,
     arg2: u64,
 ) -> u32 {
+    if cmp_log::is_on() {
+        let mut log = cmp_log::SHM_CMP_LOG.lock().expect("SHM mutex poisoned.");
+        if let Some(ref mut l) = log.deref_mut() {
+            l.log_cmp(condition, cmpid, context, size, op, arg1, arg2);
+        }
+    }
     let mut conds = shm_conds::SHM_CONDS.lock().expect("SHM mutex poisoned.");
     match conds.deref_mut() {
         &mut Some(ref mut c) => {
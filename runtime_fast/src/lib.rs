@@ -1,3 +1,4 @@
+pub mod cmp_log;
 pub mod fast;
 pub mod forkcli;
 pub mod shm_conds;
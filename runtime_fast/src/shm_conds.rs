@@ -2,7 +2,7 @@
 
 use angora_common::{cond_stmt_base::CondStmtBase, defs, shm};
 use std::{env, process, ops::DerefMut, sync::Mutex};
-use super::context;
+use super::{cmp_log, context};
 use lazy_static::lazy_static;
 
 #[no_mangle]
@@ -15,6 +15,16 @@ fn set_cmpid(cid: u32) {
     }
 }
 
+// Once the cond is matched, only the cmp log still needs the runtime
+#[inline(always)]
+fn unset_cmpid() {
+    if cmp_log::is_on() {
+        set_cmpid(defs::CMP_LOG_CMPID);
+    } else {
+        set_cmpid(0);
+    }
+}
+
 pub struct ShmConds {
     cond: shm::SHM<CondStmtBase>,
     rt_order: u32,
@@ -61,7 +71,7 @@ impl ShmConds {
         self.cond.arg2 = arg2;
         self.rt_order = 0x8000;
         self.mark_reachable(condition);
-        set_cmpid(0);
+        unset_cmpid();
         condition
     }
 
@@ -69,13 +79,17 @@ impl ShmConds {
         self.cond.arg1 = condition;
         self.rt_order = 0x8000;
         self.mark_reachable((condition == self.cond.arg2) as u32);
-        set_cmpid(0);
+        unset_cmpid();
         condition
     }
 
     pub fn reset(&mut self) {
         self.rt_order = 0;
-        set_cmpid(self.cond.cmpid);
+        if cmp_log::is_on() {
+            set_cmpid(defs::CMP_LOG_CMPID);
+        } else {
+            set_cmpid(self.cond.cmpid);
+        }
     }
 }
 
@@ -85,6 +99,7 @@ lazy_static! {
 
 #[inline(always)]
 pub fn reset_shm_conds() {
+    cmp_log::reset_cmp_log();
     let mut conds = SHM_CONDS.lock().expect("SHM mutex poisoned.");
     match conds.deref_mut() {
         &mut Some(ref mut c) => {